@@ -0,0 +1,503 @@
+//! Account-owned API keys for headless clients that can't sign SEP-10
+//! challenges.
+//!
+//! A key looks like `ssk_<id>_<secret>` and is sent in the `X-API-Key`
+//! header. Only the SHA-256 of the secret is stored; the full key is returned
+//! once, when it is created.
+
+use crate::auth::{AuthContext, AuthState, Role};
+use crate::errors::AppError;
+use axum::{extract::Path as UrlPath, http::StatusCode, Extension, Json};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+
+/// Request header carrying an API key.
+pub const API_KEY_HEADER: &str = "x-api-key";
+const KEY_PREFIX: &str = "ssk";
+const MAX_KEYS_PER_ACCOUNT: usize = 20;
+const MAX_NAME_LEN: usize = 64;
+
+/// What a credential may be used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// `/analyze` and `/compare`
+    Analyze,
+    /// Provider pool, signing key and API key management
+    Admin,
+    /// Cache eviction
+    Cache,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 3] = [Scope::Analyze, Scope::Admin, Scope::Cache];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Scope::Analyze => "analyze",
+            Scope::Admin => "admin",
+            Scope::Cache => "cache",
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateApiKeyRequest {
+    /// Label to tell keys apart, e.g. the pipeline using it
+    #[schema(example = "github-actions")]
+    pub name: String,
+    /// Defaults to `["analyze"]`
+    pub scopes: Option<Vec<Scope>>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ApiKeyInfo {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    /// Unix time the key was created
+    pub created_at: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CreateApiKeyResponse {
+    /// The key for the `X-API-Key` header. It is not shown again.
+    pub key: String,
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredKey {
+    id: String,
+    /// G… account that created the key and is authenticated by it.
+    owner: String,
+    name: String,
+    scopes: Vec<Scope>,
+    /// Hex SHA-256 of the secret part of the key.
+    secret_hash: String,
+    created_at: u64,
+}
+
+impl StoredKey {
+    fn info(&self) -> ApiKeyInfo {
+        ApiKeyInfo {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            scopes: self.scopes.clone(),
+            created_at: self.created_at,
+        }
+    }
+}
+
+/// API keys by id, optionally saved to a JSON file on every change.
+#[derive(Default)]
+pub struct ApiKeyStore {
+    keys: RwLock<HashMap<String, StoredKey>>,
+    file: Option<PathBuf>,
+}
+
+impl ApiKeyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load keys saved at `path` and keep saving changes there. A missing
+    /// file starts an empty store.
+    pub fn open(path: PathBuf) -> Result<Self, String> {
+        let keys = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str::<Vec<StoredKey>>(&contents)
+                .map_err(|e| format!("{}: {e}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(format!("{}: {e}", path.display())),
+        };
+        Ok(Self {
+            keys: RwLock::new(keys.into_iter().map(|k| (k.id.clone(), k)).collect()),
+            file: Some(path),
+        })
+    }
+
+    /// Create a key for `owner`. The returned response holds the only copy
+    /// of the full key.
+    pub fn create(
+        &self,
+        owner: &str,
+        name: &str,
+        scopes: Vec<Scope>,
+    ) -> Result<CreateApiKeyResponse, AppError> {
+        let name = name.trim();
+        if name.is_empty() || name.len() > MAX_NAME_LEN {
+            return Err(AppError::BadRequest(format!(
+                "Key name must be 1-{MAX_NAME_LEN} characters"
+            )));
+        }
+        let mut scopes = scopes;
+        scopes.sort_by_key(|s| *s as u8);
+        scopes.dedup();
+        if scopes.is_empty() {
+            return Err(AppError::BadRequest(
+                "At least one scope is required".into(),
+            ));
+        }
+
+        let mut id = [0u8; 8];
+        rand::thread_rng().fill_bytes(&mut id);
+        let id = hex::encode(id);
+        let mut secret = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut secret);
+        let secret = hex::encode(secret);
+
+        let stored = StoredKey {
+            id: id.clone(),
+            owner: owner.to_string(),
+            name: name.to_string(),
+            scopes,
+            secret_hash: hash_secret(&secret),
+            created_at: now_secs(),
+        };
+
+        let mut keys = self.write();
+        if keys.values().filter(|k| k.owner == owner).count() >= MAX_KEYS_PER_ACCOUNT {
+            return Err(AppError::BadRequest(format!(
+                "An account may hold at most {MAX_KEYS_PER_ACCOUNT} API keys"
+            )));
+        }
+        keys.insert(id.clone(), stored.clone());
+        if let Err(e) = self.save(&keys) {
+            keys.remove(&id);
+            return Err(e);
+        }
+        tracing::info!(owner, key_id = %id, "API key created");
+
+        Ok(CreateApiKeyResponse {
+            key: format!("{KEY_PREFIX}_{id}_{secret}"),
+            id,
+            name: stored.name,
+            scopes: stored.scopes,
+            created_at: stored.created_at,
+        })
+    }
+
+    /// Keys owned by `owner`, oldest first.
+    pub fn list(&self, owner: &str) -> Vec<ApiKeyInfo> {
+        let mut keys: Vec<ApiKeyInfo> = self
+            .read()
+            .values()
+            .filter(|k| k.owner == owner)
+            .map(StoredKey::info)
+            .collect();
+        keys.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+        keys
+    }
+
+    /// Delete `owner`'s key `id`. Keys of other accounts are reported as not
+    /// found.
+    pub fn revoke(&self, owner: &str, id: &str) -> Result<(), AppError> {
+        let mut keys = self.write();
+        match keys.get(id) {
+            Some(key) if key.owner == owner => {}
+            _ => return Err(AppError::NotFound(format!("API key '{id}'"))),
+        }
+        let removed = keys.remove(id).expect("checked above");
+        if let Err(e) = self.save(&keys) {
+            keys.insert(id.to_string(), removed);
+            return Err(e);
+        }
+        tracing::info!(owner, key_id = %id, "API key revoked");
+        Ok(())
+    }
+
+    /// Resolve a presented key to its caller.
+    pub fn authenticate(&self, key: &str) -> Result<AuthContext, AppError> {
+        let invalid = || AppError::Unauthorized("Invalid API key".into());
+        let rest = key
+            .strip_prefix(KEY_PREFIX)
+            .and_then(|k| k.strip_prefix('_'))
+            .ok_or_else(invalid)?;
+        let (id, secret) = rest.split_once('_').ok_or_else(invalid)?;
+
+        let keys = self.read();
+        let stored = keys.get(id).ok_or_else(invalid)?;
+        if hash_secret(secret) != stored.secret_hash {
+            return Err(invalid());
+        }
+        Ok(AuthContext {
+            subject: stored.owner.clone(),
+            session_id: None,
+            api_key_id: Some(stored.id.clone()),
+            scopes: stored.scopes.clone(),
+            role: Role::User,
+        })
+    }
+
+    fn save(&self, keys: &HashMap<String, StoredKey>) -> Result<(), AppError> {
+        let Some(path) = &self.file else {
+            return Ok(());
+        };
+        let mut list: Vec<&StoredKey> = keys.values().collect();
+        list.sort_by(|a, b| a.id.cmp(&b.id));
+        save_keys(path, &list).map_err(|e| {
+            tracing::error!(error = %e, path = %path.display(), "Failed to save API keys");
+            AppError::Internal(format!("Failed to save API keys: {e}"))
+        })
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, HashMap<String, StoredKey>> {
+        self.keys.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, HashMap<String, StoredKey>> {
+        self.keys.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn hash_secret(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Write the key list to `path`, readable only by the owner where
+/// supported, replacing it atomically.
+fn save_keys(path: &Path, keys: &[&StoredKey]) -> std::io::Result<()> {
+    let json = serde_json::to_string_pretty(keys)?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600))?;
+    }
+    std::fs::rename(&tmp, path)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/api-keys",
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 201, description = "API key created; the key is only shown here", body = CreateApiKeyResponse),
+        (status = 400, description = "Invalid name or scopes, or too many keys"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Requested a scope the caller does not have")
+    ),
+    security(
+        ("jwt" = []),
+        ("api_key" = [])
+    ),
+    tag = "Auth"
+)]
+pub async fn create_api_key_handler(
+    Extension(state): Extension<Arc<AuthState>>,
+    Extension(caller): Extension<AuthContext>,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> Result<(StatusCode, Json<CreateApiKeyResponse>), AppError> {
+    let scopes = payload.scopes.unwrap_or_else(|| vec![Scope::Analyze]);
+    // A key can't grant more than the credential that created it.
+    if let Some(scope) = scopes.iter().find(|s| !caller.has_scope(**s)) {
+        return Err(AppError::Forbidden(format!(
+            "Cannot grant scope '{}'",
+            scope.as_str()
+        )));
+    }
+    let created = state
+        .api_keys()
+        .create(&caller.subject, &payload.name, scopes)?;
+    if let Some(parent) = &caller.api_key_id {
+        tracing::info!(key_id = %created.id, %parent, "API key created with another API key");
+    }
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/api-keys",
+    responses(
+        (status = 200, description = "The caller's API keys, without secrets", body = Vec<ApiKeyInfo>),
+        (status = 401, description = "Missing or invalid credentials")
+    ),
+    security(
+        ("jwt" = []),
+        ("api_key" = [])
+    ),
+    tag = "Auth"
+)]
+pub async fn list_api_keys_handler(
+    Extension(state): Extension<Arc<AuthState>>,
+    Extension(caller): Extension<AuthContext>,
+) -> Json<Vec<ApiKeyInfo>> {
+    Json(state.api_keys().list(&caller.subject))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/auth/api-keys/{id}",
+    params(
+        ("id" = String, Path, description = "API key id")
+    ),
+    responses(
+        (status = 204, description = "API key revoked"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 404, description = "No such key for this account")
+    ),
+    security(
+        ("jwt" = []),
+        ("api_key" = [])
+    ),
+    tag = "Auth"
+)]
+pub async fn revoke_api_key_handler(
+    Extension(state): Extension<Arc<AuthState>>,
+    Extension(caller): Extension<AuthContext>,
+    UrlPath(id): UrlPath<String>,
+) -> Result<StatusCode, AppError> {
+    state.api_keys().revoke(&caller.subject, &id)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OWNER: &str = "GAAZI4TCR3TY5OJHCTJC2A4QSY6CJWJH5IAJTGKIN2ER7LBNVKOCCWN7";
+    const OTHER: &str = "GBRPYHIL2CI3FNQ4BXLFMNDLFJUNPU2HY3ZMFSHONUCEOASW7QC7OX2H";
+
+    #[test]
+    fn test_created_key_authenticates_with_its_scopes() {
+        let store = ApiKeyStore::new();
+        let created = store
+            .create(
+                OWNER,
+                "ci",
+                vec![Scope::Cache, Scope::Analyze, Scope::Cache],
+            )
+            .unwrap();
+        assert!(created.key.starts_with(&format!("ssk_{}_", created.id)));
+        assert_eq!(created.scopes, vec![Scope::Analyze, Scope::Cache]);
+
+        let caller = store.authenticate(&created.key).unwrap();
+        assert_eq!(caller.subject, OWNER);
+        assert_eq!(caller.api_key_id.as_deref(), Some(created.id.as_str()));
+        assert!(caller.session_id.is_none());
+        assert!(caller.has_scope(Scope::Analyze));
+        assert!(!caller.has_scope(Scope::Admin));
+    }
+
+    #[test]
+    fn test_secret_is_not_stored() {
+        let store = ApiKeyStore::new();
+        let created = store.create(OWNER, "ci", vec![Scope::Analyze]).unwrap();
+        let secret = created.key.rsplit('_').next().unwrap();
+
+        let keys = store.read();
+        let stored = &keys[&created.id];
+        assert_ne!(stored.secret_hash, secret);
+        assert_eq!(stored.secret_hash, hash_secret(secret));
+    }
+
+    #[test]
+    fn test_rejects_wrong_or_malformed_keys() {
+        let store = ApiKeyStore::new();
+        let created = store.create(OWNER, "ci", vec![Scope::Analyze]).unwrap();
+        let wrong_secret = format!("ssk_{}_AAAA", created.id);
+
+        for key in [
+            "",
+            "garbage",
+            "ssk_",
+            "ssk_deadbeef",
+            wrong_secret.as_str(),
+            &created.key.replacen("ssk", "xyz", 1),
+        ] {
+            assert!(
+                matches!(store.authenticate(key), Err(AppError::Unauthorized(_))),
+                "accepted {key:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_validates_name_and_scopes() {
+        let store = ApiKeyStore::new();
+        assert!(store.create(OWNER, "  ", vec![Scope::Analyze]).is_err());
+        assert!(store
+            .create(OWNER, &"x".repeat(65), vec![Scope::Analyze])
+            .is_err());
+        assert!(store.create(OWNER, "ci", Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_limits_keys_per_account() {
+        let store = ApiKeyStore::new();
+        for i in 0..MAX_KEYS_PER_ACCOUNT {
+            store
+                .create(OWNER, &format!("key-{i}"), vec![Scope::Analyze])
+                .unwrap();
+        }
+        assert!(store
+            .create(OWNER, "one-more", vec![Scope::Analyze])
+            .is_err());
+        assert!(store.create(OTHER, "ci", vec![Scope::Analyze]).is_ok());
+    }
+
+    #[test]
+    fn test_list_and_revoke_are_per_account() {
+        let store = ApiKeyStore::new();
+        let mine = store.create(OWNER, "mine", vec![Scope::Analyze]).unwrap();
+        let theirs = store.create(OTHER, "theirs", vec![Scope::Admin]).unwrap();
+
+        let listed = store.list(OWNER);
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, mine.id);
+        assert_eq!(listed[0].name, "mine");
+
+        assert!(matches!(
+            store.revoke(OWNER, &theirs.id),
+            Err(AppError::NotFound(_))
+        ));
+        assert!(store.authenticate(&theirs.key).is_ok());
+
+        store.revoke(OWNER, &mine.id).unwrap();
+        assert!(store.authenticate(&mine.key).is_err());
+        assert!(store.list(OWNER).is_empty());
+    }
+
+    #[test]
+    fn test_keys_survive_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keys.json");
+
+        let store = ApiKeyStore::open(path.clone()).unwrap();
+        let kept = store.create(OWNER, "kept", vec![Scope::Analyze]).unwrap();
+        let revoked = store.create(OWNER, "revoked", vec![Scope::Cache]).unwrap();
+        store.revoke(OWNER, &revoked.id).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains(kept.key.rsplit('_').next().unwrap()));
+
+        let reopened = ApiKeyStore::open(path).unwrap();
+        assert_eq!(reopened.authenticate(&kept.key).unwrap().subject, OWNER);
+        assert!(reopened.authenticate(&revoked.key).is_err());
+    }
+
+    #[test]
+    fn test_open_rejects_corrupt_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keys.json");
+        std::fs::write(&path, "not json").unwrap();
+        assert!(ApiKeyStore::open(path).is_err());
+    }
+}
@@ -1,30 +1,141 @@
+use crate::api_keys::{ApiKeyStore, Scope, API_KEY_HEADER};
 use crate::errors::AppError;
-use axum::{extract::Request, http::header, middleware::Next, response::Response, Extension, Json};
-use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use crate::soroban_rpc::SorobanRpc;
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use base64::{
+    engine::general_purpose::{STANDARD as BASE64, URL_SAFE_NO_PAD},
+    Engine,
+};
 use ed25519_dalek::{Signature as Ed25519Signature, Signer, SigningKey, Verifier, VerifyingKey};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use moka::future::Cache;
 use rand::RngCore;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use soroban_sdk::xdr::{
-    DecoratedSignature, Limits, ManageDataOp, Memo, MuxedAccount, Operation, OperationBody,
-    Preconditions, ReadXdr, SequenceNumber, SignatureHint, TimeBounds, TimePoint, Transaction,
+    AccountEntry, AccountId, DecoratedSignature, LedgerEntryData, LedgerKey, LedgerKeyAccount,
+    Limits, ManageDataOp, Memo, MuxedAccount, Operation, OperationBody, Preconditions, PublicKey,
+    ReadXdr, SequenceNumber, SignatureHint, SignerKey, TimeBounds, TimePoint, Transaction,
     TransactionEnvelope, TransactionExt, TransactionV1Envelope, Uint256, WriteXdr,
 };
-use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use stellar_strkey::Strkey;
 use utoipa::ToSchema;
 
 const CHALLENGE_EXPIRY_SECS: u64 = 300;
-const JWT_EXPIRY_SECS: u64 = 86400;
+/// Default lifetime of an access token.
+pub const DEFAULT_ACCESS_TOKEN_TTL: Duration = Duration::from_secs(15 * 60); // 15 minutes
+/// Default time a refresh token stays usable since it was issued.
+pub const DEFAULT_REFRESH_TOKEN_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60); // 7 days
+/// Superseded refresh secrets remembered per session to detect reuse.
+const MAX_RETIRED_SECRETS: usize = 16;
+/// Upper bound on live sessions.
+const MAX_SESSIONS: u64 = 100_000;
 const WEB_AUTH_DOMAIN: &str = "soroscope";
+/// Key of the ManageData operation naming the server that issued a challenge.
+const WEB_AUTH_DOMAIN_KEY: &str = "web_auth_domain";
+/// Random bytes in a challenge nonce; base64-encoded they fill the 64-byte
+/// ManageData value.
+const NONCE_BYTES: usize = 48;
+/// Name of the network passed to [`AuthState::new`] until
+/// [`AuthState::with_network_name`] renames it.
+const DEFAULT_NETWORK_NAME: &str = "default";
+/// Upper bound on challenges awaiting verification.
+const MAX_PENDING_CHALLENGES: u64 = 100_000;
+
+/// Default time a rotated-out server key keeps verifying the challenges it
+/// signed; long enough for all of them to expire.
+pub const DEFAULT_ROTATION_OVERLAP: Duration = Duration::from_secs(CHALLENGE_EXPIRY_SECS);
+
+/// Current SEP-10 signing key and the keys it replaced.
+struct ServerKeys {
+    current: SigningKey,
+    /// Rotated-out public keys, with the unix time until which challenges
+    /// they signed are still accepted.
+    previous: Vec<([u8; 32], u64)>,
+}
+
+/// A network challenges can be issued for.
+struct AuthNetwork {
+    name: String,
+    passphrase: String,
+    /// Used to load the signers of existing accounts. Without it every
+    /// account is treated as master-key-only.
+    rpc: Option<SorobanRpc>,
+}
+
+impl AuthNetwork {
+    /// Signers allowed to authenticate as `account`, and the weight they
+    /// must reach.
+    ///
+    /// Per SEP-10, an account that does not exist on the network can only
+    /// sign in with its master key.
+    async fn load_signers(&self, account: [u8; 32]) -> Result<AccountSigners, AppError> {
+        let Some(rpc) = &self.rpc else {
+            return Ok(AccountSigners::master_only(account));
+        };
+
+        let key = LedgerKey::Account(LedgerKeyAccount {
+            account_id: AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(account))),
+        });
+        let key_xdr = key
+            .to_xdr(Limits::none())
+            .map_err(|e| AppError::Internal(format!("XDR encode error: {e}")))?;
+        let response = rpc
+            .get_ledger_entries(&[BASE64.encode(key_xdr)])
+            .await
+            .map_err(|e| AppError::Internal(format!("Could not load account signers: {e}")))?;
+
+        let Some(entry) = response.entries.first() else {
+            return Ok(AccountSigners::master_only(account));
+        };
+        let data = BASE64
+            .decode(&entry.xdr)
+            .ok()
+            .and_then(|raw| LedgerEntryData::from_xdr(raw, Limits::none()).ok());
+        match data {
+            Some(LedgerEntryData::Account(entry)) => Ok(AccountSigners::from_entry(&entry)),
+            _ => Err(AppError::Internal(
+                "RPC returned an invalid account entry".into(),
+            )),
+        }
+    }
+}
 
 pub struct AuthState {
     pub jwt_secret: String,
-    pub signing_key: SigningKey,
-    pub server_public_key: [u8; 32],
-    pub network_passphrase: String,
+    /// Networks clients may request challenges for; the first is the
+    /// default.
+    networks: Vec<AuthNetwork>,
+    keys: RwLock<ServerKeys>,
+    /// How long a rotated-out key stays valid for verification.
+    rotation_overlap: Duration,
+    /// Where a rotated seed is saved so it survives restarts.
+    seed_file: Option<PathBuf>,
+    /// Nonces of issued challenges that have not been verified yet, mapped to
+    /// the client account each was issued for. Entries expire with the
+    /// challenge and are removed on first use.
+    challenges: Cache<String, [u8; 32]>,
+    /// Advertised as `WEB_AUTH_ENDPOINT` in `stellar.toml`.
+    web_auth_endpoint: String,
+    access_ttl: Duration,
+    /// Sessions with a usable refresh token, by session id. Entries expire
+    /// when the refresh token does.
+    sessions: Cache<String, RefreshSession>,
+    /// Logged-out sessions whose access tokens may not have expired yet.
+    revoked_sessions: Cache<String, ()>,
+    api_keys: ApiKeyStore,
+    /// Accounts granted [`Role::Admin`].
+    admins: HashSet<String>,
 }
 
 impl AuthState {
@@ -33,26 +144,180 @@ impl AuthState {
         sep10_seed: Option<[u8; 32]>,
         network_passphrase: String,
     ) -> Self {
-        let signing_key = match sep10_seed {
-            Some(seed) => SigningKey::from_bytes(&seed),
-            None => {
-                let mut seed = [0u8; 32];
-                rand::thread_rng().fill_bytes(&mut seed);
-                SigningKey::from_bytes(&seed)
-            }
-        };
-        let server_public_key = signing_key.verifying_key().to_bytes();
+        let signing_key = SigningKey::from_bytes(&sep10_seed.unwrap_or_else(random_seed));
         Self {
             jwt_secret,
-            signing_key,
-            server_public_key,
-            network_passphrase,
+            networks: vec![AuthNetwork {
+                name: DEFAULT_NETWORK_NAME.to_string(),
+                passphrase: network_passphrase,
+                rpc: None,
+            }],
+            keys: RwLock::new(ServerKeys {
+                current: signing_key,
+                previous: Vec::new(),
+            }),
+            rotation_overlap: DEFAULT_ROTATION_OVERLAP,
+            seed_file: None,
+            challenges: Cache::builder()
+                .max_capacity(MAX_PENDING_CHALLENGES)
+                .time_to_live(Duration::from_secs(CHALLENGE_EXPIRY_SECS))
+                .build(),
+            web_auth_endpoint: String::new(),
+            access_ttl: DEFAULT_ACCESS_TOKEN_TTL,
+            sessions: session_cache(DEFAULT_REFRESH_TOKEN_TTL),
+            revoked_sessions: session_cache(DEFAULT_ACCESS_TOKEN_TTL),
+            api_keys: ApiKeyStore::new(),
+            admins: HashSet::new(),
         }
     }
 
+    /// Lifetimes of access and refresh tokens.
+    pub fn with_token_ttls(mut self, access: Duration, refresh: Duration) -> Self {
+        self.access_ttl = access;
+        self.sessions = session_cache(refresh);
+        // A revoked session only needs remembering until its last access
+        // token expires.
+        self.revoked_sessions = session_cache(access);
+        self
+    }
+
+    /// Look up account signers and thresholds over `rpc` during verification.
+    pub fn with_rpc(mut self, rpc: SorobanRpc) -> Self {
+        self.networks[0].rpc = Some(rpc);
+        self
+    }
+
+    /// Name clients use to select the network passed to [`AuthState::new`].
+    pub fn with_network_name(mut self, name: &str) -> Self {
+        self.networks[0].name = name.to_string();
+        self
+    }
+
+    /// Also issue challenges for `name`, signed over `passphrase`.
+    pub fn with_network(mut self, name: &str, passphrase: &str, rpc: Option<SorobanRpc>) -> Self {
+        self.networks.push(AuthNetwork {
+            name: name.to_string(),
+            passphrase: passphrase.to_string(),
+            rpc,
+        });
+        self
+    }
+
+    /// Passphrase of the default network.
+    pub fn network_passphrase(&self) -> &str {
+        &self.networks[0].passphrase
+    }
+
+    /// The network named `name`, or the default one.
+    fn network(&self, name: Option<&str>) -> Result<&AuthNetwork, AppError> {
+        let Some(name) = name else {
+            return Ok(&self.networks[0]);
+        };
+        self.networks
+            .iter()
+            .find(|network| network.name == name)
+            .ok_or_else(|| {
+                let known: Vec<&str> = self.networks.iter().map(|n| n.name.as_str()).collect();
+                AppError::BadRequest(format!(
+                    "Unknown network '{name}'; expected one of: {}",
+                    known.join(", ")
+                ))
+            })
+    }
+
+    /// Keep verifying challenges signed by a rotated-out key for `overlap`.
+    pub fn with_rotation_overlap(mut self, overlap: Duration) -> Self {
+        self.rotation_overlap = overlap;
+        self
+    }
+
+    /// Save rotated seeds to `path`.
+    pub fn with_seed_file(mut self, path: PathBuf) -> Self {
+        self.seed_file = Some(path);
+        self
+    }
+
+    /// Accept and manage API keys from `store`.
+    pub fn with_api_keys(mut self, store: ApiKeyStore) -> Self {
+        self.api_keys = store;
+        self
+    }
+
+    pub fn with_web_auth_endpoint(mut self, url: impl Into<String>) -> Self {
+        self.web_auth_endpoint = url.into();
+        self
+    }
+
+    /// Grant [`Role::Admin`] to `accounts` (G… addresses).
+    pub fn with_admins(mut self, accounts: impl IntoIterator<Item = String>) -> Self {
+        self.admins = accounts.into_iter().collect();
+        self
+    }
+
+    pub fn role_of(&self, account: &str) -> Role {
+        if self.admins.contains(account) {
+            Role::Admin
+        } else {
+            Role::User
+        }
+    }
+
+    pub fn api_keys(&self) -> &ApiKeyStore {
+        &self.api_keys
+    }
+
+    pub fn signing_key(&self) -> SigningKey {
+        self.keys().current.clone()
+    }
+
+    pub fn server_public_key(&self) -> [u8; 32] {
+        self.keys().current.verifying_key().to_bytes()
+    }
+
+    /// Replace the signing key with one from `seed`. The old key keeps
+    /// verifying its outstanding challenges for the rotation overlap.
+    /// Returns the old public key and the unix time it stops being accepted.
+    pub fn rotate(&self, seed: [u8; 32]) -> Result<([u8; 32], u64), AppError> {
+        if let Some(path) = &self.seed_file {
+            save_seed(path, &seed)
+                .map_err(|e| AppError::Internal(format!("Could not save signing seed: {e}")))?;
+        } else {
+            tracing::warn!("No SEP-10 seed file configured; the rotated key is lost on restart");
+        }
+
+        let until = now_secs() + self.rotation_overlap.as_secs();
+        let mut keys = self.keys.write().unwrap_or_else(|e| e.into_inner());
+        let old = keys.current.verifying_key().to_bytes();
+        keys.current = SigningKey::from_bytes(&seed);
+        let now = now_secs();
+        keys.previous.retain(|(_, until)| *until >= now);
+        keys.previous.push((old, until));
+        tracing::info!(
+            signing_key = %stellar_address(&keys.current.verifying_key().to_bytes()),
+            previous_key = %stellar_address(&old),
+            overlap_secs = self.rotation_overlap.as_secs(),
+            "Rotated SEP-10 signing key"
+        );
+        Ok((old, until))
+    }
+
+    /// Whether `key` may have signed a challenge accepted right now: the
+    /// current server key, or a previous one inside its overlap window.
+    fn is_server_key(&self, key: &[u8; 32]) -> bool {
+        let keys = self.keys();
+        keys.current.verifying_key().to_bytes() == *key
+            || keys
+                .previous
+                .iter()
+                .any(|(previous, until)| previous == key && now_secs() <= *until)
+    }
+
+    fn keys(&self) -> std::sync::RwLockReadGuard<'_, ServerKeys> {
+        self.keys.read().unwrap_or_else(|e| e.into_inner())
+    }
+
     pub fn server_stellar_address(&self) -> String {
-        Strkey::PublicKeyEd25519(stellar_strkey::ed25519::PublicKey(self.server_public_key))
-            .to_string()
+        stellar_address(&self.server_public_key())
     }
 }
 
@@ -60,6 +325,10 @@ impl AuthState {
 pub struct ChallengeRequest {
     #[schema(example = "GABC...XYZ")]
     pub account: String,
+    /// Network to sign in on; defaults to the server's default network
+    #[serde(default)]
+    #[schema(example = "testnet")]
+    pub network: Option<String>,
 }
 
 #[derive(Serialize, ToSchema)]
@@ -73,9 +342,74 @@ pub struct VerifyRequest {
     pub transaction: String,
 }
 
-#[derive(Serialize, ToSchema)]
-pub struct VerifyResponse {
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TokenResponse {
+    /// Access token (JWT) for the `Authorization: Bearer` header
     pub token: String,
+    /// Single-use token for `POST /auth/refresh`
+    pub refresh_token: String,
+    /// Seconds until `token` expires
+    pub expires_in: u64,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+/// Access level of an account. Admins are configured with
+/// [`AuthState::with_admins`]; everyone else is a user.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    User,
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+/// Identity of an authenticated caller, added to request extensions by
+/// [`auth_middleware`].
+#[derive(Debug, Clone)]
+pub struct AuthContext {
+    /// G… account the credential belongs to
+    pub subject: String,
+    /// Session of a JWT caller
+    pub session_id: Option<String>,
+    /// Id of the API key used, if any
+    pub api_key_id: Option<String>,
+    /// What the credential may do; JWTs carry every scope
+    pub scopes: Vec<Scope>,
+    pub role: Role,
+}
+
+impl AuthContext {
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        self.scopes.contains(&scope)
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RotateKeyRequest {
+    /// New `S…` secret seed; a random one is generated when absent
+    pub seed: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RotateKeyResponse {
+    /// The new `SIGNING_KEY`
+    pub signing_key: String,
+    pub previous_key: String,
+    /// Unix time until which challenges signed by the previous key are accepted
+    pub previous_valid_until: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -84,6 +418,112 @@ struct Claims {
     iss: String,
     exp: u64,
     iat: u64,
+    /// Session the token belongs to; logging out revokes it.
+    sid: String,
+    /// Role at the time the token was issued. Requests are authorized with
+    /// the account's current role instead, so demotions apply immediately.
+    #[serde(default)]
+    role: Role,
+}
+
+/// Server side of a refresh token.
+#[derive(Debug, Clone)]
+struct RefreshSession {
+    subject: String,
+    /// SHA-256 of the current refresh secret.
+    secret_hash: [u8; 32],
+    /// Hashes of secrets already exchanged; presenting one again means the
+    /// token leaked.
+    retired: Vec<[u8; 32]>,
+}
+
+/// A challenge that is well-formed and signed by this server. Client
+/// signatures are checked against the account's signers afterwards, and the
+/// nonce is consumed last.
+#[derive(Debug)]
+struct VerifiedChallenge {
+    client_key: [u8; 32],
+    nonce: String,
+    /// Index into [`AuthState::networks`] of the network it was issued for.
+    network: usize,
+    hash: [u8; 32],
+    /// Signatures other than the server's.
+    signatures: Vec<DecoratedSignature>,
+}
+
+/// Ed25519 signers of an account with their weights.
+#[derive(Debug, Clone, PartialEq)]
+struct AccountSigners {
+    signers: Vec<([u8; 32], u32)>,
+    /// Weight the valid signatures must add up to.
+    threshold: u32,
+}
+
+impl AccountSigners {
+    fn master_only(account: [u8; 32]) -> Self {
+        Self {
+            signers: vec![(account, 1)],
+            threshold: 1,
+        }
+    }
+
+    /// Master key and Ed25519 signers of `entry`, held to the medium
+    /// threshold. Other signer types can't sign a challenge and are skipped.
+    fn from_entry(entry: &AccountEntry) -> Self {
+        let AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(master))) = entry.account_id;
+        let [master_weight, _low, medium, _high] = entry.thresholds.0;
+
+        let mut signers = vec![(master, u32::from(master_weight))];
+        for signer in entry.signers.iter() {
+            if let SignerKey::Ed25519(Uint256(key)) = signer.key {
+                signers.push((key, signer.weight));
+            }
+        }
+        signers.retain(|(_, weight)| *weight > 0);
+
+        Self {
+            signers,
+            // A zero threshold still needs one valid signature.
+            threshold: u32::from(medium).max(1),
+        }
+    }
+
+    /// Total weight of the distinct signers that signed `hash`. Fails if
+    /// any signature does not belong to a signer.
+    fn signed_weight(
+        &self,
+        hash: &[u8; 32],
+        signatures: &[DecoratedSignature],
+    ) -> Result<u32, AppError> {
+        let mut signed = vec![false; self.signers.len()];
+        for ds in signatures {
+            let matched = Ed25519Signature::from_slice(ds.signature.as_ref())
+                .ok()
+                .and_then(|sig| {
+                    self.signers.iter().position(|(key, _)| {
+                        key[28..32] == ds.hint.0
+                            && VerifyingKey::from_bytes(key)
+                                .is_ok_and(|vk| vk.verify(hash, &sig).is_ok())
+                    })
+                });
+            match matched {
+                Some(index) => signed[index] = true,
+                None => {
+                    return Err(AppError::Unauthorized(
+                        "Challenge has a signature from an unknown signer".into(),
+                    ))
+                }
+            }
+        }
+
+        Ok(self
+            .signers
+            .iter()
+            .zip(signed)
+            .filter(|(_, signed)| *signed)
+            .map(|((_, weight), _)| *weight)
+            .fold(0u32, u32::saturating_add))
+    }
 }
 
 fn now_secs() -> u64 {
@@ -93,6 +533,64 @@ fn now_secs() -> u64 {
         .as_secs()
 }
 
+/// Decode an `S…` secret seed.
+pub fn parse_seed(seed: &str) -> Result<[u8; 32], String> {
+    match Strkey::from_string(seed.trim()) {
+        Ok(Strkey::PrivateKeyEd25519(key)) => Ok(key.0),
+        Ok(_) => Err("expected an S... secret seed".to_string()),
+        Err(e) => Err(format!("invalid secret seed: {e}")),
+    }
+}
+
+/// The server seed from `seed` (an `S…` strkey), else from the file at
+/// `path`, which is created with a fresh seed if missing. `None` when both
+/// are empty.
+pub fn load_seed(seed: &str, path: &str) -> Result<Option<[u8; 32]>, String> {
+    if !seed.is_empty() {
+        return parse_seed(seed).map(Some);
+    }
+    if path.is_empty() {
+        return Ok(None);
+    }
+    let path = Path::new(path);
+    match std::fs::read_to_string(path) {
+        Ok(contents) => parse_seed(&contents).map(Some),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let seed = random_seed();
+            save_seed(path, &seed)
+                .map_err(|e| format!("could not create {}: {e}", path.display()))?;
+            tracing::info!(path = %path.display(), "Generated a new SEP-10 signing seed");
+            Ok(Some(seed))
+        }
+        Err(e) => Err(format!("could not read {}: {e}", path.display())),
+    }
+}
+
+/// Write `seed` as an `S…` strkey, readable only by the owner where
+/// supported. Written to a temporary file first so a crash can't leave a
+/// truncated seed behind.
+fn save_seed(path: &Path, seed: &[u8; 32]) -> std::io::Result<()> {
+    let strkey = Strkey::PrivateKeyEd25519(stellar_strkey::ed25519::PrivateKey(*seed)).to_string();
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, format!("{strkey}\n"))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600))?;
+    }
+    std::fs::rename(&tmp, path)
+}
+
+fn random_seed() -> [u8; 32] {
+    let mut seed = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut seed);
+    seed
+}
+
+fn stellar_address(key: &[u8; 32]) -> String {
+    Strkey::PublicKeyEd25519(stellar_strkey::ed25519::PublicKey(*key)).to_string()
+}
+
 fn network_id(passphrase: &str) -> [u8; 32] {
     Sha256::digest(passphrase.as_bytes()).into()
 }
@@ -108,67 +606,90 @@ fn tx_hash(tx: &Transaction, net_id: &[u8; 32]) -> Result<[u8; 32], AppError> {
     Ok(h.finalize().into())
 }
 
-fn build_challenge_envelope(
+fn sign_transaction(
+    key: &SigningKey,
+    tx: &Transaction,
+    net_id: &[u8; 32],
+) -> Result<DecoratedSignature, AppError> {
+    let hash = tx_hash(tx, net_id)?;
+    let sig = key.sign(&hash);
+    let public_key = key.verifying_key().to_bytes();
+    let hint: [u8; 4] = public_key[28..32].try_into().unwrap();
+    Ok(DecoratedSignature {
+        hint: SignatureHint(hint),
+        signature: sig
+            .to_bytes()
+            .to_vec()
+            .try_into()
+            .map_err(|_| AppError::Internal("signature conversion failed".into()))?,
+    })
+}
+
+fn manage_data_op(source: [u8; 32], name: &str, value: &str) -> Result<Operation, AppError> {
+    Ok(Operation {
+        source_account: Some(MuxedAccount::Ed25519(Uint256(source))),
+        body: OperationBody::ManageData(ManageDataOp {
+            data_name: name
+                .as_bytes()
+                .to_vec()
+                .try_into()
+                .map_err(|_| AppError::Internal("data name conversion failed".into()))?,
+            data_value: Some(
+                value
+                    .as_bytes()
+                    .to_vec()
+                    .try_into()
+                    .map_err(|_| AppError::Internal("data value conversion failed".into()))?,
+            ),
+        }),
+    })
+}
+
+/// Build a server-signed challenge for `client_pubkey` and remember its
+/// nonce until it is verified or expires.
+async fn build_challenge_envelope(
     state: &AuthState,
+    network: &AuthNetwork,
     client_pubkey: &[u8; 32],
 ) -> Result<String, AppError> {
     let now = now_secs();
+    let signing_key = state.signing_key();
+    let server_key = signing_key.verifying_key().to_bytes();
 
-    let mut nonce = [0u8; 48];
+    let mut nonce = [0u8; NONCE_BYTES];
     rand::thread_rng().fill_bytes(&mut nonce);
     let nonce_value = BASE64.encode(nonce);
 
-    let data_name = format!("{WEB_AUTH_DOMAIN} auth");
-    let manage_data = ManageDataOp {
-        data_name: data_name
-            .into_bytes()
-            .try_into()
-            .map_err(|_| AppError::Internal("data name conversion failed".into()))?,
-        data_value: Some(
-            nonce_value
-                .into_bytes()
-                .try_into()
-                .map_err(|_| AppError::Internal("nonce value conversion failed".into()))?,
-        ),
-    };
-
-    let op = Operation {
-        source_account: Some(MuxedAccount::Ed25519(Uint256(*client_pubkey))),
-        body: OperationBody::ManageData(manage_data),
-    };
+    let operations = vec![
+        manage_data_op(
+            *client_pubkey,
+            &format!("{WEB_AUTH_DOMAIN} auth"),
+            &nonce_value,
+        )?,
+        manage_data_op(server_key, WEB_AUTH_DOMAIN_KEY, WEB_AUTH_DOMAIN)?,
+    ];
 
     let tx = Transaction {
-        source_account: MuxedAccount::Ed25519(Uint256(state.server_public_key)),
-        fee: 100,
+        source_account: MuxedAccount::Ed25519(Uint256(server_key)),
+        fee: 100 * operations.len() as u32,
         seq_num: SequenceNumber(0),
         cond: Preconditions::Time(TimeBounds {
             min_time: TimePoint(now),
             max_time: TimePoint(now + CHALLENGE_EXPIRY_SECS),
         }),
         memo: Memo::None,
-        operations: vec![op]
+        operations: operations
             .try_into()
             .map_err(|_| AppError::Internal("operations conversion failed".into()))?,
         ext: TransactionExt::V0,
     };
 
-    let net_id = network_id(&state.network_passphrase);
-    let hash = tx_hash(&tx, &net_id)?;
-    let sig = state.signing_key.sign(&hash);
-
-    let hint: [u8; 4] = state.server_public_key[28..32].try_into().unwrap();
-    let decorated = DecoratedSignature {
-        hint: SignatureHint(hint),
-        signature: sig
-            .to_bytes()
-            .to_vec()
-            .try_into()
-            .map_err(|_| AppError::Internal("signature conversion failed".into()))?,
-    };
+    let net_id = network_id(&network.passphrase);
+    let signature = sign_transaction(&signing_key, &tx, &net_id)?;
 
     let envelope = TransactionEnvelope::Tx(TransactionV1Envelope {
         tx,
-        signatures: vec![decorated]
+        signatures: vec![signature]
             .try_into()
             .map_err(|_| AppError::Internal("envelope signatures failed".into()))?,
     });
@@ -177,10 +698,16 @@ fn build_challenge_envelope(
         .to_xdr(Limits::none())
         .map_err(|e| AppError::Internal(format!("XDR encode error: {e}")))?;
 
+    state.challenges.insert(nonce_value, *client_pubkey).await;
     Ok(BASE64.encode(&xdr))
 }
 
-fn verify_challenge_envelope(state: &AuthState, signed_xdr_b64: &str) -> Result<String, AppError> {
+/// Check a signed challenge per SEP-10: issued by this server, inside its
+/// time bounds, with the expected operations, and signed by both parties.
+fn verify_challenge_envelope(
+    state: &AuthState,
+    signed_xdr_b64: &str,
+) -> Result<VerifiedChallenge, AppError> {
     let raw = BASE64
         .decode(signed_xdr_b64)
         .map_err(|_| AppError::BadRequest("Invalid base64".into()))?;
@@ -211,15 +738,22 @@ fn verify_challenge_envelope(state: &AuthState, signed_xdr_b64: &str) -> Result<
             ))
         }
     };
-    if source_key != state.server_public_key {
+    if !state.is_server_key(&source_key) {
         return Err(AppError::BadRequest(
             "Challenge not issued by this server".into(),
         ));
     }
 
+    if !matches!(tx.memo, Memo::None) {
+        return Err(AppError::BadRequest("Unexpected memo in challenge".into()));
+    }
+
     let now = now_secs();
     match &tx.cond {
         Preconditions::Time(bounds) => {
+            if now < bounds.min_time.0 {
+                return Err(AppError::BadRequest("Challenge not yet valid".into()));
+            }
             if now > bounds.max_time.0 {
                 return Err(AppError::BadRequest("Challenge expired".into()));
             }
@@ -228,11 +762,11 @@ fn verify_challenge_envelope(state: &AuthState, signed_xdr_b64: &str) -> Result<
     }
 
     let ops: &[Operation] = inner.tx.operations.as_ref();
-    if ops.is_empty() {
+    let Some((first, rest)) = ops.split_first() else {
         return Err(AppError::BadRequest("No operations in challenge".into()));
-    }
+    };
 
-    let client_key = match &ops[0].source_account {
+    let client_key = match &first.source_account {
         Some(MuxedAccount::Ed25519(Uint256(b))) => *b,
         _ => {
             return Err(AppError::BadRequest(
@@ -241,7 +775,7 @@ fn verify_challenge_envelope(state: &AuthState, signed_xdr_b64: &str) -> Result<
         }
     };
 
-    match &ops[0].body {
+    let nonce = match &first.body {
         OperationBody::ManageData(md) => {
             let name = std::str::from_utf8(md.data_name.as_ref())
                 .map_err(|_| AppError::BadRequest("Invalid data name encoding".into()))?;
@@ -249,70 +783,244 @@ fn verify_challenge_envelope(state: &AuthState, signed_xdr_b64: &str) -> Result<
             if name != expected {
                 return Err(AppError::BadRequest("Invalid manage_data key".into()));
             }
+            let value = md
+                .data_value
+                .as_ref()
+                .and_then(|v| std::str::from_utf8(v.as_ref()).ok())
+                .ok_or_else(|| AppError::BadRequest("Missing challenge nonce".into()))?;
+            let valid = BASE64
+                .decode(value)
+                .is_ok_and(|bytes| bytes.len() == NONCE_BYTES);
+            if !valid {
+                return Err(AppError::BadRequest("Malformed challenge nonce".into()));
+            }
+            value.to_string()
         }
         _ => return Err(AppError::BadRequest("Expected ManageData operation".into())),
-    }
-
-    let net_id = network_id(&state.network_passphrase);
-    let hash = tx_hash(&inner.tx, &net_id)?;
-
-    let sigs: &[DecoratedSignature] = inner.signatures.as_ref();
-    let server_hint: [u8; 4] = state.server_public_key[28..32].try_into().unwrap();
-    let client_hint: [u8; 4] = client_key[28..32].try_into().unwrap();
-
-    let mut server_ok = false;
-    let mut client_ok = false;
+    };
 
-    for ds in sigs {
-        let sig_bytes: &[u8] = ds.signature.as_ref();
-        let Ok(sig) = Ed25519Signature::from_slice(sig_bytes) else {
-            continue;
+    // Every later operation must be a server-sourced ManageData; one of them
+    // names this server as the web auth domain.
+    let mut web_auth_domain_ok = false;
+    for op in rest {
+        let OperationBody::ManageData(md) = &op.body else {
+            return Err(AppError::BadRequest(
+                "Unexpected operation type in challenge".into(),
+            ));
         };
-
-        if ds.hint.0 == server_hint {
-            if let Ok(vk) = VerifyingKey::from_bytes(&state.server_public_key) {
-                if vk.verify(&hash, &sig).is_ok() {
-                    server_ok = true;
-                }
-            }
+        let server_sourced = matches!(
+            &op.source_account,
+            Some(MuxedAccount::Ed25519(Uint256(b))) if *b == source_key
+        );
+        if !server_sourced {
+            return Err(AppError::BadRequest(
+                "Additional operations must be sourced by the server".into(),
+            ));
         }
-
-        if ds.hint.0 == client_hint {
-            if let Ok(vk) = VerifyingKey::from_bytes(&client_key) {
-                if vk.verify(&hash, &sig).is_ok() {
-                    client_ok = true;
-                }
+        if md.data_name.as_slice() == WEB_AUTH_DOMAIN_KEY.as_bytes() {
+            let value = md.data_value.as_ref().map(|v| v.as_slice());
+            if value != Some(WEB_AUTH_DOMAIN.as_bytes()) {
+                return Err(AppError::BadRequest("Invalid web_auth_domain".into()));
             }
+            web_auth_domain_ok = true;
         }
     }
+    if !web_auth_domain_ok {
+        return Err(AppError::BadRequest(
+            "Missing web_auth_domain operation".into(),
+        ));
+    }
 
-    if !server_ok {
+    // The transaction source is the server key that issued the challenge.
+    let server_hint: [u8; 4] = source_key[28..32].try_into().unwrap();
+    let server_key = VerifyingKey::from_bytes(&source_key)
+        .map_err(|e| AppError::Internal(format!("Invalid server key: {e}")))?;
+    let is_server = |ds: &DecoratedSignature, hash: &[u8; 32]| {
+        ds.hint.0 == server_hint
+            && Ed25519Signature::from_slice(ds.signature.as_ref())
+                .is_ok_and(|sig| server_key.verify(hash, &sig).is_ok())
+    };
+
+    // The server signed over the passphrase of the network the challenge
+    // was issued for, so whichever one it verifies under is that network.
+    let mut issued_for = None;
+    for (index, network) in state.networks.iter().enumerate() {
+        let hash = tx_hash(&inner.tx, &network_id(&network.passphrase))?;
+        if inner.signatures.iter().any(|ds| is_server(ds, &hash)) {
+            issued_for = Some((index, hash));
+            break;
+        }
+    }
+    let Some((network, hash)) = issued_for else {
         return Err(AppError::Unauthorized(
             "Missing valid server signature".into(),
         ));
-    }
-    if !client_ok {
+    };
+    let signatures = inner
+        .signatures
+        .iter()
+        .filter(|ds| !is_server(ds, &hash))
+        .cloned()
+        .collect();
+
+    Ok(VerifiedChallenge {
+        client_key,
+        nonce,
+        network,
+        hash,
+        signatures,
+    })
+}
+
+/// Verify a signed challenge against the client account's signers and
+/// consume its nonce, starting a session.
+async fn verify_challenge(
+    state: &AuthState,
+    signed_xdr_b64: &str,
+) -> Result<TokenResponse, AppError> {
+    let verified = verify_challenge_envelope(state, signed_xdr_b64)?;
+
+    if verified.signatures.is_empty() {
         return Err(AppError::Unauthorized(
             "Missing valid client signature".into(),
         ));
     }
+    let network = &state.networks[verified.network];
+    let signers = network.load_signers(verified.client_key).await?;
+    let weight = signers.signed_weight(&verified.hash, &verified.signatures)?;
+    if weight < signers.threshold {
+        return Err(AppError::Unauthorized(format!(
+            "Signature weight {weight} is below the account threshold {}",
+            signers.threshold
+        )));
+    }
+
+    // The nonce is consumed only once the signatures check out, so a copy of
+    // an unsigned challenge cannot be used to burn it.
+    match state.challenges.remove(&verified.nonce).await {
+        Some(issued_for) if issued_for == verified.client_key => {}
+        Some(_) => {
+            return Err(AppError::Unauthorized(
+                "Challenge was issued for a different account".into(),
+            ))
+        }
+        None => {
+            return Err(AppError::Unauthorized(
+                "Challenge is unknown, expired or already used".into(),
+            ))
+        }
+    }
+
+    start_session(state, &stellar_address(&verified.client_key)).await
+}
+
+// ── Sessions ──────────────────────────────────────────────────────────────────
 
-    let client_address =
-        Strkey::PublicKeyEd25519(stellar_strkey::ed25519::PublicKey(client_key)).to_string();
+fn session_cache<V: Clone + Send + Sync + 'static>(ttl: Duration) -> Cache<String, V> {
+    Cache::builder()
+        .max_capacity(MAX_SESSIONS)
+        .time_to_live(ttl)
+        .build()
+}
+
+/// Open a session for `subject` (a G… account) and issue its first tokens.
+pub async fn start_session(state: &AuthState, subject: &str) -> Result<TokenResponse, AppError> {
+    let mut id = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut id);
+    issue_tokens(state, subject, &hex::encode(id), Vec::new()).await
+}
 
+/// Issue an access token and a fresh refresh token for a session. The
+/// refresh token is `<session id>.<secret>`; only the secret's hash is kept.
+async fn issue_tokens(
+    state: &AuthState,
+    subject: &str,
+    session_id: &str,
+    retired: Vec<[u8; 32]>,
+) -> Result<TokenResponse, AppError> {
+    let now = now_secs();
     let claims = Claims {
-        sub: client_address,
+        sub: subject.to_string(),
         iss: WEB_AUTH_DOMAIN.to_string(),
         iat: now,
-        exp: now + JWT_EXPIRY_SECS,
+        exp: now + state.access_ttl.as_secs(),
+        sid: session_id.to_string(),
+        role: state.role_of(subject),
     };
-
-    encode(
+    let token = encode(
         &Header::default(),
         &claims,
         &EncodingKey::from_secret(state.jwt_secret.as_bytes()),
     )
-    .map_err(|e| AppError::Internal(format!("JWT encode error: {e}")))
+    .map_err(|e| AppError::Internal(format!("JWT encode error: {e}")))?;
+
+    let mut secret = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut secret);
+    state
+        .sessions
+        .insert(
+            session_id.to_string(),
+            RefreshSession {
+                subject: subject.to_string(),
+                secret_hash: Sha256::digest(secret).into(),
+                retired,
+            },
+        )
+        .await;
+
+    Ok(TokenResponse {
+        token,
+        refresh_token: format!("{session_id}.{}", URL_SAFE_NO_PAD.encode(secret)),
+        expires_in: state.access_ttl.as_secs(),
+    })
+}
+
+/// Exchange a refresh token for new tokens. The presented token is retired;
+/// presenting a retired token again revokes the whole session.
+async fn refresh_session(
+    state: &AuthState,
+    refresh_token: &str,
+) -> Result<TokenResponse, AppError> {
+    let invalid = || AppError::Unauthorized("Invalid refresh token".into());
+    let (session_id, secret) = refresh_token.split_once('.').ok_or_else(invalid)?;
+    let secret = URL_SAFE_NO_PAD.decode(secret).map_err(|_| invalid())?;
+    let hash: [u8; 32] = Sha256::digest(secret).into();
+
+    let session = state
+        .sessions
+        .get(session_id)
+        .await
+        .ok_or_else(|| AppError::Unauthorized("Refresh token expired or revoked".into()))?;
+    if hash != session.secret_hash {
+        if session.retired.contains(&hash) {
+            tracing::warn!(
+                subject = %session.subject,
+                "Refresh token reused; revoking session"
+            );
+            revoke_session(state, session_id).await;
+            return Err(AppError::Unauthorized(
+                "Refresh token already used; session revoked".into(),
+            ));
+        }
+        return Err(invalid());
+    }
+
+    let mut retired = session.retired;
+    retired.push(session.secret_hash);
+    if retired.len() > MAX_RETIRED_SECRETS {
+        retired.remove(0);
+    }
+    issue_tokens(state, &session.subject, session_id, retired).await
+}
+
+/// End a session: its refresh token stops working and its access tokens are
+/// rejected until they expire.
+pub async fn revoke_session(state: &AuthState, session_id: &str) {
+    state.sessions.invalidate(session_id).await;
+    state
+        .revoked_sessions
+        .insert(session_id.to_string(), ())
+        .await;
 }
 
 #[utoipa::path(
@@ -337,11 +1045,12 @@ pub async fn challenge_handler(
         _ => return Err(AppError::BadRequest("Expected G... account address".into())),
     };
 
-    let transaction = build_challenge_envelope(&state, &pubkey)?;
+    let network = state.network(payload.network.as_deref())?;
+    let transaction = build_challenge_envelope(&state, network, &pubkey).await?;
 
     Ok(Json(ChallengeResponse {
         transaction,
-        network_passphrase: state.network_passphrase.clone(),
+        network_passphrase: network.passphrase.clone(),
     }))
 }
 
@@ -350,24 +1059,143 @@ pub async fn challenge_handler(
     path = "/auth/verify",
     request_body = VerifyRequest,
     responses(
-        (status = 200, description = "JWT token issued", body = VerifyResponse),
-        (status = 401, description = "Authentication failed")
+        (status = 200, description = "Access and refresh tokens issued", body = TokenResponse),
+        (status = 400, description = "Malformed challenge"),
+        (status = 401, description = "Missing signature, or challenge unknown or already used")
     ),
     tag = "Auth"
 )]
 pub async fn verify_handler(
     Extension(state): Extension<Arc<AuthState>>,
     Json(payload): Json<VerifyRequest>,
-) -> Result<Json<VerifyResponse>, AppError> {
-    let token = verify_challenge_envelope(&state, &payload.transaction)?;
-    Ok(Json(VerifyResponse { token }))
+) -> Result<Json<TokenResponse>, AppError> {
+    let tokens = verify_challenge(&state, &payload.transaction).await?;
+    Ok(Json(tokens))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/refresh",
+    request_body = RefreshRequest,
+    responses(
+        (status = 200, description = "New access and refresh tokens", body = TokenResponse),
+        (status = 401, description = "Refresh token invalid, expired, revoked or reused")
+    ),
+    tag = "Auth"
+)]
+pub async fn refresh_handler(
+    Extension(state): Extension<Arc<AuthState>>,
+    Json(payload): Json<RefreshRequest>,
+) -> Result<Json<TokenResponse>, AppError> {
+    let tokens = refresh_session(&state, &payload.refresh_token).await?;
+    Ok(Json(tokens))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/logout",
+    responses(
+        (status = 204, description = "Session revoked"),
+        (status = 400, description = "Called with an API key, which has no session"),
+        (status = 401, description = "Missing or invalid JWT")
+    ),
+    security(
+        ("jwt" = [])
+    ),
+    tag = "Auth"
+)]
+pub async fn logout_handler(
+    Extension(state): Extension<Arc<AuthState>>,
+    Extension(caller): Extension<AuthContext>,
+) -> Result<StatusCode, AppError> {
+    let session_id = caller.session_id.ok_or_else(|| {
+        AppError::BadRequest("API keys have no session; revoke the key instead".into())
+    })?;
+    revoke_session(&state, &session_id).await;
+    tracing::info!(subject = %caller.subject, "Session logged out");
+    Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    post,
+    path = "/auth/rotate-key",
+    request_body = RotateKeyRequest,
+    responses(
+        (status = 200, description = "Signing key rotated", body = RotateKeyResponse),
+        (status = 400, description = "Invalid seed"),
+        (status = 401, description = "Missing or invalid JWT")
+    ),
+    security(
+        ("jwt" = [])
+    ),
+    tag = "Auth"
+)]
+pub async fn rotate_key_handler(
+    Extension(state): Extension<Arc<AuthState>>,
+    Json(payload): Json<RotateKeyRequest>,
+) -> Result<Json<RotateKeyResponse>, AppError> {
+    let seed = match payload.seed.as_deref() {
+        Some(seed) => parse_seed(seed).map_err(AppError::BadRequest)?,
+        None => random_seed(),
+    };
+    let (previous, until) = state.rotate(seed)?;
+    Ok(Json(RotateKeyResponse {
+        signing_key: state.server_stellar_address(),
+        previous_key: stellar_address(&previous),
+        previous_valid_until: until,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/.well-known/stellar.toml",
+    responses(
+        (status = 200, description = "SEP-1 stellar.toml with the SEP-10 signing key", content_type = "text/plain")
+    ),
+    tag = "Auth"
+)]
+pub async fn stellar_toml_handler(
+    Extension(state): Extension<Arc<AuthState>>,
+) -> impl IntoResponse {
+    let mut toml = format!(
+        "NETWORK_PASSPHRASE=\"{}\"\nSIGNING_KEY=\"{}\"\n",
+        state.network_passphrase(),
+        state.server_stellar_address()
+    );
+    if !state.web_auth_endpoint.is_empty() {
+        toml.push_str(&format!(
+            "WEB_AUTH_ENDPOINT=\"{}\"\n",
+            state.web_auth_endpoint
+        ));
+    }
+    ([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], toml)
+}
+
+/// Require a valid, unrevoked access token or an API key (`X-API-Key`) and
+/// add the caller's [`AuthContext`] to the request.
 pub async fn auth_middleware(
     Extension(state): Extension<Arc<AuthState>>,
-    req: Request,
+    mut req: Request,
     next: Next,
 ) -> Result<Response, AppError> {
+    let caller = match req.headers().get(API_KEY_HEADER) {
+        Some(key) => {
+            let key = key
+                .to_str()
+                .map_err(|_| AppError::Unauthorized("Invalid API key".into()))?;
+            // API keys act with their owner's current role.
+            let mut caller = state.api_keys.authenticate(key)?;
+            caller.role = state.role_of(&caller.subject);
+            caller
+        }
+        None => bearer_caller(&state, &req)?,
+    };
+
+    req.extensions_mut().insert(caller);
+    Ok(next.run(req).await)
+}
+
+fn bearer_caller(state: &AuthState, req: &Request) -> Result<AuthContext, AppError> {
     let auth_header = req
         .headers()
         .get(header::AUTHORIZATION)
@@ -378,12 +1206,857 @@ pub async fn auth_middleware(
         .strip_prefix("Bearer ")
         .ok_or_else(|| AppError::Unauthorized("Expected Bearer token".into()))?;
 
-    decode::<Claims>(
+    let claims = decode::<Claims>(
         token,
         &DecodingKey::from_secret(state.jwt_secret.as_bytes()),
         &Validation::default(),
     )
-    .map_err(|e| AppError::Unauthorized(format!("Invalid token: {e}")))?;
+    .map_err(|e| AppError::Unauthorized(format!("Invalid token: {e}")))?
+    .claims;
 
+    if state.revoked_sessions.contains_key(&claims.sid) {
+        return Err(AppError::Unauthorized("Token has been revoked".into()));
+    }
+
+    // Like API keys, tokens act with their owner's current role.
+    Ok(AuthContext {
+        role: state.role_of(&claims.sub),
+        subject: claims.sub,
+        session_id: Some(claims.sid),
+        api_key_id: None,
+        scopes: Scope::ALL.to_vec(),
+    })
+}
+
+/// Reject callers whose credential lacks `scope`. Layered inside
+/// [`auth_middleware`].
+pub async fn require_scope(
+    State(scope): State<Scope>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let allowed = req
+        .extensions()
+        .get::<AuthContext>()
+        .is_some_and(|caller| caller.has_scope(scope));
+    if !allowed {
+        return Err(AppError::Forbidden(format!(
+            "Requires the '{}' scope",
+            scope.as_str()
+        )));
+    }
+    Ok(next.run(req).await)
+}
+
+/// Reject callers without `role`. Layered inside [`auth_middleware`].
+pub async fn require_role(
+    State(role): State<Role>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let allowed = req
+        .extensions()
+        .get::<AuthContext>()
+        .is_some_and(|caller| caller.role == role);
+    if !allowed {
+        return Err(AppError::Forbidden(format!(
+            "Requires the '{}' role",
+            role.as_str()
+        )));
+    }
     Ok(next.run(req).await)
 }
+
+/// Parse a comma-separated list of G… addresses.
+pub fn parse_admins(list: &str) -> Result<HashSet<String>, String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|a| !a.is_empty())
+        .map(|account| match Strkey::from_string(account) {
+            Ok(Strkey::PublicKeyEd25519(_)) => Ok(account.to_string()),
+            _ => Err(format!("'{account}' is not a G… account address")),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_rpc::{MockReply, MockRpc};
+    use crate::rpc_provider::{ProviderRegistry, RpcProvider};
+    use serde_json::json;
+    use soroban_sdk::xdr::{
+        AccountEntryExt, CreateAccountOp, Signer, String64, StringM, Thresholds,
+        TransactionV1Envelope,
+    };
+
+    const PASSPHRASE: &str = "Test SDF Network ; September 2015";
+
+    fn state() -> AuthState {
+        AuthState::new("secret".to_string(), Some([1; 32]), PASSPHRASE.to_string())
+    }
+
+    fn client(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn public_key(key: &SigningKey) -> [u8; 32] {
+        key.verifying_key().to_bytes()
+    }
+
+    async fn challenge(state: &AuthState, client: &SigningKey) -> Transaction {
+        challenge_on(state, None, client).await
+    }
+
+    async fn challenge_on(
+        state: &AuthState,
+        network: Option<&str>,
+        client: &SigningKey,
+    ) -> Transaction {
+        let network = state.network(network).unwrap();
+        let xdr = build_challenge_envelope(state, network, &public_key(client))
+            .await
+            .unwrap();
+        let raw = BASE64.decode(xdr).unwrap();
+        match TransactionEnvelope::from_xdr(raw, Limits::none()).unwrap() {
+            TransactionEnvelope::Tx(TransactionV1Envelope { tx, .. }) => tx,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Envelope for `tx` signed by each of `signers`.
+    fn envelope(state: &AuthState, tx: Transaction, signers: &[&SigningKey]) -> String {
+        envelope_on(state.network_passphrase(), tx, signers)
+    }
+
+    fn envelope_on(passphrase: &str, tx: Transaction, signers: &[&SigningKey]) -> String {
+        let net_id = network_id(passphrase);
+        let signatures: Vec<_> = signers
+            .iter()
+            .map(|key| sign_transaction(key, &tx, &net_id).unwrap())
+            .collect();
+        let envelope = TransactionEnvelope::Tx(TransactionV1Envelope {
+            tx,
+            signatures: signatures.try_into().unwrap(),
+        });
+        BASE64.encode(envelope.to_xdr(Limits::none()).unwrap())
+    }
+
+    fn signed(state: &AuthState, tx: Transaction, client: &SigningKey) -> String {
+        envelope(state, tx, &[&state.signing_key(), client])
+    }
+
+    fn set_operations(tx: &mut Transaction, ops: Vec<Operation>) {
+        tx.operations = ops.try_into().unwrap();
+    }
+
+    async fn rejects(state: &AuthState, xdr: &str, message: &str) {
+        match verify_challenge(state, xdr).await {
+            Err(AppError::BadRequest(m) | AppError::Unauthorized(m)) => {
+                assert!(m.contains(message), "expected {message:?}, got {m:?}")
+            }
+            other => panic!("expected rejection with {message:?}, got {other:?}"),
+        }
+    }
+
+    // ── Accepted ──────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_challenge_has_nonce_and_web_auth_domain() {
+        let state = state();
+        let client = client(2);
+        let tx = challenge(&state, &client).await;
+
+        let ops: &[Operation] = tx.operations.as_ref();
+        assert_eq!(ops.len(), 2);
+        let OperationBody::ManageData(nonce) = &ops[0].body else {
+            panic!("expected ManageData");
+        };
+        assert_eq!(nonce.data_value.as_ref().unwrap().len(), 64);
+        let OperationBody::ManageData(domain) = &ops[1].body else {
+            panic!("expected ManageData");
+        };
+        assert_eq!(domain.data_name.as_slice(), b"web_auth_domain");
+        assert_eq!(
+            ops[1].source_account,
+            Some(MuxedAccount::Ed25519(Uint256(state.server_public_key())))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verify_issues_token_once() {
+        let state = state();
+        let client = client(2);
+        let xdr = signed(&state, challenge(&state, &client).await, &client);
+
+        let tokens = verify_challenge(&state, &xdr).await.unwrap();
+        let claims = decode::<Claims>(
+            &tokens.token,
+            &DecodingKey::from_secret(b"secret"),
+            &Validation::default(),
+        )
+        .unwrap()
+        .claims;
+        assert!(claims.sub.starts_with('G'));
+
+        rejects(&state, &xdr, "already used").await;
+    }
+
+    // ── Nonce ─────────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_rejects_unknown_nonce() {
+        let issuer = state();
+        let client = client(2);
+        let tx = challenge(&issuer, &client).await;
+
+        // Same server key, but a fresh state that never issued this nonce.
+        let other = state();
+        rejects(&other, &signed(&other, tx, &client), "unknown").await;
+    }
+
+    #[tokio::test]
+    async fn test_rejects_nonce_issued_for_other_account() {
+        let state = state();
+        let (alice, mallory) = (client(2), client(3));
+        let mut tx = challenge(&state, &alice).await;
+
+        let mut ops: Vec<Operation> = tx.operations.to_vec();
+        ops[0].source_account = Some(MuxedAccount::Ed25519(Uint256(public_key(&mallory))));
+        set_operations(&mut tx, ops);
+        rejects(&state, &signed(&state, tx, &mallory), "different account").await;
+    }
+
+    #[tokio::test]
+    async fn test_rejects_malformed_nonce() {
+        let state = state();
+        let client = client(2);
+        let mut tx = challenge(&state, &client).await;
+
+        let mut ops: Vec<Operation> = tx.operations.to_vec();
+        ops[0] = manage_data_op(public_key(&client), "soroscope auth", "short").unwrap();
+        set_operations(&mut tx, ops);
+        rejects(
+            &state,
+            &signed(&state, tx, &client),
+            "Malformed challenge nonce",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_failed_verify_does_not_consume_nonce() {
+        let state = state();
+        let client = client(2);
+        let tx = challenge(&state, &client).await;
+
+        let unsigned = envelope(&state, tx.clone(), &[&state.signing_key()]);
+        rejects(&state, &unsigned, "client signature").await;
+        assert!(verify_challenge(&state, &signed(&state, tx, &client))
+            .await
+            .is_ok());
+    }
+
+    // ── Time bounds ───────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_rejects_challenge_before_min_time() {
+        let state = state();
+        let client = client(2);
+        let mut tx = challenge(&state, &client).await;
+        let now = now_secs();
+        tx.cond = Preconditions::Time(TimeBounds {
+            min_time: TimePoint(now + 60),
+            max_time: TimePoint(now + 120),
+        });
+        rejects(&state, &signed(&state, tx, &client), "not yet valid").await;
+    }
+
+    #[tokio::test]
+    async fn test_rejects_expired_challenge() {
+        let state = state();
+        let client = client(2);
+        let mut tx = challenge(&state, &client).await;
+        let now = now_secs();
+        tx.cond = Preconditions::Time(TimeBounds {
+            min_time: TimePoint(now - 600),
+            max_time: TimePoint(now - 300),
+        });
+        rejects(&state, &signed(&state, tx, &client), "expired").await;
+    }
+
+    // ── Operations ────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_rejects_missing_web_auth_domain() {
+        let state = state();
+        let client = client(2);
+        let mut tx = challenge(&state, &client).await;
+        let ops = vec![tx.operations[0].clone()];
+        set_operations(&mut tx, ops);
+        rejects(
+            &state,
+            &signed(&state, tx, &client),
+            "Missing web_auth_domain",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_rejects_wrong_web_auth_domain() {
+        let state = state();
+        let client = client(2);
+        let mut tx = challenge(&state, &client).await;
+        let mut ops: Vec<Operation> = tx.operations.to_vec();
+        ops[1] =
+            manage_data_op(state.server_public_key(), "web_auth_domain", "evil.example").unwrap();
+        set_operations(&mut tx, ops);
+        rejects(
+            &state,
+            &signed(&state, tx, &client),
+            "Invalid web_auth_domain",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_rejects_extra_client_sourced_operation() {
+        let state = state();
+        let client = client(2);
+        let mut tx = challenge(&state, &client).await;
+        let mut ops: Vec<Operation> = tx.operations.to_vec();
+        ops.push(manage_data_op(public_key(&client), "extra", "value").unwrap());
+        set_operations(&mut tx, ops);
+        rejects(
+            &state,
+            &signed(&state, tx, &client),
+            "sourced by the server",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_rejects_non_manage_data_operation() {
+        let state = state();
+        let client = client(2);
+        let mut tx = challenge(&state, &client).await;
+        let mut ops: Vec<Operation> = tx.operations.to_vec();
+        ops.push(Operation {
+            source_account: Some(MuxedAccount::Ed25519(Uint256(state.server_public_key()))),
+            body: OperationBody::CreateAccount(CreateAccountOp {
+                destination: soroban_sdk::xdr::AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(
+                    public_key(&client),
+                ))),
+                starting_balance: 1,
+            }),
+        });
+        set_operations(&mut tx, ops);
+        rejects(
+            &state,
+            &signed(&state, tx, &client),
+            "Unexpected operation type",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_rejects_wrong_first_operation_key() {
+        let state = state();
+        let client = client(2);
+        let mut tx = challenge(&state, &client).await;
+        let mut ops: Vec<Operation> = tx.operations.to_vec();
+        if let OperationBody::ManageData(md) = &mut ops[0].body {
+            md.data_name = String64::from(StringM::try_from("other auth").unwrap());
+        }
+        set_operations(&mut tx, ops);
+        rejects(
+            &state,
+            &signed(&state, tx, &client),
+            "Invalid manage_data key",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_rejects_memo() {
+        let state = state();
+        let client = client(2);
+        let mut tx = challenge(&state, &client).await;
+        tx.memo = Memo::Id(7);
+        rejects(&state, &signed(&state, tx, &client), "memo").await;
+    }
+
+    // ── Signatures ────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_rejects_missing_server_signature() {
+        let state = state();
+        let client = client(2);
+        let tx = challenge(&state, &client).await;
+        rejects(
+            &state,
+            &envelope(&state, tx, &[&client]),
+            "server signature",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_rejects_foreign_server() {
+        let state = state();
+        let client = client(2);
+        let mut tx = challenge(&state, &client).await;
+        tx.source_account = MuxedAccount::Ed25519(Uint256(public_key(&client)));
+        rejects(
+            &state,
+            &signed(&state, tx, &client),
+            "not issued by this server",
+        )
+        .await;
+    }
+
+    // ── Account signers ───────────────────────────────────────────────────
+
+    fn account(key: &SigningKey, thresholds: [u8; 4], signers: Vec<SignerKey>) -> AccountEntry {
+        AccountEntry {
+            account_id: AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(public_key(key)))),
+            balance: 10_000_000,
+            seq_num: SequenceNumber(1),
+            num_sub_entries: signers.len() as u32,
+            inflation_dest: None,
+            flags: 0,
+            home_domain: Default::default(),
+            thresholds: Thresholds(thresholds),
+            signers: signers
+                .into_iter()
+                .map(|key| Signer { key, weight: 1 })
+                .collect::<Vec<_>>()
+                .try_into()
+                .unwrap(),
+            ext: AccountEntryExt::V0,
+        }
+    }
+
+    fn ed25519(key: &SigningKey) -> SignerKey {
+        SignerKey::Ed25519(Uint256(public_key(key)))
+    }
+
+    /// Auth state whose account lookups return `entry`, or no entry.
+    async fn state_with_account(entry: Option<AccountEntry>) -> (AuthState, MockRpc) {
+        let rpc = MockRpc::start().await;
+        let entries = match entry {
+            Some(entry) => json!([{
+                "key": "AAAA",
+                "xdr": BASE64.encode(
+                    LedgerEntryData::Account(entry).to_xdr(Limits::none()).unwrap()
+                ),
+                "lastModifiedLedgerSeq": 1
+            }]),
+            None => json!(null),
+        };
+        rpc.on(
+            "getLedgerEntries",
+            MockReply::result(json!({ "entries": entries, "latestLedger": 10 })),
+        );
+        let registry = ProviderRegistry::new(vec![RpcProvider::new("mock", rpc.url())]);
+        (state().with_rpc(SorobanRpc::new(registry)), rpc)
+    }
+
+    #[test]
+    fn test_signers_from_account_entry() {
+        let (owner, cosigner) = (client(2), client(3));
+        let entry = account(
+            &owner,
+            [0, 0, 0, 0],
+            vec![ed25519(&cosigner), SignerKey::PreAuthTx(Uint256([9; 32]))],
+        );
+        let signers = AccountSigners::from_entry(&entry);
+
+        // The disabled master key and the pre-auth signer can't sign.
+        assert_eq!(signers.signers, vec![(public_key(&cosigner), 1)]);
+        assert_eq!(signers.threshold, 1);
+    }
+
+    #[tokio::test]
+    async fn test_missing_account_uses_master_key() {
+        let (state, rpc) = state_with_account(None).await;
+        let client = client(2);
+        let xdr = signed(&state, challenge(&state, &client).await, &client);
+
+        assert!(verify_challenge(&state, &xdr).await.is_ok());
+        assert_eq!(rpc.calls("getLedgerEntries"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_multisig_requires_medium_threshold() {
+        let (owner, cosigner) = (client(2), client(3));
+        let entry = account(&owner, [1, 1, 2, 2], vec![ed25519(&cosigner)]);
+        let (state, _rpc) = state_with_account(Some(entry)).await;
+
+        let tx = challenge(&state, &owner).await;
+        let xdr = envelope(&state, tx.clone(), &[&state.signing_key(), &owner]);
+        rejects(&state, &xdr, "below the account threshold").await;
+
+        let xdr = envelope(&state, tx, &[&state.signing_key(), &owner, &cosigner]);
+        assert!(verify_challenge(&state, &xdr).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_disabled_master_key_cannot_sign_in() {
+        let (owner, cosigner) = (client(2), client(3));
+        let entry = account(&owner, [0, 1, 1, 1], vec![ed25519(&cosigner)]);
+        let (state, _rpc) = state_with_account(Some(entry)).await;
+
+        let tx = challenge(&state, &owner).await;
+        let xdr = envelope(&state, tx.clone(), &[&state.signing_key(), &owner]);
+        rejects(&state, &xdr, "unknown signer").await;
+
+        let xdr = envelope(&state, tx, &[&state.signing_key(), &cosigner]);
+        assert!(verify_challenge(&state, &xdr).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_signatures_count_once() {
+        let owner = client(2);
+        let entry = account(&owner, [1, 2, 2, 2], vec![]);
+        let (state, _rpc) = state_with_account(Some(entry)).await;
+
+        let tx = challenge(&state, &owner).await;
+        let xdr = envelope(&state, tx, &[&state.signing_key(), &owner, &owner]);
+        rejects(&state, &xdr, "below the account threshold").await;
+    }
+
+    #[tokio::test]
+    async fn test_rejects_signature_from_non_signer() {
+        let (owner, stranger) = (client(2), client(4));
+        let (state, _rpc) = state_with_account(None).await;
+
+        let tx = challenge(&state, &owner).await;
+        let xdr = envelope(&state, tx, &[&state.signing_key(), &owner, &stranger]);
+        rejects(&state, &xdr, "unknown signer").await;
+    }
+
+    #[tokio::test]
+    async fn test_signer_lookup_failure_is_an_error() {
+        let (state, rpc) = state_with_account(None).await;
+        rpc.on("getLedgerEntries", MockReply::status(500));
+        let client = client(2);
+        let xdr = signed(&state, challenge(&state, &client).await, &client);
+
+        let result = verify_challenge(&state, &xdr).await;
+        assert!(matches!(result, Err(AppError::Internal(_))));
+        // The challenge stays usable once the lookup succeeds.
+        rpc.on(
+            "getLedgerEntries",
+            MockReply::result(json!({ "entries": null, "latestLedger": 10 })),
+        );
+        assert!(verify_challenge(&state, &xdr).await.is_ok());
+    }
+
+    // ── Signing key ───────────────────────────────────────────────────────
+
+    #[test]
+    fn test_parse_seed() {
+        let seed = [5; 32];
+        let strkey =
+            Strkey::PrivateKeyEd25519(stellar_strkey::ed25519::PrivateKey(seed)).to_string();
+        assert_eq!(parse_seed(&format!("{strkey}\n")), Ok(seed));
+
+        let address = stellar_address(&seed);
+        assert!(parse_seed(&address).unwrap_err().contains("S..."));
+        assert!(parse_seed("nope").is_err());
+    }
+
+    #[test]
+    fn test_load_seed_creates_and_reuses_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sep10.seed");
+        let path = path.to_str().unwrap();
+
+        let created = load_seed("", path).unwrap().unwrap();
+        assert_eq!(load_seed("", path).unwrap(), Some(created));
+
+        // An explicit seed wins over the file.
+        let explicit =
+            Strkey::PrivateKeyEd25519(stellar_strkey::ed25519::PrivateKey([6; 32])).to_string();
+        assert_eq!(load_seed(&explicit, path).unwrap(), Some([6; 32]));
+
+        assert_eq!(load_seed("", "").unwrap(), None);
+        std::fs::write(path, "garbage").unwrap();
+        assert!(load_seed("", path).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rotated_key_verifies_outstanding_challenges() {
+        let dir = tempfile::tempdir().unwrap();
+        let seed_file = dir.path().join("sep10.seed");
+        let state = state().with_seed_file(seed_file.clone());
+        let client = client(2);
+
+        let old_key = state.signing_key();
+        let outstanding = challenge(&state, &client).await;
+
+        let (previous, _) = state.rotate([9; 32]).unwrap();
+        assert_eq!(previous, old_key.verifying_key().to_bytes());
+        assert_ne!(state.server_public_key(), previous);
+        let saved = std::fs::read_to_string(&seed_file).unwrap();
+        assert_eq!(parse_seed(&saved), Ok([9; 32]));
+
+        // Signed by the old key, still accepted within the overlap.
+        let xdr = envelope(&state, outstanding, &[&old_key, &client]);
+        assert!(verify_challenge(&state, &xdr).await.is_ok());
+
+        // New challenges come from the new key.
+        let fresh = challenge(&state, &client).await;
+        assert_eq!(
+            fresh.source_account,
+            MuxedAccount::Ed25519(Uint256(state.server_public_key()))
+        );
+        assert!(verify_challenge(&state, &signed(&state, fresh, &client))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_rotated_key_rejected_after_overlap() {
+        let state = state();
+        let client = client(2);
+        let old_key = state.signing_key();
+        let outstanding = challenge(&state, &client).await;
+
+        state.rotate([9; 32]).unwrap();
+        state.keys.write().unwrap().previous[0].1 = 0;
+
+        let xdr = envelope(&state, outstanding, &[&old_key, &client]);
+        rejects(&state, &xdr, "not issued by this server").await;
+    }
+
+    #[tokio::test]
+    async fn test_stellar_toml_exposes_signing_key() {
+        let state = Arc::new(state().with_web_auth_endpoint("https://soroscope.example/auth"));
+        let response = stellar_toml_handler(Extension(Arc::clone(&state)))
+            .await
+            .into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let toml = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(toml.contains(&format!(
+            "SIGNING_KEY=\"{}\"",
+            state.server_stellar_address()
+        )));
+        assert!(toml.contains("WEB_AUTH_ENDPOINT=\"https://soroscope.example/auth\""));
+        assert!(toml.contains(&format!("NETWORK_PASSPHRASE=\"{PASSPHRASE}\"")));
+    }
+
+    // ── Sessions ──────────────────────────────────────────────────────────
+
+    fn claims(token: &str) -> Claims {
+        decode::<Claims>(
+            token,
+            &DecodingKey::from_secret(b"secret"),
+            &Validation::default(),
+        )
+        .unwrap()
+        .claims
+    }
+
+    #[tokio::test]
+    async fn test_access_token_is_short_lived() {
+        let state = state().with_token_ttls(Duration::from_secs(60), Duration::from_secs(600));
+        let tokens = start_session(&state, "GABC").await.unwrap();
+
+        let claims = claims(&tokens.token);
+        assert_eq!(claims.exp - claims.iat, 60);
+        assert_eq!(tokens.expires_in, 60);
+        assert!(tokens
+            .refresh_token
+            .starts_with(&format!("{}.", claims.sid)));
+    }
+
+    #[tokio::test]
+    async fn test_refresh_rotates_token_and_keeps_session() {
+        let state = state();
+        let first = start_session(&state, "GABC").await.unwrap();
+        let second = refresh_session(&state, &first.refresh_token).await.unwrap();
+
+        assert_ne!(second.refresh_token, first.refresh_token);
+        assert_eq!(claims(&second.token).sid, claims(&first.token).sid);
+        assert_eq!(claims(&second.token).sub, "GABC");
+        assert!(refresh_session(&state, &second.refresh_token).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_token_reuse_revokes_session() {
+        let state = state();
+        let first = start_session(&state, "GABC").await.unwrap();
+        let second = refresh_session(&state, &first.refresh_token).await.unwrap();
+
+        let err = refresh_session(&state, &first.refresh_token)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::Unauthorized(m) if m.contains("session revoked")));
+        // The legitimate holder is logged out too.
+        assert!(refresh_session(&state, &second.refresh_token)
+            .await
+            .is_err());
+        assert!(state
+            .revoked_sessions
+            .contains_key(&claims(&second.token).sid));
+    }
+
+    #[tokio::test]
+    async fn test_forged_refresh_token_does_not_revoke_session() {
+        let state = state();
+        let tokens = start_session(&state, "GABC").await.unwrap();
+        let sid = claims(&tokens.token).sid;
+
+        for forged in [format!("{sid}.AAAA"), format!("{sid}.!!"), sid.clone()] {
+            assert!(refresh_session(&state, &forged).await.is_err());
+        }
+        assert!(refresh_session(&state, &tokens.refresh_token).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_revoked_session_cannot_refresh() {
+        let state = state();
+        let tokens = start_session(&state, "GABC").await.unwrap();
+        revoke_session(&state, &claims(&tokens.token).sid).await;
+
+        let err = refresh_session(&state, &tokens.refresh_token)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::Unauthorized(m) if m.contains("revoked")));
+    }
+
+    // ── Networks ──────────────────────────────────────────────────────────
+
+    const FUTURENET: &str = "Test SDF Future Network ; October 2022";
+
+    fn multi_network_state() -> AuthState {
+        state()
+            .with_network_name("testnet")
+            .with_network("futurenet", FUTURENET, None)
+    }
+
+    #[tokio::test]
+    async fn test_challenge_for_named_network() {
+        let state = multi_network_state();
+        let client = client(2);
+        let tx = challenge_on(&state, Some("futurenet"), &client).await;
+        let xdr = envelope_on(FUTURENET, tx, &[&state.signing_key(), &client]);
+
+        assert!(verify_challenge(&state, &xdr).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_client_must_sign_for_the_challenge_network() {
+        let state = multi_network_state();
+        let client = client(2);
+        let tx = challenge_on(&state, Some("futurenet"), &client).await;
+        let server = sign_transaction(&state.signing_key(), &tx, &network_id(FUTURENET)).unwrap();
+        let wrong_network =
+            sign_transaction(&client, &tx, &network_id(state.network_passphrase())).unwrap();
+        let envelope = TransactionEnvelope::Tx(TransactionV1Envelope {
+            tx,
+            signatures: vec![server, wrong_network].try_into().unwrap(),
+        });
+        let xdr = BASE64.encode(envelope.to_xdr(Limits::none()).unwrap());
+
+        rejects(&state, &xdr, "unknown signer").await;
+    }
+
+    #[tokio::test]
+    async fn test_signers_are_loaded_from_the_challenge_network() {
+        let (state, rpc) = state_with_account(None).await;
+        let state = state.with_network("futurenet", FUTURENET, None);
+        let client = client(2);
+
+        let tx = challenge_on(&state, Some("futurenet"), &client).await;
+        let xdr = envelope_on(FUTURENET, tx, &[&state.signing_key(), &client]);
+        verify_challenge(&state, &xdr).await.unwrap();
+        assert_eq!(rpc.calls("getLedgerEntries"), 0);
+
+        let xdr = signed(&state, challenge(&state, &client).await, &client);
+        verify_challenge(&state, &xdr).await.unwrap();
+        assert_eq!(rpc.calls("getLedgerEntries"), 1);
+    }
+
+    #[test]
+    fn test_unknown_network_is_rejected() {
+        let state = multi_network_state();
+        assert_eq!(state.network(None).unwrap().name, "testnet");
+        let err = state.network(Some("mainnet")).err().unwrap();
+        assert!(
+            matches!(&err, AppError::BadRequest(m) if m.contains("testnet, futurenet")),
+            "{err:?}"
+        );
+    }
+
+    // ── Roles ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_parse_admins() {
+        let admins = parse_admins(
+            " GAAZI4TCR3TY5OJHCTJC2A4QSY6CJWJH5IAJTGKIN2ER7LBNVKOCCWN7 ,,\
+             GBRPYHIL2CI3FNQ4BXLFMNDLFJUNPU2HY3ZMFSHONUCEOASW7QC7OX2H",
+        )
+        .unwrap();
+        assert_eq!(admins.len(), 2);
+        assert!(parse_admins("").unwrap().is_empty());
+        assert!(parse_admins("GABC").is_err());
+        // Seeds are rejected rather than silently treated as accounts.
+        assert!(parse_admins(
+            &Strkey::PrivateKeyEd25519(stellar_strkey::ed25519::PrivateKey([1; 32])).to_string()
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tokens_carry_role() {
+        let state = state().with_admins(["GADMIN".to_string()]);
+        let admin = start_session(&state, "GADMIN").await.unwrap();
+        let user = start_session(&state, "GUSER").await.unwrap();
+        assert_eq!(claims(&admin.token).role, Role::Admin);
+        assert_eq!(claims(&user.token).role, Role::User);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_picks_up_role_changes() {
+        let state = state().with_admins(["GADMIN".to_string()]);
+        let tokens = start_session(&state, "GADMIN").await.unwrap();
+
+        let state = state.with_admins([]);
+        let refreshed = refresh_session(&state, &tokens.refresh_token)
+            .await
+            .unwrap();
+        assert_eq!(claims(&refreshed.token).role, Role::User);
+    }
+
+    #[tokio::test]
+    async fn test_demotion_applies_to_issued_tokens() {
+        let state = state().with_admins(["GADMIN".to_string()]);
+        let tokens = start_session(&state, "GADMIN").await.unwrap();
+        let request = || {
+            Request::builder()
+                .header(header::AUTHORIZATION, format!("Bearer {}", tokens.token))
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+        assert_eq!(bearer_caller(&state, &request()).unwrap().role, Role::Admin);
+
+        let state = state.with_admins([]);
+        assert_eq!(claims(&tokens.token).role, Role::Admin);
+        assert_eq!(bearer_caller(&state, &request()).unwrap().role, Role::User);
+    }
+
+    #[test]
+    fn test_claims_without_role_default_to_user() {
+        let claims: Claims = serde_json::from_value(json!({
+            "sub": "GABC", "iss": "soroscope", "exp": 1, "iat": 0, "sid": "s"
+        }))
+        .unwrap();
+        assert_eq!(claims.role, Role::User);
+    }
+}
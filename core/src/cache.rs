@@ -0,0 +1,1096 @@
+use crate::simulation::SimulationResult;
+use async_trait::async_trait;
+use moka::future::Cache;
+use moka::notification::RemovalCause;
+use redb::{Database, ReadableTable, ReadableTableMetadata, TableDefinition};
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use utoipa::ToSchema;
+
+pub const DEFAULT_CACHE_TTL_SECS: u64 = 3_600;
+pub const DEFAULT_CACHE_CAPACITY: u64 = 1_000;
+
+const ENTRIES: TableDefinition<&str, &[u8]> = TableDefinition::new("simulations");
+/// Age index over `ENTRIES`, keyed by `(stored_at, key)` so the oldest rows
+/// come first.
+const AGES: TableDefinition<(u64, &str), ()> = TableDefinition::new("simulation_ages");
+
+/// Errors raised while opening or writing a cache backend
+#[derive(Error, Debug)]
+pub enum CacheError {
+    #[error("Cache storage error: {0}")]
+    Storage(String),
+
+    #[error("Cache serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[error("Redis error: {0}")]
+    Redis(#[from] redis::RedisError),
+
+    #[error("Unknown cache backend: {0} (expected memory, disk or redis)")]
+    UnknownBackend(String),
+}
+
+fn storage_error(e: impl Into<redb::Error>) -> CacheError {
+    CacheError::Storage(e.into().to_string())
+}
+
+/// Snapshot reported by `GET /cache/stats` and `soroscope-core cache stats`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CacheStats {
+    pub backend: String,
+    pub hits: u64,
+    pub misses: u64,
+    pub hit_rate_pct: f64,
+    pub entry_count: u64,
+    /// Approximate bytes held by the backend
+    pub weighted_size_bytes: u64,
+    /// Entries dropped for capacity or TTL; absent when the backend cannot tell
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub evictions: Option<u64>,
+    /// Entries dropped because their ledger state changed or were purged
+    pub invalidations: u64,
+    /// Simulations served from an identical request already in flight
+    #[serde(default)]
+    pub coalesced_requests: u64,
+}
+
+/// Capacity and freshness shared by every backend.
+#[derive(Debug, Clone, Copy)]
+pub struct CacheSettings {
+    pub capacity: u64,
+    pub ttl: Duration,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_CACHE_CAPACITY,
+            ttl: Duration::from_secs(DEFAULT_CACHE_TTL_SECS),
+        }
+    }
+}
+
+/// Storage behind [`crate::simulation::SimulationCache`].
+///
+/// Backends only store and expire entries; hit/miss accounting stays in the
+/// cache front so every backend reports the same way.
+#[async_trait]
+pub trait CacheBackend: Send + Sync {
+    /// Short name used in logs and stats
+    fn name(&self) -> &'static str;
+
+    async fn get(&self, key: &str) -> Option<SimulationResult>;
+
+    async fn set(&self, key: String, value: SimulationResult);
+
+    async fn entry_count(&self) -> u64;
+
+    /// Approximate bytes held by the backend
+    async fn weighted_size(&self) -> u64;
+
+    /// Entries dropped for capacity or TTL, when the backend can tell
+    fn evictions(&self) -> Option<u64>;
+
+    /// Remove one entry; returns whether it existed.
+    async fn remove(&self, key: &str) -> bool;
+
+    /// Remove every entry whose key starts with `prefix`; returns how many.
+    async fn remove_prefix(&self, prefix: &str) -> u64;
+
+    /// Record a lookup in counters visible to every instance sharing the
+    /// backend. Process-local backends leave this to the cache front.
+    async fn record_lookup(&self, _hit: bool) {}
+
+    /// `(hits, misses)` across all instances, if the backend tracks them.
+    async fn shared_counters(&self) -> Option<(u64, u64)> {
+        None
+    }
+}
+
+// ── In-memory (moka) ──────────────────────────────────────────────────────────
+
+/// Process-local cache; contents are lost on restart.
+pub struct MemoryCache {
+    inner: Cache<String, SimulationResult>,
+    bytes: Arc<AtomicU64>,
+    evictions: Arc<AtomicU64>,
+}
+
+impl MemoryCache {
+    pub fn new(settings: CacheSettings) -> Self {
+        let bytes = Arc::new(AtomicU64::new(0));
+        let evictions = Arc::new(AtomicU64::new(0));
+        let (listener_bytes, listener_evictions) = (Arc::clone(&bytes), Arc::clone(&evictions));
+        let inner = Cache::builder()
+            .max_capacity(settings.capacity)
+            .time_to_live(settings.ttl)
+            .eviction_listener(move |_key, value: SimulationResult, cause| {
+                listener_bytes.fetch_sub(encoded_len(&value), Ordering::Relaxed);
+                if matches!(cause, RemovalCause::Size | RemovalCause::Expired) {
+                    listener_evictions.fetch_add(1, Ordering::Relaxed);
+                }
+            })
+            .build();
+        Self {
+            inner,
+            bytes,
+            evictions,
+        }
+    }
+}
+
+/// Serialized size, used as the weight of an entry.
+fn encoded_len(value: &SimulationResult) -> u64 {
+    serde_json::to_vec(value)
+        .map(|v| v.len() as u64)
+        .unwrap_or(0)
+}
+
+#[async_trait]
+impl CacheBackend for MemoryCache {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    async fn get(&self, key: &str) -> Option<SimulationResult> {
+        self.inner.get(key).await
+    }
+
+    async fn set(&self, key: String, value: SimulationResult) {
+        self.bytes.fetch_add(encoded_len(&value), Ordering::Relaxed);
+        self.inner.insert(key, value).await;
+    }
+
+    async fn entry_count(&self) -> u64 {
+        self.inner.run_pending_tasks().await;
+        self.inner.entry_count()
+    }
+
+    async fn weighted_size(&self) -> u64 {
+        self.inner.run_pending_tasks().await;
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    fn evictions(&self) -> Option<u64> {
+        Some(self.evictions.load(Ordering::Relaxed))
+    }
+
+    async fn remove(&self, key: &str) -> bool {
+        self.inner.remove(key).await.is_some()
+    }
+
+    async fn remove_prefix(&self, prefix: &str) -> u64 {
+        let keys: Vec<Arc<String>> = self
+            .inner
+            .iter()
+            .filter(|(k, _)| k.starts_with(prefix))
+            .map(|(k, _)| k)
+            .collect();
+        for key in &keys {
+            self.inner.invalidate(key.as_str()).await;
+        }
+        keys.len() as u64
+    }
+}
+
+// ── On-disk (redb) ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredEntry {
+    /// Unix seconds at insertion; TTL is measured from here across restarts
+    stored_at: u64,
+    result: SimulationResult,
+}
+
+/// Insertion time of a serialized [`StoredEntry`]; undecodable rows count as
+/// oldest.
+fn stored_at(bytes: &[u8]) -> u64 {
+    serde_json::from_slice::<StoredEntry>(bytes)
+        .map(|e| e.stored_at)
+        .unwrap_or(0)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Embedded on-disk cache with an in-memory hot tier.
+///
+/// Every write goes to a redb file; on open the still-fresh entries are
+/// loaded back into memory so a restarted server starts warm.
+pub struct DiskCache {
+    db: Arc<Database>,
+    path: PathBuf,
+    hot: Cache<String, StoredEntry>,
+    settings: CacheSettings,
+    evictions: Arc<AtomicU64>,
+}
+
+impl DiskCache {
+    pub async fn open(path: impl AsRef<Path>, settings: CacheSettings) -> Result<Self, CacheError> {
+        let db = Database::create(path.as_ref()).map_err(storage_error)?;
+        // Make sure the tables exist so read transactions never fail on them.
+        let txn = db.begin_write().map_err(storage_error)?;
+        {
+            let entries = txn.open_table(ENTRIES).map_err(storage_error)?;
+            let mut ages = txn.open_table(AGES).map_err(storage_error)?;
+            // Files written before the age index existed need it rebuilt once.
+            if ages.len().map_err(storage_error)? != entries.len().map_err(storage_error)? {
+                ages.retain(|_, _| false).map_err(storage_error)?;
+                for row in entries.iter().map_err(storage_error)? {
+                    let (key, value) = row.map_err(storage_error)?;
+                    ages.insert((stored_at(value.value()), key.value()), ())
+                        .map_err(storage_error)?;
+                }
+            }
+        }
+        txn.commit().map_err(storage_error)?;
+
+        let cache = Self {
+            db: Arc::new(db),
+            path: path.as_ref().to_path_buf(),
+            hot: Cache::builder().max_capacity(settings.capacity).build(),
+            settings,
+            evictions: Arc::new(AtomicU64::new(0)),
+        };
+        let loaded = cache.warm_start().await?;
+        tracing::info!(
+            path = %path.as_ref().display(),
+            entries = loaded,
+            "Disk cache opened"
+        );
+        Ok(cache)
+    }
+
+    fn is_fresh(&self, entry: &StoredEntry) -> bool {
+        unix_now().saturating_sub(entry.stored_at) < self.settings.ttl.as_secs()
+    }
+
+    /// Drop expired entries from disk and load the newest fresh ones into memory.
+    async fn warm_start(&self) -> Result<usize, CacheError> {
+        let mut fresh = Vec::new();
+        let mut expired = Vec::new();
+        {
+            let txn = self.db.begin_read().map_err(storage_error)?;
+            let table = txn.open_table(ENTRIES).map_err(storage_error)?;
+            for row in table.iter().map_err(storage_error)? {
+                let (key, value) = row.map_err(storage_error)?;
+                match serde_json::from_slice::<StoredEntry>(value.value()) {
+                    Ok(entry) if self.is_fresh(&entry) => {
+                        fresh.push((key.value().to_string(), entry))
+                    }
+                    _ => expired.push(key.value().to_string()),
+                }
+            }
+        }
+        if !expired.is_empty() {
+            Self::remove_keys(&self.db, &expired)?;
+            self.evictions
+                .fetch_add(expired.len() as u64, Ordering::Relaxed);
+        }
+
+        fresh.sort_by_key(|(_, entry)| std::cmp::Reverse(entry.stored_at));
+        fresh.truncate(self.settings.capacity as usize);
+        let loaded = fresh.len();
+        for (key, entry) in fresh {
+            self.hot.insert(key, entry).await;
+        }
+        Ok(loaded)
+    }
+
+    fn keys_with_prefix(db: &Database, prefix: &str) -> Result<Vec<String>, CacheError> {
+        let txn = db.begin_read().map_err(storage_error)?;
+        let table = txn.open_table(ENTRIES).map_err(storage_error)?;
+        let mut keys = Vec::new();
+        for row in table.range(prefix..).map_err(storage_error)? {
+            let (key, _) = row.map_err(storage_error)?;
+            if !key.value().starts_with(prefix) {
+                break;
+            }
+            keys.push(key.value().to_string());
+        }
+        Ok(keys)
+    }
+
+    /// Delete `keys`; returns how many were present.
+    fn remove_keys(db: &Database, keys: &[String]) -> Result<u64, CacheError> {
+        let txn = db.begin_write().map_err(storage_error)?;
+        let mut removed = 0;
+        {
+            let mut table = txn.open_table(ENTRIES).map_err(storage_error)?;
+            let mut ages = txn.open_table(AGES).map_err(storage_error)?;
+            for key in keys {
+                if let Some(old) = table.remove(key.as_str()).map_err(storage_error)? {
+                    ages.remove((stored_at(old.value()), key.as_str()))
+                        .map_err(storage_error)?;
+                    removed += 1;
+                }
+            }
+        }
+        txn.commit().map_err(storage_error)?;
+        Ok(removed)
+    }
+
+    /// Persist `entry`, then evict the oldest rows if the file is over
+    /// capacity; returns the evicted keys.
+    fn write_entry(
+        db: &Database,
+        capacity: u64,
+        key: &str,
+        entry: &StoredEntry,
+    ) -> Result<Vec<String>, CacheError> {
+        let bytes = serde_json::to_vec(entry)?;
+        let txn = db.begin_write().map_err(storage_error)?;
+        let mut evicted = Vec::new();
+        {
+            let mut table = txn.open_table(ENTRIES).map_err(storage_error)?;
+            let mut ages = txn.open_table(AGES).map_err(storage_error)?;
+            if let Some(old) = table.insert(key, bytes.as_slice()).map_err(storage_error)? {
+                ages.remove((stored_at(old.value()), key))
+                    .map_err(storage_error)?;
+            }
+            ages.insert((entry.stored_at, key), ())
+                .map_err(storage_error)?;
+
+            while table.len().map_err(storage_error)? > capacity {
+                let Some((oldest, _)) = ages.pop_first().map_err(storage_error)? else {
+                    break;
+                };
+                let (_, old_key) = oldest.value();
+                table.remove(old_key).map_err(storage_error)?;
+                evicted.push(old_key.to_string());
+            }
+        }
+        txn.commit().map_err(storage_error)?;
+        Ok(evicted)
+    }
+
+    fn read_entry(db: &Database, key: &str) -> Result<Option<StoredEntry>, CacheError> {
+        let txn = db.begin_read().map_err(storage_error)?;
+        let table = txn.open_table(ENTRIES).map_err(storage_error)?;
+        match table.get(key).map_err(storage_error)? {
+            Some(value) => Ok(Some(serde_json::from_slice(value.value())?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn put_entry(&self, key: String, entry: StoredEntry) {
+        self.hot.insert(key.clone(), entry.clone()).await;
+        let db = Arc::clone(&self.db);
+        let capacity = self.settings.capacity;
+        let written =
+            tokio::task::spawn_blocking(move || Self::write_entry(&db, capacity, &key, &entry))
+                .await;
+        match written {
+            Ok(Ok(evicted)) => {
+                for key in &evicted {
+                    self.hot.invalidate(key.as_str()).await;
+                }
+                self.evictions
+                    .fetch_add(evicted.len() as u64, Ordering::Relaxed);
+            }
+            Ok(Err(e)) => tracing::warn!(error = %e, "Failed to persist cache entry"),
+            Err(e) => tracing::warn!(error = %e, "Cache write task failed"),
+        }
+    }
+}
+
+#[async_trait]
+impl CacheBackend for DiskCache {
+    fn name(&self) -> &'static str {
+        "disk"
+    }
+
+    async fn get(&self, key: &str) -> Option<SimulationResult> {
+        let entry = match self.hot.get(key).await {
+            Some(entry) => Some(entry),
+            None => {
+                let db = Arc::clone(&self.db);
+                let owned = key.to_string();
+                match tokio::task::spawn_blocking(move || Self::read_entry(&db, &owned)).await {
+                    Ok(Ok(entry)) => entry,
+                    Ok(Err(e)) => {
+                        tracing::warn!(error = %e, "Failed to read cache entry");
+                        None
+                    }
+                    Err(e) => {
+                        tracing::warn!(error = %e, "Cache read task failed");
+                        None
+                    }
+                }
+            }
+        }?;
+
+        if !self.is_fresh(&entry) {
+            if self.remove(key).await {
+                self.evictions.fetch_add(1, Ordering::Relaxed);
+            }
+            return None;
+        }
+        self.hot.insert(key.to_string(), entry.clone()).await;
+        Some(entry.result)
+    }
+
+    async fn set(&self, key: String, value: SimulationResult) {
+        let entry = StoredEntry {
+            stored_at: unix_now(),
+            result: value,
+        };
+        self.put_entry(key, entry).await;
+    }
+
+    async fn entry_count(&self) -> u64 {
+        let db = Arc::clone(&self.db);
+        let counted = tokio::task::spawn_blocking(move || {
+            db.begin_read()
+                .map_err(storage_error)?
+                .open_table(ENTRIES)
+                .map_err(storage_error)?
+                .len()
+                .map_err(storage_error)
+        })
+        .await;
+        match counted {
+            Ok(Ok(len)) => len,
+            Ok(Err(e)) => {
+                tracing::warn!(error = %e, "Failed to count cache entries");
+                0
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "Cache count task failed");
+                0
+            }
+        }
+    }
+
+    async fn weighted_size(&self) -> u64 {
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || std::fs::metadata(path).map(|m| m.len()).unwrap_or(0))
+            .await
+            .unwrap_or(0)
+    }
+
+    fn evictions(&self) -> Option<u64> {
+        Some(self.evictions.load(Ordering::Relaxed))
+    }
+
+    async fn remove(&self, key: &str) -> bool {
+        self.hot.invalidate(key).await;
+        let db = Arc::clone(&self.db);
+        let keys = vec![key.to_string()];
+        match tokio::task::spawn_blocking(move || Self::remove_keys(&db, &keys)).await {
+            Ok(Ok(removed)) => removed > 0,
+            Ok(Err(e)) => {
+                tracing::warn!(error = %e, "Failed to remove cache entry");
+                false
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "Cache remove task failed");
+                false
+            }
+        }
+    }
+
+    async fn remove_prefix(&self, prefix: &str) -> u64 {
+        let db = Arc::clone(&self.db);
+        let owned = prefix.to_string();
+        let removed = tokio::task::spawn_blocking(move || {
+            let keys = Self::keys_with_prefix(&db, &owned)?;
+            Self::remove_keys(&db, &keys)?;
+            Ok::<_, CacheError>(keys)
+        })
+        .await;
+        let keys = match removed {
+            Ok(Ok(keys)) => keys,
+            Ok(Err(e)) => {
+                tracing::warn!(error = %e, "Failed to remove cache entries");
+                return 0;
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "Cache remove task failed");
+                return 0;
+            }
+        };
+        for key in &keys {
+            self.hot.invalidate(key.as_str()).await;
+        }
+        keys.len() as u64
+    }
+}
+
+// ── Redis ───────────────────────────────────────────────────────────────────
+
+const REDIS_ENTRY_PREFIX: &str = "soroscope:sim:";
+const REDIS_HITS_KEY: &str = "soroscope:stats:hits";
+const REDIS_MISSES_KEY: &str = "soroscope:stats:misses";
+
+/// Distributed cache shared by every SoroScope instance pointing at the same
+/// Redis. Entries expire through Redis `EX`; capacity is left to the server's
+/// `maxmemory` policy.
+pub struct RedisCache {
+    conn: ConnectionManager,
+    ttl_secs: u64,
+}
+
+impl RedisCache {
+    pub async fn connect(url: &str, settings: CacheSettings) -> Result<Self, CacheError> {
+        let client = redis::Client::open(url)?;
+        let conn = ConnectionManager::new(client).await?;
+        tracing::info!(redis_url = %url, "Connected to Redis cache");
+        Ok(Self {
+            conn,
+            ttl_secs: settings.ttl.as_secs().max(1),
+        })
+    }
+
+    fn entry_key(key: &str) -> String {
+        format!("{}{}", REDIS_ENTRY_PREFIX, key)
+    }
+
+    /// Full Redis keys of the entries whose cache key starts with `prefix`.
+    async fn scan_keys(&self, prefix: &str) -> Result<Vec<String>, CacheError> {
+        let mut conn = self.conn.clone();
+        let pattern = format!("{}*", escape_glob(&Self::entry_key(prefix)));
+        let mut cursor: u64 = 0;
+        let mut keys = Vec::new();
+        loop {
+            let (next, page): (u64, Vec<String>) = redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(&pattern)
+                .arg("COUNT")
+                .arg(1_000)
+                .query_async(&mut conn)
+                .await?;
+            keys.extend(page);
+            if next == 0 {
+                return Ok(keys);
+            }
+            cursor = next;
+        }
+    }
+}
+
+/// Escape the `SCAN MATCH` glob metacharacters in `literal`.
+fn escape_glob(literal: &str) -> String {
+    let mut escaped = String::with_capacity(literal.len());
+    for c in literal.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[async_trait]
+impl CacheBackend for RedisCache {
+    fn name(&self) -> &'static str {
+        "redis"
+    }
+
+    async fn get(&self, key: &str) -> Option<SimulationResult> {
+        let mut conn = self.conn.clone();
+        let raw: Option<String> = match conn.get(Self::entry_key(key)).await {
+            Ok(raw) => raw,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to read cache entry from Redis");
+                return None;
+            }
+        };
+        raw.and_then(|json| match serde_json::from_str(&json) {
+            Ok(result) => Some(result),
+            Err(e) => {
+                tracing::warn!(error = %e, "Discarding undecodable Redis cache entry");
+                None
+            }
+        })
+    }
+
+    async fn set(&self, key: String, value: SimulationResult) {
+        let json = match serde_json::to_string(&value) {
+            Ok(json) => json,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to serialize cache entry");
+                return;
+            }
+        };
+        let mut conn = self.conn.clone();
+        let stored: Result<(), _> = conn
+            .set_ex(Self::entry_key(&key), json, self.ttl_secs)
+            .await;
+        if let Err(e) = stored {
+            tracing::warn!(error = %e, "Failed to write cache entry to Redis");
+        }
+    }
+
+    async fn entry_count(&self) -> u64 {
+        match self.scan_keys("").await {
+            Ok(keys) => keys.len() as u64,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to count Redis cache entries");
+                0
+            }
+        }
+    }
+
+    async fn weighted_size(&self) -> u64 {
+        let keys = match self.scan_keys("").await {
+            Ok(keys) if keys.is_empty() => return 0,
+            Ok(keys) => keys,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to list Redis cache entries");
+                return 0;
+            }
+        };
+        let mut pipe = redis::pipe();
+        for key in &keys {
+            pipe.strlen(key);
+        }
+        let mut conn = self.conn.clone();
+        let sizes: Result<Vec<u64>, _> = pipe.query_async(&mut conn).await;
+        match sizes {
+            Ok(sizes) => sizes.iter().sum(),
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to size Redis cache entries");
+                0
+            }
+        }
+    }
+
+    /// Expiry and `maxmemory` eviction happen inside Redis and are not
+    /// attributed per key prefix.
+    fn evictions(&self) -> Option<u64> {
+        None
+    }
+
+    async fn remove(&self, key: &str) -> bool {
+        let mut conn = self.conn.clone();
+        match conn.del::<_, u64>(Self::entry_key(key)).await {
+            Ok(removed) => removed > 0,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to remove Redis cache entry");
+                false
+            }
+        }
+    }
+
+    async fn remove_prefix(&self, prefix: &str) -> u64 {
+        let keys = match self.scan_keys(prefix).await {
+            Ok(keys) if keys.is_empty() => return 0,
+            Ok(keys) => keys,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to list Redis cache entries");
+                return 0;
+            }
+        };
+        let mut conn = self.conn.clone();
+        match conn.del::<_, u64>(&keys).await {
+            Ok(removed) => removed,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to remove Redis cache entries");
+                0
+            }
+        }
+    }
+
+    async fn record_lookup(&self, hit: bool) {
+        let counter = if hit {
+            REDIS_HITS_KEY
+        } else {
+            REDIS_MISSES_KEY
+        };
+        let mut conn = self.conn.clone();
+        let incremented: Result<u64, _> = conn.incr(counter, 1).await;
+        if let Err(e) = incremented {
+            tracing::warn!(error = %e, "Failed to update shared cache counters");
+        }
+    }
+
+    async fn shared_counters(&self) -> Option<(u64, u64)> {
+        let mut conn = self.conn.clone();
+        let counters: Result<(Option<u64>, Option<u64>), _> = redis::pipe()
+            .get(REDIS_HITS_KEY)
+            .get(REDIS_MISSES_KEY)
+            .query_async(&mut conn)
+            .await;
+        match counters {
+            Ok((hits, misses)) => Some((hits.unwrap_or(0), misses.unwrap_or(0))),
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to read shared cache counters");
+                None
+            }
+        }
+    }
+}
+
+/// Build the backend named by `kind` (`memory`, `disk` or `redis`).
+pub async fn open_backend(
+    kind: &str,
+    path: &str,
+    redis_url: &str,
+    settings: CacheSettings,
+) -> Result<Box<dyn CacheBackend>, CacheError> {
+    match kind {
+        "memory" => Ok(Box::new(MemoryCache::new(settings))),
+        "disk" => Ok(Box::new(DiskCache::open(path, settings).await?)),
+        "redis" => Ok(Box::new(RedisCache::connect(redis_url, settings).await?)),
+        other => Err(CacheError::UnknownBackend(other.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::SorobanResources;
+
+    fn make_result(cpu: u64) -> SimulationResult {
+        SimulationResult {
+            resources: SorobanResources {
+                cpu_instructions: cpu,
+                ..Default::default()
+            },
+            transaction_hash: None,
+            latest_ledger: 42,
+            cost_stroops: 10,
+            state_dependency: None,
+            headroom: None,
+            footprint: None,
+            cost_breakdown: None,
+            provider: None,
+            ledger_lag: None,
+        }
+    }
+
+    fn settings(capacity: u64) -> CacheSettings {
+        CacheSettings {
+            capacity,
+            ttl: Duration::from_secs(60),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory_backend_roundtrip() {
+        let cache = MemoryCache::new(settings(10));
+        assert!(cache.get("k").await.is_none());
+        cache.set("k".to_string(), make_result(7)).await;
+        assert_eq!(cache.get("k").await.unwrap().resources.cpu_instructions, 7);
+        assert_eq!(cache.entry_count().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_memory_backend_tracks_size_and_evictions() {
+        let cache = MemoryCache::new(settings(1));
+        cache.set("a".to_string(), make_result(1)).await;
+        let one_entry = cache.weighted_size().await;
+        assert!(one_entry > 0);
+
+        assert!(cache.remove("a").await);
+        assert!(!cache.remove("a").await);
+        assert_eq!(cache.weighted_size().await, 0);
+        assert_eq!(cache.evictions(), Some(0));
+
+        for key in ["b", "c", "d"] {
+            cache.set(key.to_string(), make_result(1)).await;
+            cache.entry_count().await;
+        }
+        assert_eq!(cache.entry_count().await, 1);
+        assert_eq!(cache.evictions(), Some(2));
+        assert_eq!(cache.weighted_size().await, one_entry);
+    }
+
+    #[tokio::test]
+    async fn test_disk_backend_warm_start() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.redb");
+        {
+            let cache = DiskCache::open(&path, settings(10)).await.unwrap();
+            cache.set("k".to_string(), make_result(7)).await;
+        }
+
+        let reopened = DiskCache::open(&path, settings(10)).await.unwrap();
+        assert_eq!(
+            reopened.hot.get("k").await.map(|e| e.stored_at > 0),
+            Some(true)
+        );
+        assert_eq!(
+            reopened.get("k").await.unwrap().resources.cpu_instructions,
+            7
+        );
+    }
+
+    #[tokio::test]
+    async fn test_disk_backend_drops_expired_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.redb");
+        {
+            let cache = DiskCache::open(&path, settings(10)).await.unwrap();
+            let stale = StoredEntry {
+                stored_at: unix_now() - 120,
+                result: make_result(1),
+            };
+            cache.put_entry("stale".to_string(), stale).await;
+            assert!(cache.get("stale").await.is_none());
+        }
+
+        let reopened = DiskCache::open(&path, settings(10)).await.unwrap();
+        assert!(reopened.get("stale").await.is_none());
+        assert_eq!(reopened.entry_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_disk_backend_evicts_oldest_over_capacity() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.redb");
+        let cache = DiskCache::open(&path, settings(2)).await.unwrap();
+        let now = unix_now();
+        for (i, key) in ["a", "b", "c"].iter().enumerate() {
+            let entry = StoredEntry {
+                stored_at: now - 30 + i as u64,
+                result: make_result(i as u64),
+            };
+            cache.put_entry(key.to_string(), entry).await;
+        }
+        assert_eq!(cache.entry_count().await, 2);
+        assert_eq!(cache.evictions(), Some(1));
+        assert!(cache.weighted_size().await > 0);
+        drop(cache);
+
+        let reopened = DiskCache::open(&path, settings(2)).await.unwrap();
+        assert!(reopened.get("a").await.is_none());
+        assert!(reopened.get("c").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_disk_backend_rewrite_refreshes_age() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DiskCache::open(dir.path().join("cache.redb"), settings(2))
+            .await
+            .unwrap();
+        let now = unix_now();
+        for (age, key) in [(30, "a"), (20, "b"), (10, "a"), (0, "c")] {
+            let entry = StoredEntry {
+                stored_at: now - age,
+                result: make_result(age),
+            };
+            cache.put_entry(key.to_string(), entry).await;
+        }
+
+        // "a" was rewritten after "b", so "b" is the oldest and goes first.
+        assert_eq!(cache.entry_count().await, 2);
+        assert_eq!(cache.evictions(), Some(1));
+        assert_eq!(cache.get("a").await.unwrap().resources.cpu_instructions, 10);
+        assert!(cache.get("b").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_disk_backend_remove_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DiskCache::open(dir.path().join("cache.redb"), settings(10))
+            .await
+            .unwrap();
+        cache.set("CA:1".to_string(), make_result(1)).await;
+        cache.set("CA:2".to_string(), make_result(2)).await;
+        cache.set("CB:1".to_string(), make_result(3)).await;
+
+        assert_eq!(cache.remove_prefix("CA:").await, 2);
+        assert!(cache.get("CA:1").await.is_none());
+        assert!(cache.get("CB:1").await.is_some());
+        assert_eq!(cache.entry_count().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_open_backend_rejects_unknown_kind() {
+        let result = open_backend("floppy", "unused", "unused", settings(1)).await;
+        assert!(matches!(result, Err(CacheError::UnknownBackend(_))));
+    }
+
+    // ── Redis via an in-process RESP stand-in ────────────────────────────────
+
+    mod resp {
+        use std::collections::HashMap;
+        use std::sync::{Arc, Mutex};
+        use std::time::{Duration, Instant};
+        use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+        use tokio::net::{TcpListener, TcpStream};
+
+        type Store = Arc<Mutex<HashMap<String, (Vec<u8>, Option<Instant>)>>>;
+
+        /// Serve just enough RESP2 for `RedisCache`; returns a `redis://` URL.
+        pub async fn spawn_stand_in() -> String {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let store = Store::default();
+            tokio::spawn(async move {
+                while let Ok((socket, _)) = listener.accept().await {
+                    tokio::spawn(serve(socket, Arc::clone(&store)));
+                }
+            });
+            format!("redis://{}", addr)
+        }
+
+        async fn serve(socket: TcpStream, store: Store) {
+            let (reader, mut writer) = socket.into_split();
+            let mut reader = BufReader::new(reader);
+            while let Some(cmd) = read_command(&mut reader).await {
+                let reply = execute(&cmd, &store);
+                if writer.write_all(&reply).await.is_err() {
+                    return;
+                }
+            }
+        }
+
+        async fn read_command<R: AsyncBufReadExt + Unpin>(reader: &mut R) -> Option<Vec<Vec<u8>>> {
+            let mut line = String::new();
+            if reader.read_line(&mut line).await.ok()? == 0 {
+                return None;
+            }
+            let count: usize = line.trim_end().strip_prefix('*')?.parse().ok()?;
+            let mut parts = Vec::with_capacity(count);
+            for _ in 0..count {
+                line.clear();
+                reader.read_line(&mut line).await.ok()?;
+                let len: usize = line.trim_end().strip_prefix('$')?.parse().ok()?;
+                let mut buf = vec![0; len + 2];
+                reader.read_exact(&mut buf).await.ok()?;
+                buf.truncate(len);
+                parts.push(buf);
+            }
+            Some(parts)
+        }
+
+        fn bulk(value: &[u8]) -> Vec<u8> {
+            let mut out = format!("${}\r\n", value.len()).into_bytes();
+            out.extend_from_slice(value);
+            out.extend_from_slice(b"\r\n");
+            out
+        }
+
+        fn execute(cmd: &[Vec<u8>], store: &Store) -> Vec<u8> {
+            let arg = |i: usize| String::from_utf8_lossy(&cmd[i]).to_string();
+            let mut store = store.lock().unwrap();
+            let now = Instant::now();
+            store.retain(|_, (_, expires)| expires.is_none_or(|at| at > now));
+
+            match arg(0).to_uppercase().as_str() {
+                "PING" => b"+PONG\r\n".to_vec(),
+                "GET" => match store.get(&arg(1)) {
+                    Some((value, _)) => bulk(value),
+                    None => b"$-1\r\n".to_vec(),
+                },
+                "SETEX" => {
+                    let ttl = Duration::from_secs(arg(2).parse().unwrap());
+                    store.insert(arg(1), (cmd[3].clone(), Some(now + ttl)));
+                    b"+OK\r\n".to_vec()
+                }
+                "INCRBY" => {
+                    let current: i64 = store
+                        .get(&arg(1))
+                        .map(|(v, _)| String::from_utf8_lossy(v).parse().unwrap())
+                        .unwrap_or(0);
+                    let next = current + arg(2).parse::<i64>().unwrap();
+                    store.insert(arg(1), (next.to_string().into_bytes(), None));
+                    format!(":{}\r\n", next).into_bytes()
+                }
+                "STRLEN" => {
+                    let len = store.get(&arg(1)).map_or(0, |(v, _)| v.len());
+                    format!(":{}\r\n", len).into_bytes()
+                }
+                "DEL" => {
+                    let removed = cmd[1..]
+                        .iter()
+                        .filter(|k| store.remove(&*String::from_utf8_lossy(k)).is_some())
+                        .count();
+                    format!(":{}\r\n", removed).into_bytes()
+                }
+                "SCAN" => {
+                    // Only `<escaped literal>*` patterns are supported.
+                    let pattern = arg(3);
+                    let mut prefix = String::new();
+                    let mut chars = pattern.strip_suffix('*').unwrap().chars();
+                    while let Some(c) = chars.next() {
+                        prefix.push(if c == '\\' { chars.next().unwrap() } else { c });
+                    }
+                    let keys: Vec<&String> =
+                        store.keys().filter(|k| k.starts_with(&prefix)).collect();
+                    let mut out = b"*2\r\n$1\r\n0\r\n".to_vec();
+                    out.extend(format!("*{}\r\n", keys.len()).into_bytes());
+                    for key in keys {
+                        out.extend(bulk(key.as_bytes()));
+                    }
+                    out
+                }
+                other => format!("-ERR unknown command '{}'\r\n", other).into_bytes(),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_redis_backend_roundtrip_and_count() {
+        let url = resp::spawn_stand_in().await;
+        let cache = RedisCache::connect(&url, settings(10)).await.unwrap();
+        assert!(cache.get("k").await.is_none());
+
+        cache.set("k".to_string(), make_result(7)).await;
+        assert_eq!(cache.get("k").await.unwrap().resources.cpu_instructions, 7);
+        assert_eq!(cache.entry_count().await, 1);
+
+        cache.set("CA:1".to_string(), make_result(1)).await;
+        cache.set("CA:2".to_string(), make_result(2)).await;
+        assert_eq!(cache.remove_prefix("CA:").await, 2);
+        cache.remove("k").await;
+        assert_eq!(cache.entry_count().await, 0);
+    }
+
+    #[test]
+    fn test_escape_glob_metacharacters() {
+        assert_eq!(escape_glob("soroscope:sim:CA"), "soroscope:sim:CA");
+        assert_eq!(escape_glob(r"a*b?c[d]e\f"), r"a\*b\?c\[d\]e\\f");
+    }
+
+    #[tokio::test]
+    async fn test_redis_backend_honours_ttl() {
+        let url = resp::spawn_stand_in().await;
+        let short = CacheSettings {
+            capacity: 10,
+            ttl: Duration::from_secs(1),
+        };
+        let cache = RedisCache::connect(&url, short).await.unwrap();
+        cache.set("k".to_string(), make_result(7)).await;
+        assert!(cache.get("k").await.is_some());
+
+        tokio::time::sleep(Duration::from_millis(1_100)).await;
+        assert!(cache.get("k").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_redis_counters_shared_across_instances() {
+        use crate::simulation::SimulationCache;
+
+        let url = resp::spawn_stand_in().await;
+        let first = SimulationCache::with_backend(Box::new(
+            RedisCache::connect(&url, settings(10)).await.unwrap(),
+        ));
+        let second = SimulationCache::with_backend(Box::new(
+            RedisCache::connect(&url, settings(10)).await.unwrap(),
+        ));
+
+        assert!(first.get("k").await.is_none());
+        first.set("k".to_string(), make_result(7)).await;
+        // Written by one instance, served by the other.
+        assert!(second.get("k").await.is_some());
+
+        assert_eq!(first.counters().await, (1, 1));
+        assert_eq!(second.counters().await, (1, 1));
+    }
+}
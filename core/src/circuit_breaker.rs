@@ -0,0 +1,447 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+/// Default number of consecutive failures before a provider is tripped.
+pub const DEFAULT_FAILURE_THRESHOLD: u64 = 3;
+
+/// Default time a tripped provider is excluded before it is tried again.
+pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(5 * 60); // 5 minutes
+
+/// Default cap for the cooldown after repeated trips.
+pub const DEFAULT_MAX_COOLDOWN: Duration = Duration::from_secs(60 * 60); // 1 hour
+
+/// Default number of trial requests admitted while half-open.
+pub const DEFAULT_HALF_OPEN_TRIALS: u32 = 1;
+
+/// Tunables for a [`CircuitBreaker`].
+#[derive(Debug, Clone, Copy)]
+pub struct BreakerConfig {
+    /// Consecutive failures that open a closed breaker.
+    pub failure_threshold: u64,
+    /// Cooldown after the first trip; doubles on every trip until the
+    /// breaker closes again.
+    pub cooldown: Duration,
+    /// Upper bound for the doubled cooldown.
+    pub max_cooldown: Duration,
+    /// Trial requests admitted while half-open. All of them must succeed to
+    /// close the breaker; any failure opens it again.
+    pub half_open_trials: u32,
+}
+
+impl Default for BreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            cooldown: DEFAULT_COOLDOWN,
+            max_cooldown: DEFAULT_MAX_COOLDOWN,
+            half_open_trials: DEFAULT_HALF_OPEN_TRIALS,
+        }
+    }
+}
+
+/// Position of a breaker in its state machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Requests flow normally.
+    Closed,
+    /// Requests are rejected until the cooldown elapses.
+    Open,
+    /// A limited number of trial requests decide whether to close or reopen.
+    HalfOpen,
+}
+
+/// How [`CircuitBreaker::admit`] let a request through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Admission {
+    /// The breaker is closed.
+    Normal,
+    /// The request holds one of the half-open trial slots.
+    Trial,
+}
+
+/// Count of state transitions, by target state.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, ToSchema)]
+pub struct BreakerTransitions {
+    pub opened: u64,
+    pub half_opened: u64,
+    pub closed: u64,
+}
+
+impl std::ops::AddAssign for BreakerTransitions {
+    fn add_assign(&mut self, other: Self) {
+        self.opened += other.opened;
+        self.half_opened += other.half_opened;
+        self.closed += other.closed;
+    }
+}
+
+/// Point-in-time view of a breaker.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct BreakerSnapshot {
+    pub state: CircuitState,
+    pub consecutive_failures: u64,
+    /// Trips since the breaker last closed; drives the cooldown backoff.
+    pub consecutive_trips: u32,
+    /// Seconds until an open breaker admits trial requests.
+    pub cooldown_remaining_secs: Option<u64>,
+    pub transitions: BreakerTransitions,
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: CircuitState,
+    consecutive_failures: u64,
+    consecutive_trips: u32,
+    opened_at: Option<Instant>,
+    cooldown: Duration,
+    trials_admitted: u32,
+    trial_successes: u32,
+    transitions: BreakerTransitions,
+}
+
+/// Closed/Open/HalfOpen circuit breaker for a single provider.
+///
+/// Transitions are logged under the provider's name and counted so they can
+/// be reported as metrics.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    name: String,
+    config: BreakerConfig,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    pub fn new(name: impl Into<String>, config: BreakerConfig) -> Self {
+        Self {
+            name: name.into(),
+            config,
+            inner: Mutex::new(Inner {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                consecutive_trips: 0,
+                opened_at: None,
+                cooldown: config.cooldown,
+                trials_admitted: 0,
+                trial_successes: 0,
+                transitions: BreakerTransitions::default(),
+            }),
+        }
+    }
+
+    /// Current state, moving an open breaker to half-open once its cooldown
+    /// has elapsed.
+    pub fn state(&self) -> CircuitState {
+        let mut inner = self.lock();
+        self.poll(&mut inner);
+        inner.state
+    }
+
+    /// Whether a request could be admitted right now, without claiming a
+    /// half-open trial.
+    pub fn is_available(&self) -> bool {
+        let mut inner = self.lock();
+        self.poll(&mut inner);
+        match inner.state {
+            CircuitState::Closed => true,
+            CircuitState::Open => false,
+            CircuitState::HalfOpen => inner.trials_admitted < self.config.half_open_trials,
+        }
+    }
+
+    /// Admit a request, claiming a trial slot when half-open. Returns `None`
+    /// if the request must not be sent. The outcome is reported with the
+    /// admission through [`Self::on_success`] or [`Self::on_failure`]; a trial
+    /// slot without an outcome is handed back with [`Self::release_trial`].
+    pub fn admit(&self) -> Option<Admission> {
+        let mut inner = self.lock();
+        self.poll(&mut inner);
+        match inner.state {
+            CircuitState::Closed => Some(Admission::Normal),
+            CircuitState::Open => None,
+            CircuitState::HalfOpen => {
+                if inner.trials_admitted < self.config.half_open_trials {
+                    inner.trials_admitted += 1;
+                    Some(Admission::Trial)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Hand back a trial slot whose request was abandoned before it produced
+    /// an outcome, e.g. because it was cancelled.
+    pub fn release_trial(&self) {
+        let mut inner = self.lock();
+        if inner.state == CircuitState::HalfOpen && inner.trials_admitted > inner.trial_successes {
+            inner.trials_admitted -= 1;
+        }
+    }
+
+    /// Record a successful request admitted as `admission`. While half-open,
+    /// a successful trial counts towards closing the breaker.
+    pub fn on_success(&self, admission: Admission) {
+        let mut inner = self.lock();
+        inner.consecutive_failures = 0;
+        // Successes of requests sent before the trip don't end it early.
+        if inner.state != CircuitState::HalfOpen || admission != Admission::Trial {
+            return;
+        }
+        inner.trial_successes += 1;
+        if inner.trial_successes >= self.config.half_open_trials {
+            self.close(&mut inner);
+        }
+    }
+
+    /// Record a failed request admitted as `admission`, opening the breaker
+    /// when the threshold is reached or a half-open trial fails.
+    pub fn on_failure(&self, admission: Admission) {
+        let mut inner = self.lock();
+        inner.consecutive_failures += 1;
+        match inner.state {
+            CircuitState::Closed if inner.consecutive_failures >= self.config.failure_threshold => {
+                self.open(&mut inner)
+            }
+            CircuitState::HalfOpen if admission == Admission::Trial => self.open(&mut inner),
+            // Failures of requests sent before the trip don't extend it.
+            CircuitState::Closed | CircuitState::Open | CircuitState::HalfOpen => {}
+        }
+    }
+
+    /// Force the breaker open, e.g. by an operator. The cooldown backs off as
+    /// for any other trip.
+    pub fn trip(&self) {
+        let mut inner = self.lock();
+        if inner.state != CircuitState::Open {
+            self.open(&mut inner);
+        }
+    }
+
+    pub fn snapshot(&self) -> BreakerSnapshot {
+        let mut inner = self.lock();
+        self.poll(&mut inner);
+        let cooldown_remaining_secs = match (inner.state, inner.opened_at) {
+            (CircuitState::Open, Some(at)) => {
+                Some(inner.cooldown.saturating_sub(at.elapsed()).as_secs())
+            }
+            _ => None,
+        };
+        BreakerSnapshot {
+            state: inner.state,
+            consecutive_failures: inner.consecutive_failures,
+            consecutive_trips: inner.consecutive_trips,
+            cooldown_remaining_secs,
+            transitions: inner.transitions,
+        }
+    }
+
+    pub fn transitions(&self) -> BreakerTransitions {
+        self.lock().transitions
+    }
+
+    // ── Transitions ───────────────────────────────────────────────────────
+
+    fn poll(&self, inner: &mut Inner) {
+        if inner.state != CircuitState::Open {
+            return;
+        }
+        if inner
+            .opened_at
+            .is_some_and(|at| at.elapsed() >= inner.cooldown)
+        {
+            inner.state = CircuitState::HalfOpen;
+            inner.trials_admitted = 0;
+            inner.trial_successes = 0;
+            inner.transitions.half_opened += 1;
+            tracing::info!(
+                provider = %self.name,
+                trials = self.config.half_open_trials,
+                "Circuit breaker HALF-OPEN — admitting trial requests"
+            );
+        }
+    }
+
+    fn open(&self, inner: &mut Inner) {
+        // Double the cooldown for every trip since the breaker last closed.
+        let factor = 2u32.saturating_pow(inner.consecutive_trips);
+        inner.cooldown = self
+            .config
+            .cooldown
+            .saturating_mul(factor)
+            .min(self.config.max_cooldown);
+        inner.consecutive_trips += 1;
+        inner.state = CircuitState::Open;
+        inner.opened_at = Some(Instant::now());
+        inner.transitions.opened += 1;
+        tracing::warn!(
+            provider = %self.name,
+            failures = inner.consecutive_failures,
+            trips = inner.consecutive_trips,
+            "Circuit breaker OPEN — provider excluded for {:?}",
+            inner.cooldown
+        );
+    }
+
+    fn close(&self, inner: &mut Inner) {
+        inner.state = CircuitState::Closed;
+        inner.consecutive_failures = 0;
+        inner.consecutive_trips = 0;
+        inner.opened_at = None;
+        inner.cooldown = self.config.cooldown;
+        inner.transitions.closed += 1;
+        tracing::info!(provider = %self.name, "Circuit breaker CLOSED — provider recovered");
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker(cooldown_ms: u64, trials: u32) -> CircuitBreaker {
+        CircuitBreaker::new(
+            "test",
+            BreakerConfig {
+                failure_threshold: 2,
+                cooldown: Duration::from_millis(cooldown_ms),
+                max_cooldown: Duration::from_millis(cooldown_ms * 3),
+                half_open_trials: trials,
+            },
+        )
+    }
+
+    fn trip(breaker: &CircuitBreaker) {
+        breaker.on_failure(Admission::Normal);
+        breaker.on_failure(Admission::Normal);
+    }
+
+    #[test]
+    fn test_opens_after_threshold() {
+        let breaker = breaker(60_000, 1);
+        breaker.on_failure(Admission::Normal);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        breaker.on_failure(Admission::Normal);
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(breaker.admit().is_none());
+        assert_eq!(breaker.transitions().opened, 1);
+    }
+
+    #[test]
+    fn test_half_open_admits_limited_trials() {
+        let breaker = breaker(10, 2);
+        trip(&breaker);
+        std::thread::sleep(Duration::from_millis(20));
+
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(breaker.admit().is_some());
+        assert!(breaker.admit().is_some());
+        assert!(breaker.admit().is_none());
+        assert!(!breaker.is_available());
+
+        // Every trial must pass before the breaker closes.
+        breaker.on_success(Admission::Trial);
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        breaker.on_success(Admission::Trial);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        let transitions = breaker.transitions();
+        assert_eq!((transitions.half_opened, transitions.closed), (1, 1));
+    }
+
+    #[test]
+    fn test_released_trial_can_be_claimed_again() {
+        let breaker = breaker(10, 1);
+        trip(&breaker);
+        std::thread::sleep(Duration::from_millis(20));
+
+        assert_eq!(breaker.admit(), Some(Admission::Trial));
+        assert_eq!(breaker.admit(), None);
+        breaker.release_trial();
+        assert_eq!(breaker.admit(), Some(Admission::Trial));
+
+        // Releasing while closed has nothing to give back.
+        breaker.on_success(Admission::Trial);
+        breaker.release_trial();
+        assert_eq!(breaker.admit(), Some(Admission::Normal));
+    }
+
+    #[test]
+    fn test_failed_trial_reopens_with_backoff() {
+        let breaker = breaker(10, 1);
+        trip(&breaker);
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(breaker.admit().is_some());
+
+        breaker.on_failure(Admission::Trial);
+        let snapshot = breaker.snapshot();
+        assert_eq!(snapshot.state, CircuitState::Open);
+        assert_eq!(snapshot.consecutive_trips, 2);
+
+        // The second cooldown is twice as long, so 15ms is not enough.
+        std::thread::sleep(Duration::from_millis(15));
+        assert_eq!(breaker.state(), CircuitState::Open);
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+    }
+
+    #[test]
+    fn test_cooldown_is_capped() {
+        let breaker = breaker(10, 1);
+        for _ in 0..5 {
+            breaker.trip();
+            std::thread::sleep(Duration::from_millis(35));
+            assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        }
+    }
+
+    #[test]
+    fn test_trial_success_closes_and_resets_backoff() {
+        let breaker = breaker(10, 1);
+        trip(&breaker);
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(breaker.admit(), Some(Admission::Trial));
+        breaker.on_success(Admission::Trial);
+        let snapshot = breaker.snapshot();
+        assert_eq!(snapshot.state, CircuitState::Closed);
+        assert_eq!(snapshot.consecutive_trips, 0);
+        assert!(snapshot.cooldown_remaining_secs.is_none());
+    }
+
+    #[test]
+    fn test_only_trials_decide_half_open_breaker() {
+        let breaker = breaker(10, 1);
+        trip(&breaker);
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(breaker.admit(), Some(Admission::Trial));
+
+        // Requests admitted before the trip report back while half-open.
+        breaker.on_failure(Admission::Normal);
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        breaker.on_success(Admission::Normal);
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        breaker.on_success(Admission::Trial);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_late_success_does_not_close_open_breaker() {
+        let breaker = breaker(60_000, 1);
+        trip(&breaker);
+        breaker.on_success(Admission::Normal);
+        let snapshot = breaker.snapshot();
+        assert_eq!(snapshot.state, CircuitState::Open);
+        assert_eq!(snapshot.consecutive_trips, 1);
+        assert!(snapshot.cooldown_remaining_secs.is_some());
+
+        breaker.trip();
+        breaker.on_success(Admission::Normal);
+        assert_eq!(breaker.state(), CircuitState::Open);
+    }
+}
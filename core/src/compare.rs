@@ -0,0 +1,705 @@
+use crate::sandbox::MAX_WASM_SIZE_BYTES;
+use crate::simulation::{Footprint, SimulationEngine, SimulationError, SimulationResult};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use soroban_sdk::xdr::{
+    ContractDataDurability, Hash, LedgerKey, Limits, ReadXdr, ScAddress, ScVal, WriteXdr,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use utoipa::ToSchema;
+
+/// Change of a single scalar metric between baseline and candidate.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct MetricDelta {
+    pub metric: String,
+    pub baseline: u64,
+    pub candidate: u64,
+    pub delta: i64,
+    /// Relative change; absent when the baseline is zero
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delta_pct: Option<f64>,
+}
+
+impl MetricDelta {
+    fn new(metric: &str, baseline: u64, candidate: u64) -> Self {
+        let delta = candidate as i64 - baseline as i64;
+        let delta_pct = (baseline > 0).then(|| delta as f64 / baseline as f64 * 100.0);
+        Self {
+            metric: metric.to_string(),
+            baseline,
+            candidate,
+            delta,
+            delta_pct,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FootprintAccess {
+    ReadOnly,
+    ReadWrite,
+}
+
+impl FootprintAccess {
+    fn label(access: Option<Self>) -> &'static str {
+        match access {
+            Some(Self::ReadOnly) => "read-only",
+            Some(Self::ReadWrite) => "read-write",
+            None => "-",
+        }
+    }
+}
+
+/// A ledger key that was added, removed or changed access mode.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct FootprintChange {
+    /// Base64 `LedgerKey` XDR
+    pub key: String,
+    /// Short human-readable description of the key
+    pub description: String,
+    pub baseline: Option<FootprintAccess>,
+    pub candidate: Option<FootprintAccess>,
+}
+
+impl FootprintChange {
+    fn kind(&self) -> &'static str {
+        match (self.baseline, self.candidate) {
+            (None, Some(_)) => "added",
+            (Some(_), None) => "removed",
+            _ => "access changed",
+        }
+    }
+}
+
+/// Change in CPU and memory charged to one host cost type.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct CostTypeDelta {
+    pub cost_type: String,
+    pub cpu: MetricDelta,
+    pub mem: MetricDelta,
+}
+
+/// Side-by-side comparison of two simulations of the same function.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ComparisonReport {
+    pub baseline: String,
+    pub candidate: String,
+    pub metrics: Vec<MetricDelta>,
+    pub footprint_changes: Vec<FootprintChange>,
+    /// Only populated when both sides carry a cost breakdown (local runs)
+    pub cost_type_changes: Vec<CostTypeDelta>,
+}
+
+impl ComparisonReport {
+    pub fn new(
+        baseline_label: &str,
+        baseline: &SimulationResult,
+        candidate_label: &str,
+        candidate: &SimulationResult,
+    ) -> Self {
+        let (a, b) = (&baseline.resources, &candidate.resources);
+        let metrics = vec![
+            MetricDelta::new("cpu_instructions", a.cpu_instructions, b.cpu_instructions),
+            MetricDelta::new("ram_bytes", a.ram_bytes, b.ram_bytes),
+            MetricDelta::new(
+                "ledger_read_bytes",
+                a.ledger_read_bytes,
+                b.ledger_read_bytes,
+            ),
+            MetricDelta::new(
+                "ledger_write_bytes",
+                a.ledger_write_bytes,
+                b.ledger_write_bytes,
+            ),
+            MetricDelta::new("read_entries", a.read_entries, b.read_entries),
+            MetricDelta::new("write_entries", a.write_entries, b.write_entries),
+            MetricDelta::new(
+                "events_size_bytes",
+                a.events_size_bytes,
+                b.events_size_bytes,
+            ),
+            MetricDelta::new(
+                "transaction_size_bytes",
+                a.transaction_size_bytes,
+                b.transaction_size_bytes,
+            ),
+            MetricDelta::new(
+                "cost_stroops",
+                baseline.cost_stroops,
+                candidate.cost_stroops,
+            ),
+        ];
+
+        Self {
+            baseline: baseline_label.to_string(),
+            candidate: candidate_label.to_string(),
+            metrics,
+            footprint_changes: diff_footprints(
+                baseline.footprint.as_ref(),
+                candidate.footprint.as_ref(),
+            ),
+            cost_type_changes: diff_cost_types(baseline, candidate),
+        }
+    }
+
+    pub fn render_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "## Comparison: `{}` → `{}`\n",
+            self.baseline, self.candidate
+        );
+        let _ = writeln!(out, "| Metric | Baseline | Candidate | Δ | Δ % |");
+        let _ = writeln!(out, "|---|---:|---:|---:|---:|");
+        for m in &self.metrics {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {:+} | {} |",
+                m.metric,
+                m.baseline,
+                m.candidate,
+                m.delta,
+                format_pct(m.delta_pct)
+            );
+        }
+
+        if !self.footprint_changes.is_empty() {
+            let _ = writeln!(out, "\n### Footprint changes\n");
+            let _ = writeln!(out, "| Change | Key | Baseline | Candidate |");
+            let _ = writeln!(out, "|---|---|---|---|");
+            for c in &self.footprint_changes {
+                let _ = writeln!(
+                    out,
+                    "| {} | `{}` | {} | {} |",
+                    c.kind(),
+                    c.description,
+                    FootprintAccess::label(c.baseline),
+                    FootprintAccess::label(c.candidate)
+                );
+            }
+        }
+
+        if !self.cost_type_changes.is_empty() {
+            let _ = writeln!(out, "\n### Cost type changes\n");
+            let _ = writeln!(out, "| Cost type | CPU Δ | CPU Δ % | Mem Δ | Mem Δ % |");
+            let _ = writeln!(out, "|---|---:|---:|---:|---:|");
+            for c in &self.cost_type_changes {
+                let _ = writeln!(
+                    out,
+                    "| {} | {:+} | {} | {:+} | {} |",
+                    c.cost_type,
+                    c.cpu.delta,
+                    format_pct(c.cpu.delta_pct),
+                    c.mem.delta,
+                    format_pct(c.mem.delta_pct)
+                );
+            }
+        }
+        out
+    }
+
+    pub fn render_table(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Comparing {} -> {}", self.baseline, self.candidate);
+        let _ = writeln!(
+            out,
+            "{:<24} {:>14} {:>14} {:>14} {:>9}",
+            "metric", "baseline", "candidate", "delta", "delta%"
+        );
+        for m in &self.metrics {
+            let _ = writeln!(
+                out,
+                "{:<24} {:>14} {:>14} {:>+14} {:>9}",
+                m.metric,
+                m.baseline,
+                m.candidate,
+                m.delta,
+                format_pct(m.delta_pct)
+            );
+        }
+
+        if !self.footprint_changes.is_empty() {
+            let _ = writeln!(out, "\nFootprint changes:");
+            for c in &self.footprint_changes {
+                let _ = writeln!(
+                    out,
+                    "  {:<15} {:<60} {} -> {}",
+                    c.kind(),
+                    c.description,
+                    FootprintAccess::label(c.baseline),
+                    FootprintAccess::label(c.candidate)
+                );
+            }
+        }
+
+        if !self.cost_type_changes.is_empty() {
+            let _ = writeln!(out, "\nCost type changes:");
+            let _ = writeln!(
+                out,
+                "  {:<36} {:>14} {:>9} {:>12} {:>9}",
+                "cost_type", "cpu_delta", "cpu%", "mem_delta", "mem%"
+            );
+            for c in &self.cost_type_changes {
+                let _ = writeln!(
+                    out,
+                    "  {:<36} {:>+14} {:>9} {:>+12} {:>9}",
+                    c.cost_type,
+                    c.cpu.delta,
+                    format_pct(c.cpu.delta_pct),
+                    c.mem.delta,
+                    format_pct(c.mem.delta_pct)
+                );
+            }
+        }
+        out
+    }
+}
+
+/// One side of a comparison: a deployed contract or raw WASM run locally.
+#[derive(Debug, Clone)]
+pub enum CompareTarget {
+    Contract(String),
+    Wasm(Vec<u8>),
+}
+
+impl CompareTarget {
+    /// Interpret `value` as a contract id (`C…`) or base64-encoded WASM of at
+    /// most [`MAX_WASM_SIZE_BYTES`].
+    pub fn parse(value: &str) -> Result<Self, String> {
+        if stellar_strkey::Contract::from_string(value).is_ok() {
+            return Ok(Self::Contract(value.to_string()));
+        }
+        let too_large = || format!("WASM exceeds the maximum of {MAX_WASM_SIZE_BYTES} bytes");
+        // Reject oversized input before decoding it.
+        if value.len() / 4 * 3 > MAX_WASM_SIZE_BYTES + 2 {
+            return Err(too_large());
+        }
+        let wasm = BASE64
+            .decode(value)
+            .map_err(|_| "expected a contract id or base64-encoded WASM".to_string())?;
+        if wasm.len() > MAX_WASM_SIZE_BYTES {
+            return Err(too_large());
+        }
+        Ok(Self::Wasm(wasm))
+    }
+
+    pub async fn simulate(
+        &self,
+        engine: &SimulationEngine,
+        function_name: &str,
+        args: Vec<String>,
+    ) -> Result<SimulationResult, SimulationError> {
+        match self {
+            Self::Contract(id) => {
+                engine
+                    .simulate_from_contract_id(id, function_name, args, None, None)
+                    .await
+            }
+            Self::Wasm(wasm) => {
+                engine
+                    .simulate_wasm(wasm.clone(), function_name, args)
+                    .await
+            }
+        }
+    }
+}
+
+fn format_pct(pct: Option<f64>) -> String {
+    match pct {
+        Some(p) => format!("{p:+.2}%"),
+        None => "n/a".to_string(),
+    }
+}
+
+fn decode_ledger_key(encoded: &str) -> Option<LedgerKey> {
+    BASE64
+        .decode(encoded)
+        .ok()
+        .and_then(|bytes| LedgerKey::from_xdr(bytes, Limits::none()).ok())
+}
+
+/// Contracts whose storage appears in the footprint.
+fn data_owners(footprint: Option<&Footprint>) -> BTreeSet<ScAddress> {
+    footprint
+        .into_iter()
+        .flat_map(|fp| fp.read_only.iter().chain(&fp.read_write))
+        .filter_map(|key| match decode_ledger_key(key)? {
+            LedgerKey::ContractData(data) => Some(data.contract),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Key used to line up entries across the two sides. Storage of `own`, the
+/// compared contract when the sides are different deployments, is matched by
+/// durability and key alone.
+fn match_key(encoded: &str, own: Option<&ScAddress>) -> String {
+    match decode_ledger_key(encoded) {
+        Some(LedgerKey::ContractData(mut data)) if Some(&data.contract) == own => {
+            data.contract = ScAddress::Contract(Hash([0; 32]));
+            LedgerKey::ContractData(data)
+                .to_xdr(Limits::none())
+                .map(|bytes| BASE64.encode(bytes))
+                .unwrap_or_else(|_| encoded.to_string())
+        }
+        _ => encoded.to_string(),
+    }
+}
+
+fn access_map<'a>(
+    footprint: Option<&'a Footprint>,
+    own: Option<&ScAddress>,
+) -> BTreeMap<String, (&'a str, FootprintAccess)> {
+    let mut map = BTreeMap::new();
+    if let Some(fp) = footprint {
+        for key in &fp.read_only {
+            map.insert(
+                match_key(key, own),
+                (key.as_str(), FootprintAccess::ReadOnly),
+            );
+        }
+        for key in &fp.read_write {
+            map.insert(
+                match_key(key, own),
+                (key.as_str(), FootprintAccess::ReadWrite),
+            );
+        }
+    }
+    map
+}
+
+fn diff_footprints(
+    baseline: Option<&Footprint>,
+    candidate: Option<&Footprint>,
+) -> Vec<FootprintChange> {
+    let (owners_b, owners_a) = (data_owners(baseline), data_owners(candidate));
+    // The compared contract is the one storage owner unique to each side;
+    // with more than one there is no telling which pairs up, so keep raw keys.
+    let only = |set: &BTreeSet<ScAddress>, other: &BTreeSet<ScAddress>| {
+        let mut unique = set.difference(other);
+        match (unique.next(), unique.next()) {
+            (Some(own), None) => Some(own.clone()),
+            _ => None,
+        }
+    };
+    let (own_b, own_a) = match (only(&owners_b, &owners_a), only(&owners_a, &owners_b)) {
+        (Some(b), Some(a)) => (Some(b), Some(a)),
+        _ => (None, None),
+    };
+    let before = access_map(baseline, own_b.as_ref());
+    let after = access_map(candidate, own_a.as_ref());
+    let mut keys: Vec<&String> = before.keys().chain(after.keys()).collect();
+    keys.sort_unstable();
+    keys.dedup();
+
+    keys.into_iter()
+        .filter_map(|key| {
+            let (b, a) = (before.get(key), after.get(key));
+            let access = |side: Option<&(&str, FootprintAccess)>| side.map(|(_, access)| *access);
+            if access(b) == access(a) {
+                return None;
+            }
+            // Report the candidate's key when both sides touch the entry.
+            let (raw, _) = a.or(b)?;
+            Some(FootprintChange {
+                key: raw.to_string(),
+                description: describe_ledger_key(raw),
+                baseline: access(b),
+                candidate: access(a),
+            })
+        })
+        .collect()
+}
+
+fn diff_cost_types(
+    baseline: &SimulationResult,
+    candidate: &SimulationResult,
+) -> Vec<CostTypeDelta> {
+    let (Some(before), Some(after)) = (&baseline.cost_breakdown, &candidate.cost_breakdown) else {
+        return Vec::new();
+    };
+    // cost type -> ((cpu, mem) before, (cpu, mem) after)
+    type Usage = (u64, u64);
+    let mut totals: BTreeMap<&str, (Usage, Usage)> = BTreeMap::new();
+    for usage in before {
+        totals.entry(&usage.cost_type).or_default().0 = (usage.cpu_instructions, usage.mem_bytes);
+    }
+    for usage in after {
+        totals.entry(&usage.cost_type).or_default().1 = (usage.cpu_instructions, usage.mem_bytes);
+    }
+
+    totals
+        .into_iter()
+        .filter(|(_, (b, a))| b != a)
+        .map(
+            |(cost_type, ((cpu_b, mem_b), (cpu_a, mem_a)))| CostTypeDelta {
+                cost_type: cost_type.to_string(),
+                cpu: MetricDelta::new("cpu_instructions", cpu_b, cpu_a),
+                mem: MetricDelta::new("mem_bytes", mem_b, mem_a),
+            },
+        )
+        .collect()
+}
+
+/// Summarise a base64 `LedgerKey` as e.g. `data CABC…XYZ persistent Counter`.
+fn describe_ledger_key(encoded: &str) -> String {
+    let Some(key) = decode_ledger_key(encoded) else {
+        return encoded.to_string();
+    };
+
+    match &key {
+        LedgerKey::ContractCode(code) => format!("code {}", short(&hex::encode(code.hash.0))),
+        LedgerKey::ContractData(data) => {
+            let durability = match data.durability {
+                ContractDataDurability::Persistent => "persistent",
+                ContractDataDurability::Temporary => "temporary",
+            };
+            format!(
+                "data {} {} {}",
+                short(&describe_address(&data.contract)),
+                durability,
+                describe_scval(&data.key)
+            )
+        }
+        LedgerKey::Account(account) => {
+            let soroban_sdk::xdr::PublicKey::PublicKeyTypeEd25519(pk) = &account.account_id.0;
+            let strkey = stellar_strkey::ed25519::PublicKey(pk.0).to_string();
+            format!("account {}", short(&strkey))
+        }
+        other => other.name().to_lowercase(),
+    }
+}
+
+fn describe_address(address: &ScAddress) -> String {
+    match address {
+        ScAddress::Contract(hash) => stellar_strkey::Contract(hash.0).to_string(),
+        ScAddress::Account(account) => {
+            let soroban_sdk::xdr::PublicKey::PublicKeyTypeEd25519(pk) = &account.0;
+            stellar_strkey::ed25519::PublicKey(pk.0).to_string()
+        }
+    }
+}
+
+fn describe_scval(val: &ScVal) -> String {
+    match val {
+        ScVal::LedgerKeyContractInstance => "instance".to_string(),
+        ScVal::Symbol(sym) => sym.to_utf8_string_lossy(),
+        ScVal::Vec(Some(items)) => match items.first() {
+            Some(ScVal::Symbol(sym)) => format!("{}(…)", sym.to_utf8_string_lossy()),
+            _ => "vec".to_string(),
+        },
+        other => match other.to_xdr(Limits::none()) {
+            Ok(bytes) => format!("#{}", short(&hex::encode(bytes))),
+            Err(_) => other.name().to_lowercase(),
+        },
+    }
+}
+
+fn short(s: &str) -> String {
+    if s.len() <= 12 {
+        s.to_string()
+    } else {
+        format!("{}…{}", &s[..6], &s[s.len() - 4..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{CostTypeUsage, SorobanResources};
+    use soroban_sdk::xdr::{LedgerKeyContractData, ScSymbol};
+
+    fn result(cpu: u64, footprint: Footprint) -> SimulationResult {
+        SimulationResult {
+            resources: SorobanResources {
+                cpu_instructions: cpu,
+                ram_bytes: 2_000,
+                ..Default::default()
+            },
+            transaction_hash: None,
+            latest_ledger: 0,
+            cost_stroops: cpu / 10_000,
+            state_dependency: None,
+            headroom: None,
+            footprint: Some(footprint),
+            cost_breakdown: None,
+            provider: None,
+            ledger_lag: None,
+        }
+    }
+
+    fn data_key(name: &str) -> String {
+        contract_data_key(7, name)
+    }
+
+    fn contract_data_key(contract: u8, name: &str) -> String {
+        let key = LedgerKey::ContractData(LedgerKeyContractData {
+            contract: ScAddress::Contract(Hash([contract; 32])),
+            key: ScVal::Symbol(ScSymbol(name.try_into().unwrap())),
+            durability: ContractDataDurability::Persistent,
+        });
+        BASE64.encode(key.to_xdr(Limits::none()).unwrap())
+    }
+
+    #[test]
+    fn test_parse_rejects_oversized_wasm() {
+        let wasm = vec![0u8; MAX_WASM_SIZE_BYTES + 1];
+        let err = CompareTarget::parse(&BASE64.encode(wasm)).unwrap_err();
+        assert!(err.contains("maximum"), "{err}");
+
+        let wasm = vec![0u8; MAX_WASM_SIZE_BYTES];
+        assert!(matches!(
+            CompareTarget::parse(&BASE64.encode(wasm)),
+            Ok(CompareTarget::Wasm(_))
+        ));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_wasm_target_runs_on_current_thread_runtime() {
+        let engine = SimulationEngine::new("https://test.com".to_string());
+        let target = CompareTarget::Wasm(b"not wasm".to_vec());
+        let err = target.simulate(&engine, "hello", vec![]).await.unwrap_err();
+        assert!(matches!(err, SimulationError::LocalExecution(_)), "{err:?}");
+    }
+
+    #[test]
+    fn test_metric_delta_abs_and_pct() {
+        let d = MetricDelta::new("cpu", 200, 150);
+        assert_eq!(d.delta, -50);
+        assert_eq!(d.delta_pct, Some(-25.0));
+
+        let zero = MetricDelta::new("cpu", 0, 10);
+        assert_eq!(zero.delta, 10);
+        assert_eq!(zero.delta_pct, None);
+    }
+
+    #[test]
+    fn test_footprint_changes_detected() {
+        let (kept, dropped, added, upgraded) = (
+            data_key("Kept"),
+            data_key("Dropped"),
+            data_key("Added"),
+            data_key("Upgraded"),
+        );
+        let a = result(
+            1_000,
+            Footprint {
+                read_only: vec![kept.clone(), dropped.clone(), upgraded.clone()],
+                read_write: vec![],
+            },
+        );
+        let b = result(
+            1_000,
+            Footprint {
+                read_only: vec![kept],
+                read_write: vec![added.clone(), upgraded.clone()],
+            },
+        );
+
+        let report = ComparisonReport::new("a", &a, "b", &b);
+        let kinds: BTreeMap<String, &str> = report
+            .footprint_changes
+            .iter()
+            .map(|c| (c.key.clone(), c.kind()))
+            .collect();
+        assert_eq!(kinds.len(), 3);
+        assert_eq!(kinds[&dropped], "removed");
+        assert_eq!(kinds[&added], "added");
+        assert_eq!(kinds[&upgraded], "access changed");
+        assert!(report
+            .footprint_changes
+            .iter()
+            .any(|c| c.description.ends_with("persistent Added")));
+    }
+
+    #[test]
+    fn test_footprint_matches_storage_across_contract_ids() {
+        // Two deployments of the same contract that both call a shared token.
+        let a = result(
+            1_000,
+            Footprint {
+                read_only: vec![
+                    contract_data_key(1, "Admin"),
+                    contract_data_key(9, "Balance"),
+                ],
+                read_write: vec![contract_data_key(1, "Counter")],
+            },
+        );
+        let b = result(
+            1_000,
+            Footprint {
+                read_only: vec![
+                    contract_data_key(2, "Admin"),
+                    contract_data_key(9, "Balance"),
+                ],
+                read_write: vec![
+                    contract_data_key(2, "Counter"),
+                    contract_data_key(2, "Added"),
+                ],
+            },
+        );
+
+        let report = ComparisonReport::new("a", &a, "b", &b);
+        assert_eq!(report.footprint_changes.len(), 1);
+        let change = &report.footprint_changes[0];
+        assert_eq!(change.key, contract_data_key(2, "Added"));
+        assert_eq!(change.kind(), "added");
+
+        // With two unmatched owners per side nothing is paired up.
+        let c = result(
+            1_000,
+            Footprint {
+                read_only: vec![
+                    contract_data_key(2, "Admin"),
+                    contract_data_key(8, "Balance"),
+                ],
+                read_write: vec![contract_data_key(2, "Counter")],
+            },
+        );
+        let report = ComparisonReport::new("a", &a, "c", &c);
+        let kinds: Vec<&str> = report.footprint_changes.iter().map(|c| c.kind()).collect();
+        assert_eq!(kinds.iter().filter(|k| **k == "removed").count(), 3);
+        assert_eq!(kinds.iter().filter(|k| **k == "added").count(), 3);
+    }
+
+    #[test]
+    fn test_cost_type_changes_only_with_breakdown() {
+        let usage = |cpu| {
+            Some(vec![CostTypeUsage {
+                cost_type: "WasmInsnExec".to_string(),
+                iterations: 1,
+                cpu_instructions: cpu,
+                mem_bytes: 0,
+            }])
+        };
+        let mut a = result(1_000, Footprint::default());
+        let mut b = result(1_500, Footprint::default());
+        assert!(ComparisonReport::new("a", &a, "b", &b)
+            .cost_type_changes
+            .is_empty());
+
+        a.cost_breakdown = usage(400);
+        b.cost_breakdown = usage(600);
+        let report = ComparisonReport::new("a", &a, "b", &b);
+        assert_eq!(report.cost_type_changes.len(), 1);
+        assert_eq!(report.cost_type_changes[0].cpu.delta, 200);
+        assert_eq!(report.cost_type_changes[0].cpu.delta_pct, Some(50.0));
+    }
+
+    #[test]
+    fn test_render_markdown_and_table() {
+        let a = result(1_000, Footprint::default());
+        let b = result(1_500, Footprint::default());
+        let report = ComparisonReport::new("v1.wasm", &a, "v2.wasm", &b);
+
+        let md = report.render_markdown();
+        assert!(md.contains("| cpu_instructions | 1000 | 1500 | +500 | +50.00% |"));
+        assert!(!md.contains("Footprint changes"));
+
+        let table = report.render_table();
+        assert!(table.contains("Comparing v1.wasm -> v2.wasm"));
+        assert!(table.contains("+50.00%"));
+    }
+}
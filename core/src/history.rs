@@ -0,0 +1,851 @@
+//! Per-account record of past analyses, kept in an embedded redb database.
+//!
+//! A run can be shared: it is then also published under a content-addressed
+//! report id (SHA-256 of the request and result) that anyone may read.
+//!
+//! Rows are keyed `<account>/<id>`, where ids are hex microsecond timestamps
+//! (bumped when two runs land on the same microsecond), so one account's runs
+//! sort oldest to newest and never mix with another account's.
+
+use redb::backends::InMemoryBackend;
+use redb::{Database, ReadableTable, TableDefinition};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use utoipa::{IntoParams, ToSchema};
+
+/// Oldest runs beyond this are dropped when an account records a new one.
+pub const MAX_ENTRIES_PER_ACCOUNT: usize = 1_000;
+pub const DEFAULT_PAGE_SIZE: usize = 20;
+pub const MAX_PAGE_SIZE: usize = 100;
+
+const ENTRIES: TableDefinition<&str, &[u8]> = TableDefinition::new("history");
+/// Shared reports by report id.
+const REPORTS: TableDefinition<&str, &[u8]> = TableDefinition::new("shared_reports");
+
+#[derive(Error, Debug)]
+pub enum HistoryError {
+    #[error("History storage error: {0}")]
+    Storage(String),
+
+    #[error("History serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+fn storage_error(e: impl Into<redb::Error>) -> HistoryError {
+    HistoryError::Storage(e.into().to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RunKind {
+    Analyze,
+    Compare,
+}
+
+/// A run to be recorded.
+#[derive(Debug, Clone)]
+pub struct NewRun {
+    pub kind: RunKind,
+    /// Contract analysed, or the baseline of a comparison
+    pub contract_id: String,
+    /// Candidate of a comparison
+    pub candidate: Option<String>,
+    pub function_name: String,
+    pub args: Vec<String>,
+    /// Network profile the run simulated against
+    pub network: Option<String>,
+    /// The response body returned for the run
+    pub result: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HistoryEntry {
+    pub id: String,
+    pub kind: RunKind,
+    /// Contract analysed, or the baseline of a comparison
+    pub contract_id: String,
+    /// Candidate of a comparison
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub candidate: Option<String>,
+    pub function_name: String,
+    pub args: Vec<String>,
+    /// Network profile the run simulated against; absent for runs recorded
+    /// before networks were selectable
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+    /// Unix time of the run
+    pub created_at: u64,
+    /// The response body returned for the run
+    #[schema(value_type = Object)]
+    pub result: serde_json::Value,
+    /// Hash of the request and result; the run is readable at
+    /// `/reports/{report_id}` while `shared` is set
+    #[serde(default)]
+    pub report_id: String,
+    #[serde(default)]
+    pub shared: bool,
+}
+
+impl HistoryEntry {
+    /// SHA-256 over the request and result, so identical runs share an id.
+    fn content_hash(&self) -> String {
+        // serde_json sorts object keys, so the encoding is canonical.
+        let mut content = serde_json::json!({
+            "kind": self.kind,
+            "contract_id": self.contract_id,
+            "candidate": self.candidate,
+            "function_name": self.function_name,
+            "args": self.args,
+            "result": self.result,
+        });
+        // Left out when absent so runs recorded before networks keep their ids.
+        if let Some(network) = &self.network {
+            content["network"] = network.clone().into();
+        }
+        hex::encode(Sha256::digest(content.to_string().as_bytes()))
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, HistoryError> {
+        let mut entry: Self = serde_json::from_slice(bytes)?;
+        if entry.report_id.is_empty() {
+            entry.report_id = entry.content_hash();
+        }
+        Ok(entry)
+    }
+}
+
+/// A shared run as served publicly; carries nothing about the account.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SharedReport {
+    pub report_id: String,
+    pub kind: RunKind,
+    pub contract_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub candidate: Option<String>,
+    pub function_name: String,
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+    /// Unix time of the run first shared with this content
+    pub created_at: u64,
+    #[schema(value_type = Object)]
+    pub result: serde_json::Value,
+}
+
+impl From<&HistoryEntry> for SharedReport {
+    fn from(entry: &HistoryEntry) -> Self {
+        Self {
+            report_id: entry.report_id.clone(),
+            kind: entry.kind,
+            contract_id: entry.contract_id.clone(),
+            candidate: entry.candidate.clone(),
+            function_name: entry.function_name.clone(),
+            args: entry.args.clone(),
+            network: entry.network.clone(),
+            created_at: entry.created_at,
+            result: entry.result.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ReportRow {
+    report: SharedReport,
+    /// History rows sharing this report; it is unpublished when the last
+    /// one stops sharing.
+    sharers: Vec<String>,
+}
+
+/// A history row without its result.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct HistorySummary {
+    pub id: String,
+    pub kind: RunKind,
+    pub contract_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub candidate: Option<String>,
+    pub function_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+    pub created_at: u64,
+    pub report_id: String,
+    pub shared: bool,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct HistoryQuery {
+    /// Only runs involving this contract (either side of a comparison)
+    pub contract_id: Option<String>,
+    pub function_name: Option<String>,
+    /// Only runs at or after this unix time
+    pub from: Option<u64>,
+    /// Only runs at or before this unix time
+    pub to: Option<u64>,
+    /// Page size (default 20, at most 100)
+    pub limit: Option<usize>,
+    /// `next_cursor` of the previous page
+    pub cursor: Option<String>,
+}
+
+impl HistoryQuery {
+    fn matches(&self, entry: &HistoryEntry) -> bool {
+        let contract = self
+            .contract_id
+            .as_deref()
+            .is_none_or(|id| entry.contract_id == id || entry.candidate.as_deref() == Some(id));
+        contract
+            && self
+                .function_name
+                .as_deref()
+                .is_none_or(|f| entry.function_name == f)
+            && self.from.is_none_or(|from| entry.created_at >= from)
+            && self.to.is_none_or(|to| entry.created_at <= to)
+    }
+}
+
+/// Newest-first page of runs.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HistoryPage {
+    pub entries: Vec<HistorySummary>,
+    /// Pass as `cursor` to fetch the next page; absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+impl From<&HistoryEntry> for HistorySummary {
+    fn from(entry: &HistoryEntry) -> Self {
+        Self {
+            id: entry.id.clone(),
+            kind: entry.kind,
+            contract_id: entry.contract_id.clone(),
+            candidate: entry.candidate.clone(),
+            function_name: entry.function_name.clone(),
+            network: entry.network.clone(),
+            created_at: entry.created_at,
+            report_id: entry.report_id.clone(),
+            shared: entry.shared,
+        }
+    }
+}
+
+pub struct HistoryStore {
+    db: Arc<Database>,
+}
+
+impl HistoryStore {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, HistoryError> {
+        Self::init(Database::create(path.as_ref()).map_err(storage_error)?)
+    }
+
+    /// A store that lives only as long as the process.
+    pub fn in_memory() -> Result<Self, HistoryError> {
+        Self::init(
+            Database::builder()
+                .create_with_backend(InMemoryBackend::new())
+                .map_err(storage_error)?,
+        )
+    }
+
+    fn init(db: Database) -> Result<Self, HistoryError> {
+        // Make sure the table exists so read transactions never fail on it.
+        let txn = db.begin_write().map_err(storage_error)?;
+        txn.open_table(ENTRIES).map_err(storage_error)?;
+        txn.open_table(REPORTS).map_err(storage_error)?;
+        txn.commit().map_err(storage_error)?;
+        Ok(Self { db: Arc::new(db) })
+    }
+
+    /// Save `run` for `account`, dropping its oldest runs past
+    /// [`MAX_ENTRIES_PER_ACCOUNT`].
+    pub async fn record(&self, account: &str, run: NewRun) -> Result<HistoryEntry, HistoryError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let mut entry = HistoryEntry {
+            id: next_id(now.as_micros() as u64),
+            kind: run.kind,
+            contract_id: run.contract_id,
+            candidate: run.candidate,
+            function_name: run.function_name,
+            args: run.args,
+            network: run.network,
+            created_at: now.as_secs(),
+            result: run.result,
+            report_id: String::new(),
+            shared: false,
+        };
+        entry.report_id = entry.content_hash();
+        let db = Arc::clone(&self.db);
+        let account = account.to_string();
+        let saved = entry.clone();
+        blocking(move || Self::write_entry(&db, &account, &saved)).await?;
+        Ok(entry)
+    }
+
+    /// Runs of `account` matching `query`, newest first.
+    pub async fn list(
+        &self,
+        account: &str,
+        query: HistoryQuery,
+    ) -> Result<HistoryPage, HistoryError> {
+        let db = Arc::clone(&self.db);
+        let account = account.to_string();
+        blocking(move || Self::read_page(&db, &account, &query)).await
+    }
+
+    pub async fn get(&self, account: &str, id: &str) -> Result<Option<HistoryEntry>, HistoryError> {
+        let db = Arc::clone(&self.db);
+        let key = row_key(account, id);
+        blocking(move || {
+            let txn = db.begin_read().map_err(storage_error)?;
+            let table = txn.open_table(ENTRIES).map_err(storage_error)?;
+            match table.get(key.as_str()).map_err(storage_error)? {
+                Some(value) => Ok(Some(HistoryEntry::decode(value.value())?)),
+                None => Ok(None),
+            }
+        })
+        .await
+    }
+
+    /// Publish or unpublish one of `account`'s runs at its report id.
+    /// Returns the updated run, or `None` if there is no such run.
+    pub async fn set_shared(
+        &self,
+        account: &str,
+        id: &str,
+        shared: bool,
+    ) -> Result<Option<HistoryEntry>, HistoryError> {
+        let db = Arc::clone(&self.db);
+        let key = row_key(account, id);
+        blocking(move || {
+            let txn = db.begin_write().map_err(storage_error)?;
+            let entry = {
+                let mut entries = txn.open_table(ENTRIES).map_err(storage_error)?;
+                let mut reports = txn.open_table(REPORTS).map_err(storage_error)?;
+                let mut entry = match entries.get(key.as_str()).map_err(storage_error)? {
+                    Some(value) => HistoryEntry::decode(value.value())?,
+                    None => return Ok(None),
+                };
+                if entry.shared != shared {
+                    entry.shared = shared;
+                    let bytes = serde_json::to_vec(&entry)?;
+                    entries
+                        .insert(key.as_str(), bytes.as_slice())
+                        .map_err(storage_error)?;
+                    if shared {
+                        publish(&mut reports, &key, &entry)?;
+                    } else {
+                        unpublish(&mut reports, &key, &entry.report_id)?;
+                    }
+                }
+                entry
+            };
+            txn.commit().map_err(storage_error)?;
+            Ok(Some(entry))
+        })
+        .await
+    }
+
+    /// A shared report, readable by anyone who has its id.
+    pub async fn report(&self, report_id: &str) -> Result<Option<SharedReport>, HistoryError> {
+        let db = Arc::clone(&self.db);
+        let report_id = report_id.to_string();
+        blocking(move || {
+            let txn = db.begin_read().map_err(storage_error)?;
+            let table = txn.open_table(REPORTS).map_err(storage_error)?;
+            match table.get(report_id.as_str()).map_err(storage_error)? {
+                Some(value) => Ok(Some(
+                    serde_json::from_slice::<ReportRow>(value.value())?.report,
+                )),
+                None => Ok(None),
+            }
+        })
+        .await
+    }
+
+    /// Delete one run; returns whether it existed.
+    pub async fn delete(&self, account: &str, id: &str) -> Result<bool, HistoryError> {
+        let db = Arc::clone(&self.db);
+        let key = row_key(account, id);
+        blocking(move || {
+            let txn = db.begin_write().map_err(storage_error)?;
+            let removed = {
+                let mut entries = txn.open_table(ENTRIES).map_err(storage_error)?;
+                let mut reports = txn.open_table(REPORTS).map_err(storage_error)?;
+                let removed = entries.remove(key.as_str()).map_err(storage_error)?;
+                match removed {
+                    Some(value) => {
+                        release(&mut reports, &key, value.value())?;
+                        true
+                    }
+                    None => false,
+                }
+            };
+            txn.commit().map_err(storage_error)?;
+            Ok(removed)
+        })
+        .await
+    }
+
+    /// Delete every run of `account`; returns how many were removed.
+    pub async fn clear(&self, account: &str) -> Result<u64, HistoryError> {
+        let db = Arc::clone(&self.db);
+        let account = account.to_string();
+        blocking(move || {
+            let (start, end) = account_range(&account);
+            let txn = db.begin_write().map_err(storage_error)?;
+            let removed = {
+                let mut entries = txn.open_table(ENTRIES).map_err(storage_error)?;
+                let mut reports = txn.open_table(REPORTS).map_err(storage_error)?;
+                let mut removed = Vec::new();
+                for row in entries
+                    .extract_from_if(start.as_str()..end.as_str(), |_, _| true)
+                    .map_err(storage_error)?
+                {
+                    let (key, value) = row.map_err(storage_error)?;
+                    removed.push((key.value().to_string(), value.value().to_vec()));
+                }
+                for (key, value) in &removed {
+                    release(&mut reports, key, value)?;
+                }
+                removed.len() as u64
+            };
+            txn.commit().map_err(storage_error)?;
+            Ok(removed)
+        })
+        .await
+    }
+
+    fn write_entry(db: &Database, account: &str, entry: &HistoryEntry) -> Result<(), HistoryError> {
+        let bytes = serde_json::to_vec(entry)?;
+        let (start, end) = account_range(account);
+        let txn = db.begin_write().map_err(storage_error)?;
+        {
+            let mut table = txn.open_table(ENTRIES).map_err(storage_error)?;
+            table
+                .insert(row_key(account, &entry.id).as_str(), bytes.as_slice())
+                .map_err(storage_error)?;
+
+            let mut keys = Vec::new();
+            for row in table
+                .range(start.as_str()..end.as_str())
+                .map_err(storage_error)?
+            {
+                let (key, _) = row.map_err(storage_error)?;
+                keys.push(key.value().to_string());
+            }
+            let excess = keys.len().saturating_sub(MAX_ENTRIES_PER_ACCOUNT);
+            let mut evicted = Vec::new();
+            for key in &keys[..excess] {
+                if let Some(value) = table.remove(key.as_str()).map_err(storage_error)? {
+                    evicted.push((key, value.value().to_vec()));
+                }
+            }
+            if !evicted.is_empty() {
+                let mut reports = txn.open_table(REPORTS).map_err(storage_error)?;
+                for (key, value) in evicted {
+                    release(&mut reports, key, &value)?;
+                }
+            }
+        }
+        txn.commit().map_err(storage_error)?;
+        Ok(())
+    }
+
+    fn read_page(
+        db: &Database,
+        account: &str,
+        query: &HistoryQuery,
+    ) -> Result<HistoryPage, HistoryError> {
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE);
+        let (start, mut end) = account_range(account);
+        if let Some(cursor) = &query.cursor {
+            end = row_key(account, cursor);
+        }
+
+        let txn = db.begin_read().map_err(storage_error)?;
+        let table = txn.open_table(ENTRIES).map_err(storage_error)?;
+        let mut entries = Vec::new();
+        let mut next_cursor = None;
+        for row in table
+            .range(start.as_str()..end.as_str())
+            .map_err(storage_error)?
+            .rev()
+        {
+            let (_, value) = row.map_err(storage_error)?;
+            let entry = HistoryEntry::decode(value.value())?;
+            if !query.matches(&entry) {
+                continue;
+            }
+            if entries.len() == limit {
+                next_cursor = entries.last().map(|e: &HistorySummary| e.id.clone());
+                break;
+            }
+            entries.push(HistorySummary::from(&entry));
+        }
+        Ok(HistoryPage {
+            entries,
+            next_cursor,
+        })
+    }
+}
+
+type Table<'txn> = redb::Table<'txn, &'static str, &'static [u8]>;
+
+/// Add history row `key` to the sharers of its report, publishing it if new.
+fn publish(reports: &mut Table, key: &str, entry: &HistoryEntry) -> Result<(), HistoryError> {
+    let existing = reports
+        .get(entry.report_id.as_str())
+        .map_err(storage_error)?
+        .map(|value| serde_json::from_slice::<ReportRow>(value.value()))
+        .transpose()?;
+    let mut row = existing.unwrap_or_else(|| ReportRow {
+        report: SharedReport::from(entry),
+        sharers: Vec::new(),
+    });
+    if !row.sharers.iter().any(|k| k == key) {
+        row.sharers.push(key.to_string());
+    }
+    let bytes = serde_json::to_vec(&row)?;
+    reports
+        .insert(entry.report_id.as_str(), bytes.as_slice())
+        .map_err(storage_error)?;
+    Ok(())
+}
+
+/// Remove history row `key` from the sharers of `report_id`, unpublishing
+/// the report when nobody shares it any more.
+fn unpublish(reports: &mut Table, key: &str, report_id: &str) -> Result<(), HistoryError> {
+    let existing = reports
+        .get(report_id)
+        .map_err(storage_error)?
+        .map(|value| serde_json::from_slice::<ReportRow>(value.value()))
+        .transpose()?;
+    let Some(mut row) = existing else {
+        return Ok(());
+    };
+    row.sharers.retain(|k| k != key);
+    if row.sharers.is_empty() {
+        reports.remove(report_id).map_err(storage_error)?;
+    } else {
+        let bytes = serde_json::to_vec(&row)?;
+        reports
+            .insert(report_id, bytes.as_slice())
+            .map_err(storage_error)?;
+    }
+    Ok(())
+}
+
+/// Stop sharing a history row that is being deleted.
+fn release(reports: &mut Table, key: &str, value: &[u8]) -> Result<(), HistoryError> {
+    let entry = HistoryEntry::decode(value)?;
+    if entry.shared {
+        unpublish(reports, key, &entry.report_id)?;
+    }
+    Ok(())
+}
+
+/// A time-ordered id no smaller than `micros` and greater than any id issued
+/// before by this process.
+fn next_id(micros: u64) -> String {
+    static LAST: AtomicU64 = AtomicU64::new(0);
+    let mut last = LAST.load(Ordering::Relaxed);
+    loop {
+        let id = micros.max(last + 1);
+        match LAST.compare_exchange_weak(last, id, Ordering::Relaxed, Ordering::Relaxed) {
+            Ok(_) => return format!("{id:016x}"),
+            Err(current) => last = current,
+        }
+    }
+}
+
+fn row_key(account: &str, id: &str) -> String {
+    format!("{account}/{id}")
+}
+
+/// Key range holding every row of `account`; `0` sorts right after `/`.
+fn account_range(account: &str) -> (String, String) {
+    (format!("{account}/"), format!("{account}0"))
+}
+
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, HistoryError> + Send + 'static,
+) -> Result<T, HistoryError> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| HistoryError::Storage(format!("History task failed: {e}")))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const ALICE: &str = "GALICE";
+    const BOB: &str = "GBOB";
+
+    fn run(contract_id: &str, function_name: &str) -> NewRun {
+        NewRun {
+            kind: RunKind::Analyze,
+            contract_id: contract_id.to_string(),
+            candidate: None,
+            function_name: function_name.to_string(),
+            args: vec!["1".to_string()],
+            network: Some("testnet".to_string()),
+            result: json!({ "cpu_instructions": 1500 }),
+        }
+    }
+
+    fn ids(page: &HistoryPage) -> Vec<&str> {
+        page.entries.iter().map(|e| e.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_ids_are_unique_and_ordered() {
+        let a = next_id(1_000);
+        let b = next_id(1_000);
+        let c = next_id(0);
+        assert!(a < b && b < c, "{a} {b} {c}");
+        assert_eq!(a.len(), 16);
+    }
+
+    #[tokio::test]
+    async fn test_record_and_get() {
+        let store = HistoryStore::in_memory().unwrap();
+        let entry = store.record(ALICE, run("CA", "hello")).await.unwrap();
+
+        let loaded = store.get(ALICE, &entry.id).await.unwrap().unwrap();
+        assert_eq!(loaded.contract_id, "CA");
+        assert_eq!(loaded.args, vec!["1"]);
+        assert_eq!(loaded.result["cpu_instructions"], 1500);
+        // Other accounts can't see it.
+        assert!(store.get(BOB, &entry.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_list_is_newest_first_and_paginated() {
+        let store = HistoryStore::in_memory().unwrap();
+        let mut recorded = Vec::new();
+        for _ in 0..5 {
+            recorded.push(store.record(ALICE, run("CA", "hello")).await.unwrap().id);
+        }
+        store.record(BOB, run("CA", "hello")).await.unwrap();
+        recorded.sort();
+        recorded.reverse();
+
+        let first = store
+            .list(
+                ALICE,
+                HistoryQuery {
+                    limit: Some(2),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(ids(&first), recorded[..2]);
+        let second = store
+            .list(
+                ALICE,
+                HistoryQuery {
+                    limit: Some(2),
+                    cursor: first.next_cursor.clone(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(ids(&second), recorded[2..4]);
+        let last = store
+            .list(
+                ALICE,
+                HistoryQuery {
+                    limit: Some(2),
+                    cursor: second.next_cursor.clone(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(ids(&last), recorded[4..]);
+        assert!(last.next_cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_list_filters() {
+        let store = HistoryStore::in_memory().unwrap();
+        store.record(ALICE, run("CA", "hello")).await.unwrap();
+        store.record(ALICE, run("CB", "hello")).await.unwrap();
+        store.record(ALICE, run("CA", "transfer")).await.unwrap();
+        store
+            .record(
+                ALICE,
+                NewRun {
+                    kind: RunKind::Compare,
+                    candidate: Some("CA".to_string()),
+                    ..run("CC", "hello")
+                },
+            )
+            .await
+            .unwrap();
+
+        let count = |query: HistoryQuery| {
+            let store = &store;
+            async move { store.list(ALICE, query).await.unwrap().entries.len() }
+        };
+        let by_contract = HistoryQuery {
+            contract_id: Some("CA".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(count(by_contract).await, 3);
+        let by_function = HistoryQuery {
+            contract_id: Some("CA".to_string()),
+            function_name: Some("hello".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(count(by_function).await, 2);
+        let future = HistoryQuery {
+            from: Some(u64::MAX),
+            ..Default::default()
+        };
+        assert_eq!(count(future).await, 0);
+        let until_now = HistoryQuery {
+            to: Some(u64::MAX),
+            ..Default::default()
+        };
+        assert_eq!(count(until_now).await, 4);
+    }
+
+    #[tokio::test]
+    async fn test_delete_and_clear() {
+        let store = HistoryStore::in_memory().unwrap();
+        let first = store.record(ALICE, run("CA", "hello")).await.unwrap();
+        store.record(ALICE, run("CA", "hello")).await.unwrap();
+        store.record(BOB, run("CA", "hello")).await.unwrap();
+
+        assert!(!store.delete(BOB, &first.id).await.unwrap());
+        assert!(store.delete(ALICE, &first.id).await.unwrap());
+        assert!(store.get(ALICE, &first.id).await.unwrap().is_none());
+
+        assert_eq!(store.clear(ALICE).await.unwrap(), 1);
+        let bob = store.list(BOB, HistoryQuery::default()).await.unwrap();
+        assert_eq!(bob.entries.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_keeps_newest_entries_per_account() {
+        let store = HistoryStore::in_memory().unwrap();
+        let first = store.record(ALICE, run("CA", "hello")).await.unwrap();
+        for _ in 0..MAX_ENTRIES_PER_ACCOUNT {
+            store.record(ALICE, run("CA", "hello")).await.unwrap();
+        }
+        assert!(store.get(ALICE, &first.id).await.unwrap().is_none());
+        let page = store
+            .list(
+                ALICE,
+                HistoryQuery {
+                    limit: Some(MAX_PAGE_SIZE),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(page.entries.len(), MAX_PAGE_SIZE);
+    }
+
+    #[tokio::test]
+    async fn test_survives_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.redb");
+        let id = {
+            let store = HistoryStore::open(&path).unwrap();
+            store.record(ALICE, run("CA", "hello")).await.unwrap().id
+        };
+        let store = HistoryStore::open(&path).unwrap();
+        assert!(store.get(ALICE, &id).await.unwrap().is_some());
+    }
+
+    // ── Shared reports ────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_report_id_is_content_addressed() {
+        let store = HistoryStore::in_memory().unwrap();
+        let a = store.record(ALICE, run("CA", "hello")).await.unwrap();
+        let b = store.record(BOB, run("CA", "hello")).await.unwrap();
+        let c = store.record(ALICE, run("CA", "transfer")).await.unwrap();
+        let mut d = run("CA", "hello");
+        d.result = json!({ "cpu_instructions": 1501 });
+        let d = store.record(ALICE, d).await.unwrap();
+        let mut e = run("CA", "hello");
+        e.network = Some("futurenet".to_string());
+        let e = store.record(ALICE, e).await.unwrap();
+
+        assert_ne!(a.id, b.id);
+        assert_eq!(a.report_id, b.report_id);
+        assert_ne!(a.report_id, c.report_id);
+        assert_ne!(a.report_id, d.report_id);
+        assert_ne!(a.report_id, e.report_id);
+        assert_eq!(a.report_id.len(), 64);
+    }
+
+    #[tokio::test]
+    async fn test_reports_are_opt_in() {
+        let store = HistoryStore::in_memory().unwrap();
+        let entry = store.record(ALICE, run("CA", "hello")).await.unwrap();
+        assert!(!entry.shared);
+        assert!(store.report(&entry.report_id).await.unwrap().is_none());
+
+        // Only the owner can share a run.
+        assert!(store
+            .set_shared(BOB, &entry.id, true)
+            .await
+            .unwrap()
+            .is_none());
+        let shared = store
+            .set_shared(ALICE, &entry.id, true)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(shared.shared);
+        let report = store.report(&entry.report_id).await.unwrap().unwrap();
+        assert_eq!(report.contract_id, "CA");
+        assert_eq!(report.result, entry.result);
+
+        store.set_shared(ALICE, &entry.id, false).await.unwrap();
+        assert!(store.report(&entry.report_id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_report_stays_published_while_anyone_shares_it() {
+        let store = HistoryStore::in_memory().unwrap();
+        let a = store.record(ALICE, run("CA", "hello")).await.unwrap();
+        let b = store.record(BOB, run("CA", "hello")).await.unwrap();
+        store.set_shared(ALICE, &a.id, true).await.unwrap();
+        store.set_shared(BOB, &b.id, true).await.unwrap();
+
+        store.set_shared(ALICE, &a.id, false).await.unwrap();
+        assert!(store.report(&a.report_id).await.unwrap().is_some());
+
+        // Deleting the last shared run unpublishes the report.
+        assert_eq!(store.clear(BOB).await.unwrap(), 1);
+        assert!(store.report(&a.report_id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_deleting_shared_run_unpublishes_it() {
+        let store = HistoryStore::in_memory().unwrap();
+        let entry = store.record(ALICE, run("CA", "hello")).await.unwrap();
+        store.set_shared(ALICE, &entry.id, true).await.unwrap();
+
+        assert!(store.delete(ALICE, &entry.id).await.unwrap());
+        assert!(store.report(&entry.report_id).await.unwrap().is_none());
+    }
+}
@@ -1,3 +1,14 @@
+pub mod cache;
+pub mod circuit_breaker;
+pub mod compare;
+pub mod history;
+pub mod limits;
+#[cfg(test)]
+pub mod mock_rpc;
 pub mod parser;
 pub mod rpc_provider;
+pub mod sandbox;
 pub mod simulation;
+pub mod singleflight;
+pub mod soroban_rpc;
+pub mod sweep;
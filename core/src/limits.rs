@@ -0,0 +1,338 @@
+use crate::simulation::SorobanResources;
+use crate::soroban_rpc::SorobanRpc;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use soroban_sdk::xdr::{
+    ConfigSettingEntry, ConfigSettingId, LedgerEntryData, LedgerKey, LedgerKeyConfigSetting,
+    Limits, ReadXdr, WriteXdr,
+};
+use utoipa::ToSchema;
+
+// ── Bundled snapshot ──────────────────────────────────────────────────────────
+// Per-transaction limits as configured on Stellar Mainnet (protocol 22).
+// Used whenever live `ConfigSetting` values are unavailable.
+
+const BUNDLED_TX_MAX_INSTRUCTIONS: u64 = 100_000_000;
+const BUNDLED_TX_MEMORY_LIMIT: u64 = 41_943_040;
+const BUNDLED_TX_MAX_READ_LEDGER_ENTRIES: u64 = 40;
+const BUNDLED_TX_MAX_READ_BYTES: u64 = 200_000;
+const BUNDLED_TX_MAX_WRITE_LEDGER_ENTRIES: u64 = 25;
+const BUNDLED_TX_MAX_WRITE_BYTES: u64 = 132_096;
+const BUNDLED_TX_MAX_CONTRACT_EVENTS_SIZE_BYTES: u64 = 8_198;
+pub(crate) const BUNDLED_TX_MAX_SIZE_BYTES: u64 = 132_096;
+
+/// Default usage percentage above which a resource is flagged in headroom reports.
+pub const DEFAULT_WARNING_THRESHOLD_PCT: f64 = 80.0;
+
+/// Where a set of limits came from.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitsSource {
+    /// Read from the network's `ConfigSetting` ledger entries.
+    Live,
+    /// The snapshot compiled into this build.
+    Bundled,
+}
+
+/// Soroban per-transaction resource limits.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NetworkLimits {
+    pub source: LimitsSource,
+    pub tx_max_instructions: u64,
+    pub tx_memory_limit: u64,
+    pub tx_max_read_ledger_entries: u64,
+    pub tx_max_read_bytes: u64,
+    pub tx_max_write_ledger_entries: u64,
+    pub tx_max_write_bytes: u64,
+    pub tx_max_contract_events_size_bytes: u64,
+    pub tx_max_size_bytes: u64,
+}
+
+impl NetworkLimits {
+    /// Limits from the snapshot bundled with this build.
+    pub fn bundled() -> Self {
+        Self {
+            source: LimitsSource::Bundled,
+            tx_max_instructions: BUNDLED_TX_MAX_INSTRUCTIONS,
+            tx_memory_limit: BUNDLED_TX_MEMORY_LIMIT,
+            tx_max_read_ledger_entries: BUNDLED_TX_MAX_READ_LEDGER_ENTRIES,
+            tx_max_read_bytes: BUNDLED_TX_MAX_READ_BYTES,
+            tx_max_write_ledger_entries: BUNDLED_TX_MAX_WRITE_LEDGER_ENTRIES,
+            tx_max_write_bytes: BUNDLED_TX_MAX_WRITE_BYTES,
+            tx_max_contract_events_size_bytes: BUNDLED_TX_MAX_CONTRACT_EVENTS_SIZE_BYTES,
+            tx_max_size_bytes: BUNDLED_TX_MAX_SIZE_BYTES,
+        }
+    }
+
+    /// Ledger keys of the `ConfigSetting` entries that carry per-transaction limits.
+    pub fn config_setting_keys() -> Vec<LedgerKey> {
+        [
+            ConfigSettingId::ContractComputeV0,
+            ConfigSettingId::ContractLedgerCostV0,
+            ConfigSettingId::ContractEventsV0,
+            ConfigSettingId::ContractBandwidthV0,
+        ]
+        .into_iter()
+        .map(|config_setting_id| {
+            LedgerKey::ConfigSetting(LedgerKeyConfigSetting { config_setting_id })
+        })
+        .collect()
+    }
+
+    /// Overwrite the limits carried by a single `ConfigSetting` entry.
+    /// Returns `false` for entries that carry no per-transaction limits.
+    pub fn apply_config_entry(&mut self, entry: &ConfigSettingEntry) -> bool {
+        match entry {
+            ConfigSettingEntry::ContractComputeV0(c) => {
+                self.tx_max_instructions = c.tx_max_instructions.max(0) as u64;
+                self.tx_memory_limit = c.tx_memory_limit as u64;
+            }
+            ConfigSettingEntry::ContractLedgerCostV0(c) => {
+                self.tx_max_read_ledger_entries = c.tx_max_read_ledger_entries as u64;
+                self.tx_max_read_bytes = c.tx_max_read_bytes as u64;
+                self.tx_max_write_ledger_entries = c.tx_max_write_ledger_entries as u64;
+                self.tx_max_write_bytes = c.tx_max_write_bytes as u64;
+            }
+            ConfigSettingEntry::ContractEventsV0(c) => {
+                self.tx_max_contract_events_size_bytes = c.tx_max_contract_events_size_bytes as u64;
+            }
+            ConfigSettingEntry::ContractBandwidthV0(c) => {
+                self.tx_max_size_bytes = c.tx_max_size_bytes as u64;
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Fetch the live limits via `getLedgerEntries` on the provider pool.
+    ///
+    /// Settings missing from the response keep their bundled values.
+    pub async fn fetch(rpc: &SorobanRpc) -> Result<Self, String> {
+        let keys = Self::config_setting_keys()
+            .iter()
+            .map(|k| k.to_xdr(Limits::none()).map(|b| BASE64.encode(b)))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("XDR encode error: {e}"))?;
+
+        let response = rpc
+            .get_ledger_entries(&keys)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut limits = Self {
+            source: LimitsSource::Live,
+            ..Self::bundled()
+        };
+        let mut applied = 0;
+        for entry in &response.entries {
+            let bytes = BASE64
+                .decode(&entry.xdr)
+                .map_err(|e| format!("base64 error: {e}"))?;
+            let data = LedgerEntryData::from_xdr(&bytes, Limits::none())
+                .map_err(|e| format!("XDR decode error: {e}"))?;
+            if let LedgerEntryData::ConfigSetting(setting) = data {
+                if limits.apply_config_entry(&setting) {
+                    applied += 1;
+                }
+            }
+        }
+
+        if applied == 0 {
+            return Err("no ConfigSetting entries returned".to_string());
+        }
+        Ok(limits)
+    }
+
+    /// Compare `resources` against these limits, flagging anything whose usage
+    /// is above `warning_threshold_pct` percent.
+    pub fn headroom(
+        &self,
+        resources: &SorobanResources,
+        warning_threshold_pct: f64,
+    ) -> HeadroomReport {
+        let rows = [
+            (
+                "cpu_instructions",
+                resources.cpu_instructions,
+                self.tx_max_instructions,
+            ),
+            ("ram_bytes", resources.ram_bytes, self.tx_memory_limit),
+            (
+                "read_entries",
+                resources.read_entries,
+                self.tx_max_read_ledger_entries,
+            ),
+            (
+                "write_entries",
+                resources.write_entries,
+                self.tx_max_write_ledger_entries,
+            ),
+            (
+                "ledger_read_bytes",
+                resources.ledger_read_bytes,
+                self.tx_max_read_bytes,
+            ),
+            (
+                "ledger_write_bytes",
+                resources.ledger_write_bytes,
+                self.tx_max_write_bytes,
+            ),
+            (
+                "events_size_bytes",
+                resources.events_size_bytes,
+                self.tx_max_contract_events_size_bytes,
+            ),
+            (
+                "transaction_size_bytes",
+                resources.transaction_size_bytes,
+                self.tx_max_size_bytes,
+            ),
+        ];
+
+        let entries: Vec<HeadroomEntry> = rows
+            .into_iter()
+            .map(|(resource, used, limit)| {
+                let used_pct = if limit > 0 {
+                    used as f64 * 100.0 / limit as f64
+                } else {
+                    0.0
+                };
+                HeadroomEntry {
+                    resource: resource.to_string(),
+                    used,
+                    limit,
+                    used_pct,
+                    warning: used_pct > warning_threshold_pct,
+                }
+            })
+            .collect();
+
+        HeadroomReport {
+            limits_source: self.source,
+            warning_threshold_pct,
+            has_warnings: entries.iter().any(|e| e.warning),
+            entries,
+        }
+    }
+}
+
+/// Usage of one resource relative to its per-transaction limit.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct HeadroomEntry {
+    #[schema(example = "cpu_instructions")]
+    pub resource: String,
+    pub used: u64,
+    pub limit: u64,
+    /// `used` as a percentage of `limit`.
+    pub used_pct: f64,
+    /// `true` when `used_pct` is above the warning threshold.
+    pub warning: bool,
+}
+
+/// How close a simulation comes to the network's per-transaction limits.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct HeadroomReport {
+    pub limits_source: LimitsSource,
+    pub warning_threshold_pct: f64,
+    pub has_warnings: bool,
+    pub entries: Vec<HeadroomEntry>,
+}
+
+impl Default for NetworkLimits {
+    fn default() -> Self {
+        Self::bundled()
+    }
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use soroban_sdk::xdr::{
+        ConfigSettingContractBandwidthV0, ConfigSettingContractComputeV0,
+        ConfigSettingContractEventsV0,
+    };
+
+    fn resources() -> SorobanResources {
+        SorobanResources {
+            cpu_instructions: 90_000_000,
+            ram_bytes: 1_000_000,
+            ledger_read_bytes: 1_000,
+            ledger_write_bytes: 500,
+            transaction_size_bytes: 300,
+            read_entries: 3,
+            write_entries: 1,
+            events_size_bytes: 0,
+        }
+    }
+
+    #[test]
+    fn test_bundled_limits() {
+        let limits = NetworkLimits::default();
+        assert_eq!(limits.source, LimitsSource::Bundled);
+        assert_eq!(limits.tx_max_instructions, 100_000_000);
+        assert_eq!(NetworkLimits::config_setting_keys().len(), 4);
+    }
+
+    #[test]
+    fn test_apply_config_entries() {
+        let mut limits = NetworkLimits::bundled();
+        assert!(
+            limits.apply_config_entry(&ConfigSettingEntry::ContractComputeV0(
+                ConfigSettingContractComputeV0 {
+                    ledger_max_instructions: 500_000_000,
+                    tx_max_instructions: 200_000_000,
+                    fee_rate_per_instructions_increment: 25,
+                    tx_memory_limit: 50_000_000,
+                }
+            ))
+        );
+        assert!(
+            limits.apply_config_entry(&ConfigSettingEntry::ContractEventsV0(
+                ConfigSettingContractEventsV0 {
+                    tx_max_contract_events_size_bytes: 16_384,
+                    fee_contract_events1_kb: 10_000,
+                }
+            ))
+        );
+        assert!(
+            limits.apply_config_entry(&ConfigSettingEntry::ContractBandwidthV0(
+                ConfigSettingContractBandwidthV0 {
+                    ledger_max_txs_size_bytes: 1_000_000,
+                    tx_max_size_bytes: 70_000,
+                    fee_tx_size1_kb: 1_624,
+                }
+            ))
+        );
+        assert!(!limits.apply_config_entry(&ConfigSettingEntry::ContractMaxSizeBytes(65_536)));
+
+        assert_eq!(limits.tx_max_instructions, 200_000_000);
+        assert_eq!(limits.tx_memory_limit, 50_000_000);
+        assert_eq!(limits.tx_max_contract_events_size_bytes, 16_384);
+        assert_eq!(limits.tx_max_size_bytes, 70_000);
+        // Untouched settings keep their bundled values.
+        assert_eq!(limits.tx_max_read_bytes, 200_000);
+    }
+
+    #[test]
+    fn test_headroom_flags_resources_above_threshold() {
+        let report = NetworkLimits::bundled().headroom(&resources(), 80.0);
+        assert_eq!(report.entries.len(), 8);
+        assert!(report.has_warnings);
+
+        let cpu = &report.entries[0];
+        assert_eq!(cpu.resource, "cpu_instructions");
+        assert_eq!(cpu.used_pct, 90.0);
+        assert!(cpu.warning);
+        assert!(report.entries[1..].iter().all(|e| !e.warning));
+    }
+
+    #[test]
+    fn test_headroom_threshold_is_configurable() {
+        let report = NetworkLimits::bundled().headroom(&resources(), 95.0);
+        assert!(!report.has_warnings);
+        assert_eq!(report.warning_threshold_pct, 95.0);
+        assert_eq!(report.limits_source, LimitsSource::Bundled);
+    }
+}
@@ -1,19 +1,53 @@
+mod api_keys;
 mod auth;
 mod benchmarks;
+mod cache;
+mod circuit_breaker;
+mod compare;
 mod errors;
+mod history;
+mod limits;
+#[cfg(test)]
+mod mock_rpc;
+mod networks;
 mod parser;
+mod rate_limit;
 pub mod rpc_provider;
+mod sandbox;
 mod simulation;
+mod singleflight;
+pub mod soroban_rpc;
+mod sweep;
 
+use crate::api_keys::Scope;
+use crate::auth::Role;
+use crate::cache::{CacheSettings, CacheStats, MemoryCache};
+use crate::circuit_breaker::{BreakerConfig, BreakerSnapshot, BreakerTransitions, CircuitState};
+use crate::compare::{
+    CompareTarget, ComparisonReport, CostTypeDelta, FootprintAccess, FootprintChange, MetricDelta,
+};
 use crate::errors::AppError;
-use crate::rpc_provider::{ProviderRegistry, RpcProvider};
-use crate::network_config::NetworkConfig;
-use crate::simulation::{SimulationCache, SimulationEngine, SimulationResult};
+use crate::history::{
+    HistoryEntry, HistoryPage, HistoryQuery, HistoryStore, HistorySummary, NewRun, RunKind,
+    SharedReport,
+};
+use crate::limits::{HeadroomEntry, HeadroomReport, LimitsSource, NetworkLimits};
+use crate::networks::{Network, NetworkInfo, NetworkQuery, Networks};
+use crate::rate_limit::{RateLimiter, RateLimits, UsageReport};
+use crate::rpc_provider::{
+    LatencyStats, PoolStatus, ProviderError, ProviderRegistry, ProviderStatus, RegistryConfig,
+    RpcProvider, SelectionStrategy,
+};
+use crate::simulation::{SimulationCache, SimulationEngine, SimulationKey, SimulationResult};
+use crate::singleflight::SingleFlight;
+use crate::soroban_rpc::SorobanRpc;
+use crate::sweep::{SweepSpec, SweepVariable};
 use axum::{
-    extract::{Json, State},
-    http::{HeaderMap, HeaderName, HeaderValue},
+    extract::{Json, Path, Query, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
     middleware,
-    routing::{get, post},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
     Extension, Router,
 };
 use config::{Config, ConfigError};
@@ -25,7 +59,7 @@ use std::sync::Arc;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
-use utoipa::{OpenApi, ToSchema};
+use utoipa::{IntoParams, OpenApi, ToSchema};
 use utoipa_swagger_ui::SwaggerUi;
 
 #[derive(Debug, Deserialize)]
@@ -37,10 +71,64 @@ struct AppConfig {
     /// `RPC_PROVIDERS` is not set.
     soroban_rpc_url: String,
     jwt_secret: String,
+    /// Passphrase of the default network, which `SOROBAN_RPC_URL`,
+    /// `RPC_PROVIDERS` and `RPC_PROVIDERS_FILE` configure.
     network_passphrase: String,
-    /// Redis URL reserved for the distributed cache migration (issue #65).
-    /// Unused in the MVP in-memory implementation — present so the config
-    /// surface is stable when Redis is wired in.
+    /// Name requests use for the default network (default `testnet`).
+    #[serde(default = "default_default_network")]
+    default_network: String,
+    /// JSON-encoded array of further network profiles requests may select
+    /// with `"network"`. Example:
+    /// ```json
+    /// [
+    ///   {"name":"futurenet"},
+    ///   {"name":"mainnet","rpc_url":"https://mainnet.example.org"},
+    ///   {"name":"private","passphrase":"My Network","providers":[{"name":"a","url":"http://a"}]}
+    /// ]
+    /// ```
+    /// `testnet`, `mainnet`, `futurenet` and `local` profiles default their
+    /// passphrase, and all but `mainnet` their RPC URL.
+    #[serde(default)]
+    networks: String,
+    /// SEP-10 server signing seed (`S…`). Takes precedence over
+    /// `sep10_signing_seed_file`.
+    #[serde(default)]
+    sep10_signing_seed: Redacted,
+    /// File holding the SEP-10 signing seed; created with a fresh seed when
+    /// missing, and updated by key rotation. Empty (with no seed set) uses a
+    /// new random key on every start.
+    #[serde(default = "default_sep10_signing_seed_file")]
+    sep10_signing_seed_file: String,
+    /// Seconds a rotated-out signing key keeps verifying the challenges it
+    /// signed (default 300).
+    #[serde(default = "default_sep10_rotation_overlap_secs")]
+    sep10_rotation_overlap_secs: u64,
+    /// Lifetime of access tokens in seconds (default 900).
+    #[serde(default = "default_access_token_ttl_secs")]
+    access_token_ttl_secs: u64,
+    /// Seconds a refresh token stays usable (default 604800, 7 days).
+    #[serde(default = "default_refresh_token_ttl_secs")]
+    refresh_token_ttl_secs: u64,
+    /// Requests each caller may make to `/analyze` and `/compare` per minute
+    /// (default 60; 0 disables).
+    #[serde(default = "default_rate_limit_per_minute")]
+    rate_limit_per_minute: u32,
+    /// Simulations each caller may run per UTC day (default 1000; 0
+    /// disables).
+    #[serde(default = "default_daily_simulation_quota")]
+    daily_simulation_quota: u64,
+    /// Comma-separated G… accounts with the admin role, which is required
+    /// for provider management, signing key rotation and cache purges.
+    #[serde(default)]
+    admin_accounts: String,
+    /// File API keys are saved to (hashed). Empty keeps them in memory only.
+    #[serde(default = "default_api_keys_file")]
+    api_keys_file: String,
+    /// Advertised as `WEB_AUTH_ENDPOINT` in `/.well-known/stellar.toml`;
+    /// defaults to `http://localhost:<port>/auth`.
+    #[serde(default)]
+    web_auth_endpoint: String,
+    /// Redis URL used when `cache_backend` is `redis`.
     redis_url: String,
     /// JSON-encoded array of RPC provider objects.  Example:
     /// ```json
@@ -52,15 +140,165 @@ struct AppConfig {
     /// When empty or absent the engine falls back to `soroban_rpc_url`.
     #[serde(default)]
     rpc_providers: String,
+    /// File the provider pool is saved to after runtime changes through
+    /// `/providers`. When it exists it takes precedence over `rpc_providers`
+    /// at startup. Empty disables persistence.
+    #[serde(default = "default_rpc_providers_file")]
+    rpc_providers_file: String,
+    /// Order in which healthy providers are tried: `priority` (default),
+    /// `round_robin`, `weighted` or `lowest_latency`.
+    #[serde(default = "default_rpc_selection_strategy")]
+    rpc_selection_strategy: String,
+    /// Ledgers a provider may trail the freshest one before it is tried last
+    /// (default 10).
+    #[serde(default = "default_max_ledger_lag")]
+    max_ledger_lag: u64,
+    /// Milliseconds to wait for a provider before sending a hedged request to
+    /// the next one; 0 (default) disables hedging.
+    #[serde(default)]
+    hedge_delay_ms: u64,
+    /// Consecutive failures that open a provider's circuit breaker (default 3).
+    #[serde(default = "default_circuit_failure_threshold")]
+    circuit_failure_threshold: u64,
+    /// Seconds an open breaker waits before admitting trial requests; doubles
+    /// on every repeated trip (default 300).
+    #[serde(default = "default_circuit_cooldown_secs")]
+    circuit_cooldown_secs: u64,
+    /// Upper bound for the doubled cooldown in seconds (default 3600).
+    #[serde(default = "default_circuit_max_cooldown_secs")]
+    circuit_max_cooldown_secs: u64,
+    /// Trial requests a half-open breaker admits; all must succeed to close
+    /// it (default 1).
+    #[serde(default = "default_circuit_half_open_trials")]
+    circuit_half_open_trials: u32,
     /// Health-check interval in seconds (default 30).
     #[serde(default = "default_health_check_interval")]
     health_check_interval_secs: u64,
+    /// Usage percentage of a network per-transaction limit above which the
+    /// headroom report flags a warning (default 80).
+    #[serde(default = "default_limit_warning_pct")]
+    limit_warning_pct: f64,
+    /// Simulation cache backend: `memory` (default), `disk` or `redis`.
+    #[serde(default = "default_cache_backend")]
+    cache_backend: String,
+    /// File used by the `disk` cache backend.
+    #[serde(default = "default_cache_path")]
+    cache_path: String,
+    /// File holding each account's analysis history. Empty keeps history in
+    /// memory only.
+    #[serde(default = "default_history_path")]
+    history_path: String,
+    /// Maximum number of cached simulation results (default 1000).
+    #[serde(default = "default_cache_capacity")]
+    cache_capacity: u64,
+    /// Seconds a cached simulation result stays valid (default 3600).
+    #[serde(default = "default_cache_ttl_secs")]
+    cache_ttl_secs: u64,
+}
+
+/// A config value that is never written to logs.
+#[derive(Default, Deserialize)]
+#[serde(transparent)]
+struct Redacted(String);
+
+impl std::fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.is_empty() {
+            f.write_str("\"\"")
+        } else {
+            f.write_str("\"<redacted>\"")
+        }
+    }
+}
+
+fn default_default_network() -> String {
+    "testnet".to_string()
+}
+
+fn default_sep10_signing_seed_file() -> String {
+    "soroscope-sep10.seed".to_string()
+}
+
+fn default_sep10_rotation_overlap_secs() -> u64 {
+    auth::DEFAULT_ROTATION_OVERLAP.as_secs()
+}
+
+fn default_access_token_ttl_secs() -> u64 {
+    auth::DEFAULT_ACCESS_TOKEN_TTL.as_secs()
+}
+
+fn default_refresh_token_ttl_secs() -> u64 {
+    auth::DEFAULT_REFRESH_TOKEN_TTL.as_secs()
+}
+
+fn default_rate_limit_per_minute() -> u32 {
+    rate_limit::DEFAULT_REQUESTS_PER_MINUTE
+}
+
+fn default_daily_simulation_quota() -> u64 {
+    rate_limit::DEFAULT_DAILY_SIMULATION_QUOTA
+}
+
+fn default_api_keys_file() -> String {
+    "soroscope-api-keys.json".to_string()
+}
+
+fn default_rpc_providers_file() -> String {
+    "soroscope-providers.json".to_string()
+}
+
+fn default_rpc_selection_strategy() -> String {
+    "priority".to_string()
+}
+
+fn default_max_ledger_lag() -> u64 {
+    rpc_provider::DEFAULT_MAX_LEDGER_LAG
+}
+
+fn default_circuit_failure_threshold() -> u64 {
+    circuit_breaker::DEFAULT_FAILURE_THRESHOLD
+}
+
+fn default_circuit_cooldown_secs() -> u64 {
+    circuit_breaker::DEFAULT_COOLDOWN.as_secs()
+}
+
+fn default_circuit_max_cooldown_secs() -> u64 {
+    circuit_breaker::DEFAULT_MAX_COOLDOWN.as_secs()
+}
+
+fn default_circuit_half_open_trials() -> u32 {
+    circuit_breaker::DEFAULT_HALF_OPEN_TRIALS
 }
 
 fn default_health_check_interval() -> u64 {
     30
 }
 
+fn default_limit_warning_pct() -> f64 {
+    limits::DEFAULT_WARNING_THRESHOLD_PCT
+}
+
+fn default_cache_backend() -> String {
+    "memory".to_string()
+}
+
+fn default_history_path() -> String {
+    "soroscope-history.redb".to_string()
+}
+
+fn default_cache_path() -> String {
+    "soroscope-cache.redb".to_string()
+}
+
+fn default_cache_capacity() -> u64 {
+    cache::DEFAULT_CACHE_CAPACITY
+}
+
+fn default_cache_ttl_secs() -> u64 {
+    cache::DEFAULT_CACHE_TTL_SECS
+}
+
 fn load_config() -> Result<AppConfig, ConfigError> {
     dotenvy::dotenv().ok();
 
@@ -70,18 +308,75 @@ fn load_config() -> Result<AppConfig, ConfigError> {
         .set_default("rust_log", "info")?
         .set_default("soroban_rpc_url", "https://soroban-testnet.stellar.org")?
         .set_default("jwt_secret", "dev-secret-change-in-production")?
-        .set_default("network_passphrase", "Test SDF Network ; September 2015")?
+        .set_default("network_passphrase", networks::TESTNET_PASSPHRASE)?
+        .set_default("default_network", "testnet")?
+        .set_default("networks", "")?
+        .set_default("sep10_signing_seed", "")?
+        .set_default("sep10_signing_seed_file", "soroscope-sep10.seed")?
+        .set_default(
+            "sep10_rotation_overlap_secs",
+            auth::DEFAULT_ROTATION_OVERLAP.as_secs(),
+        )?
+        .set_default(
+            "access_token_ttl_secs",
+            auth::DEFAULT_ACCESS_TOKEN_TTL.as_secs(),
+        )?
+        .set_default(
+            "refresh_token_ttl_secs",
+            auth::DEFAULT_REFRESH_TOKEN_TTL.as_secs(),
+        )?
+        .set_default("web_auth_endpoint", "")?
         .set_default("redis_url", "redis://127.0.0.1:6379")?
         .set_default("rpc_providers", "")?
+        .set_default(
+            "rate_limit_per_minute",
+            rate_limit::DEFAULT_REQUESTS_PER_MINUTE,
+        )?
+        .set_default(
+            "daily_simulation_quota",
+            rate_limit::DEFAULT_DAILY_SIMULATION_QUOTA,
+        )?
+        .set_default("api_keys_file", "soroscope-api-keys.json")?
+        .set_default("rpc_providers_file", "soroscope-providers.json")?
+        .set_default("rpc_selection_strategy", "priority")?
+        .set_default("max_ledger_lag", rpc_provider::DEFAULT_MAX_LEDGER_LAG)?
+        .set_default("hedge_delay_ms", 0)?
+        .set_default(
+            "circuit_failure_threshold",
+            circuit_breaker::DEFAULT_FAILURE_THRESHOLD,
+        )?
+        .set_default(
+            "circuit_cooldown_secs",
+            circuit_breaker::DEFAULT_COOLDOWN.as_secs(),
+        )?
+        .set_default(
+            "circuit_max_cooldown_secs",
+            circuit_breaker::DEFAULT_MAX_COOLDOWN.as_secs(),
+        )?
+        .set_default(
+            "circuit_half_open_trials",
+            circuit_breaker::DEFAULT_HALF_OPEN_TRIALS,
+        )?
         .set_default("health_check_interval_secs", 30)?
+        .set_default("limit_warning_pct", limits::DEFAULT_WARNING_THRESHOLD_PCT)?
+        .set_default("cache_backend", "memory")?
+        .set_default("cache_path", "soroscope-cache.redb")?
+        .set_default("history_path", "soroscope-history.redb")?
+        .set_default("cache_capacity", cache::DEFAULT_CACHE_CAPACITY)?
+        .set_default("cache_ttl_secs", cache::DEFAULT_CACHE_TTL_SECS)?
         .build()?;
 
     settings.try_deserialize()
 }
 
-/// Parse the `RPC_PROVIDERS` env var (JSON array) or fall back to wrapping the
-/// single `SOROBAN_RPC_URL` into a one-element provider list.
+/// Load the pool saved by runtime changes, else parse the `RPC_PROVIDERS` env
+/// var (JSON array), else wrap the single `SOROBAN_RPC_URL` into a
+/// one-element provider list.
 fn build_providers(config: &AppConfig) -> Vec<RpcProvider> {
+    if let Some(providers) = providers_file(config).and_then(|path| load_saved_providers(&path)) {
+        return providers;
+    }
+
     if !config.rpc_providers.is_empty() {
         match serde_json::from_str::<Vec<RpcProvider>>(&config.rpc_providers) {
             Ok(providers) if !providers.is_empty() => {
@@ -103,19 +398,128 @@ fn build_providers(config: &AppConfig) -> Vec<RpcProvider> {
         }
     }
 
-    vec![RpcProvider {
-        name: "default".to_string(),
-        url: config.soroban_rpc_url.clone(),
-        auth_header: None,
-        auth_value: None,
-    }]
+    vec![RpcProvider::new("default", config.soroban_rpc_url.clone())]
+}
+
+fn open_history(config: &AppConfig) -> HistoryStore {
+    if !config.history_path.is_empty() {
+        match HistoryStore::open(&config.history_path) {
+            Ok(store) => return store,
+            Err(e) => tracing::warn!(
+                error = %e,
+                path = %config.history_path,
+                "Could not open history database, keeping history in memory"
+            ),
+        }
+    }
+    HistoryStore::in_memory().expect("Failed to create in-memory history store")
+}
+
+fn providers_file(config: &AppConfig) -> Option<PathBuf> {
+    (!config.rpc_providers_file.is_empty()).then(|| PathBuf::from(&config.rpc_providers_file))
+}
+
+/// Where the pool of the non-default network `name` is saved:
+/// `soroscope-providers.json` becomes `soroscope-providers.<name>.json`.
+fn network_providers_file(config: &AppConfig, name: &str) -> Option<PathBuf> {
+    providers_file(config).map(|path| path.with_extension(format!("{name}.json")))
+}
+
+/// The pool saved by runtime changes, if `path` holds a non-empty one.
+fn load_saved_providers(path: &std::path::Path) -> Option<Vec<RpcProvider>> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => match serde_json::from_str::<Vec<RpcProvider>>(&contents) {
+            Ok(providers) if !providers.is_empty() => {
+                tracing::info!(
+                    count = providers.len(),
+                    path = %path.display(),
+                    "Loaded RPC providers from saved pool"
+                );
+                return Some(providers);
+            }
+            Ok(_) => tracing::warn!(path = %path.display(), "Saved provider pool is empty"),
+            Err(e) => tracing::warn!(
+                error = %e,
+                path = %path.display(),
+                "Failed to parse saved provider pool"
+            ),
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => tracing::warn!(
+            error = %e,
+            path = %path.display(),
+            "Failed to read saved provider pool"
+        ),
+    }
+    None
+}
+
+/// Write the provider pool to `path`, replacing it atomically.
+fn save_providers(path: &std::path::Path, providers: &[RpcProvider]) -> std::io::Result<()> {
+    let json = serde_json::to_string_pretty(providers)?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json)?;
+    std::fs::rename(&tmp, path)
 }
 
 /// Shared application state injected into every Axum handler via [`State`].
 struct AppState {
-    #[allow(dead_code)] // will be used when RPC simulation is wired into analyze handler
-    engine: SimulationEngine,
+    /// Networks requests can simulate against, each with its own engine
+    /// and provider pool.
+    networks: Networks,
+    /// Shared by all networks; keys include the network passphrase.
     cache: Arc<SimulationCache>,
+    /// Coalesces concurrent identical simulations, keyed like the cache.
+    inflight: SingleFlight<Result<SimulationResult, String>>,
+    /// Cache keys confirmed fresh within the last [`FRESHNESS_RECHECK_INTERVAL`];
+    /// their hits are served without asking the RPC again.
+    verified: moka::future::Cache<String, ()>,
+    /// Per-caller limits on simulation requests.
+    rate_limiter: Arc<RateLimiter>,
+    /// Past analyses of each account.
+    history: HistoryStore,
+}
+
+/// How long a cached result confirmed fresh is served without rechecking its
+/// footprint; about one ledger close.
+const FRESHNESS_RECHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+fn verified_keys() -> moka::future::Cache<String, ()> {
+    moka::future::Cache::builder()
+        .max_capacity(10_000)
+        .time_to_live(FRESHNESS_RECHECK_INTERVAL)
+        .build()
+}
+
+impl AppState {
+    /// Add a completed run to `account`'s history. Failures are logged; the
+    /// response is sent either way.
+    async fn record_run(&self, account: &str, run: NewRun) {
+        if let Err(e) = self.history.record(account, run).await {
+            tracing::warn!(error = %e, "Failed to record analysis history");
+        }
+    }
+}
+
+/// Save `network`'s provider pool after a runtime change. Failures are
+/// logged; the change itself stays in effect.
+fn persist_providers(network: &Network) {
+    let Some(path) = &network.providers_file else {
+        return;
+    };
+    match save_providers(path, &network.registry.providers()) {
+        Ok(()) => tracing::info!(
+            network = %network.name,
+            path = %path.display(),
+            "Saved RPC provider pool"
+        ),
+        Err(e) => tracing::error!(
+            error = %e,
+            network = %network.name,
+            path = %path.display(),
+            "Failed to save RPC provider pool"
+        ),
+    }
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
@@ -128,6 +532,11 @@ pub struct AnalyzeRequest {
     pub args: Option<Vec<String>>,
     /// Map of Key-Base64 to Value-Base64 ledger entry overrides
     pub ledger_overrides: Option<HashMap<String, String>>,
+    /// Transaction source account (G…); a zero account is used when absent
+    pub source_account: Option<String>,
+    /// Network profile to simulate against; the default network when absent
+    #[schema(example = "testnet")]
+    pub network: Option<String>,
 }
 
 #[derive(Serialize, ToSchema)]
@@ -149,6 +558,17 @@ pub struct ResourceReport {
     pub transaction_size_bytes: u64,
     /// Report showing which data was injected vs live
     pub state_dependency: Option<Vec<StateDependencyReport>>,
+    /// Usage relative to the network's per-transaction limits
+    pub headroom: Option<HeadroomReport>,
+    /// RPC provider that served the simulation (absent for local runs)
+    #[schema(example = "stellar-testnet")]
+    pub provider: Option<String>,
+    /// Ledger the simulation ran against
+    #[schema(example = 123456)]
+    pub latest_ledger: u64,
+    /// Ledgers the serving provider trailed the freshest known provider by
+    #[schema(example = 0)]
+    pub ledger_lag: Option<u64>,
 }
 
 #[derive(Serialize, ToSchema, Debug)]
@@ -173,6 +593,10 @@ fn to_report(result: &SimulationResult) -> ResourceReport {
                 })
                 .collect()
         }),
+        headroom: result.headroom.clone(),
+        provider: result.provider.clone(),
+        latest_ledger: result.latest_ledger,
+        ledger_lag: result.ledger_lag,
     }
 }
 
@@ -182,6 +606,7 @@ fn to_report(result: &SimulationResult) -> ResourceReport {
     request_body = AnalyzeRequest,
     responses(
         (status = 200, description = "Resource analysis successful", body = ResourceReport),
+        (status = 400, description = "Unknown network or invalid contract id"),
         (status = 401, description = "Unauthorized"),
         (status = 500, description = "Analysis failed")
     ),
@@ -192,6 +617,7 @@ fn to_report(result: &SimulationResult) -> ResourceReport {
 )]
 async fn analyze(
     State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<auth::AuthContext>,
     Json(payload): Json<AnalyzeRequest>,
 ) -> Result<(HeaderMap, Json<ResourceReport>), AppError> {
     tracing::info!(
@@ -199,30 +625,91 @@ async fn analyze(
         function_name = %payload.function_name,
         "Received analyze request"
     );
+    // The contract id prefixes the cache key, so it must be a real strkey.
+    if stellar_strkey::Contract::from_string(&payload.contract_id).is_err() {
+        return Err(AppError::BadRequest(format!(
+            "Invalid contract id: {}",
+            payload.contract_id
+        )));
+    }
 
+    let network = state.networks.get(payload.network.as_deref())?;
     let args = payload.args.clone().unwrap_or_default();
-    let cache_key =
-        SimulationCache::generate_key(&payload.contract_id, &payload.function_name, &args);
+    let cache_key = SimulationCache::generate_key(
+        &SimulationKey::new(
+            &network.passphrase,
+            &payload.contract_id,
+            &payload.function_name,
+            &args,
+        )
+        .with_source_account(payload.source_account.as_deref())
+        .with_overrides(payload.ledger_overrides.as_ref()),
+    );
 
-    let (result, cache_status): (SimulationResult, &'static str) =
-        if let Some(cached) = state.cache.get(&cache_key).await {
-            (cached, "HIT")
+    // A hit is only served while none of its footprint entries changed
+    // on-chain. Each key is rechecked at most once per recheck interval.
+    let mut cache_status: &'static str = "MISS";
+    let mut cached = state.cache.get(&cache_key).await;
+    if let Some(hit) = &cached {
+        if state.verified.contains_key(&cache_key) {
+            cache_status = "HIT";
         } else {
-            let sim: SimulationResult = state
-                .engine
-                .simulate_from_contract_id(
-                    &payload.contract_id,
-                    &payload.function_name,
-                    args,
-                    payload.ledger_overrides.clone(),
-                )
-                .await
-                .map_err(|e| AppError::Internal(format!("Simulation failed: {}", e)))?;
-            state.cache.set(cache_key, sim.clone()).await;
-            (sim, "MISS")
-        };
+            match network.engine.is_stale(hit).await {
+                Ok(false) => {
+                    state.verified.insert(cache_key.clone(), ()).await;
+                    cache_status = "HIT";
+                }
+                Ok(true) => {
+                    state.cache.invalidate(&cache_key).await;
+                    cached = None;
+                    cache_status = "STALE";
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "Could not check cached result freshness, re-simulating");
+                    cached = None;
+                    cache_status = "STALE";
+                }
+            }
+        }
+    }
+
+    let result: SimulationResult = match cached {
+        Some(hit) => hit,
+        None => {
+            // Identical requests arriving while this one is simulating share
+            // its RPC call instead of issuing their own.
+            let (outcome, coalesced) = state
+                .inflight
+                .run(&cache_key, || async {
+                    let sim = network
+                        .engine
+                        .simulate_from_contract_id(
+                            &payload.contract_id,
+                            &payload.function_name,
+                            args,
+                            payload.ledger_overrides.clone(),
+                            payload.source_account.as_deref(),
+                        )
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    state.cache.set(cache_key.clone(), sim.clone()).await;
+                    state.verified.insert(cache_key.clone(), ()).await;
+                    Ok(sim)
+                })
+                .await;
+            if coalesced {
+                cache_status = "COALESCED";
+                tracing::info!(
+                    coalesced_total = state.inflight.coalesced(),
+                    executed_total = state.inflight.executed(),
+                    "Simulation shared with an in-flight request"
+                );
+            }
+            outcome.map_err(|e| AppError::Internal(format!("Simulation failed: {}", e)))?
+        }
+    };
 
-    state.cache.log_stats();
+    state.cache.log_stats().await;
 
     let mut headers = HeaderMap::new();
     headers.insert(
@@ -230,123 +717,1054 @@ async fn analyze(
         HeaderValue::from_static(cache_status),
     );
 
-    Ok((headers, Json(to_report(&result))))
+    let report = to_report(&result);
+    state
+        .record_run(
+            &caller.subject,
+            NewRun {
+                kind: RunKind::Analyze,
+                contract_id: payload.contract_id,
+                candidate: None,
+                function_name: payload.function_name,
+                args: payload.args.unwrap_or_default(),
+                network: Some(network.name.clone()),
+                result: serde_json::to_value(&report).unwrap_or_default(),
+            },
+        )
+        .await;
+
+    Ok((headers, Json(report)))
 }
 
-#[derive(OpenApi)]
-#[openapi(
-    paths(analyze, auth::challenge_handler, auth::verify_handler),
-    components(schemas(
-        AnalyzeRequest, ResourceReport,
-        auth::ChallengeRequest, auth::ChallengeResponse,
-        auth::VerifyRequest, auth::VerifyResponse
-    )),
-    tags(
-        (name = "Analysis", description = "Soroban contract resource analysis endpoints"),
-        (name = "Auth", description = "SEP-10 wallet authentication")
-    ),
-    info(
-        title = "SoroScope API",
-        version = "0.1.0",
-        description = "API for analyzing Soroban smart contract resource consumption"
-    )
-)]
-struct ApiDoc;
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CompareRequest {
+    /// Baseline: a deployed contract id or base64-encoded WASM
+    #[schema(example = "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC")]
+    pub baseline: String,
+    /// Candidate: a deployed contract id or base64-encoded WASM
+    pub candidate: String,
+    #[schema(example = "hello")]
+    pub function_name: String,
+    #[schema(example = "[]")]
+    pub args: Option<Vec<String>>,
+    /// Network profile both sides run on; the default network when absent
+    #[schema(example = "testnet")]
+    pub network: Option<String>,
+}
 
-async fn health_check() -> &'static str {
-    "OK"
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct CompareQuery {
+    /// Output format: `json` (default), `markdown` or `table`
+    pub format: Option<String>,
 }
 
-#[tokio::main]
-async fn main() {
-    if env::var("RUST_LOG").is_err() {
-        env::set_var("RUST_LOG", "info");
+/// Short label for a comparison side: the contract id, or the WASM hash.
+fn target_label(target: &CompareTarget) -> String {
+    match target {
+        CompareTarget::Contract(id) => id.clone(),
+        CompareTarget::Wasm(wasm) => {
+            use sha2::{Digest, Sha256};
+            format!("wasm:{}", &hex::encode(Sha256::digest(wasm))[..12])
+        }
     }
+}
 
-    tracing_subscriber::registry()
-        .with(EnvFilter::from_default_env())
-        .with(tracing_subscriber::fmt::layer())
-        .init();
-
-    tracing::info!("SoroScope Starting...");
+#[utoipa::path(
+    post,
+    path = "/compare",
+    request_body = CompareRequest,
+    params(CompareQuery),
+    responses(
+        (status = 200, description = "Comparison of the two simulations", body = ComparisonReport),
+        (status = 400, description = "Invalid target, format or network"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Simulation failed")
+    ),
+    security(
+        ("jwt" = [])
+    ),
+    tag = "Analysis"
+)]
+async fn compare_handler(
+    State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<auth::AuthContext>,
+    Query(query): Query<CompareQuery>,
+    Json(payload): Json<CompareRequest>,
+) -> Result<Response, AppError> {
+    let format = query.format.as_deref().unwrap_or("json");
+    if !matches!(format, "json" | "markdown" | "table") {
+        return Err(AppError::BadRequest(format!("Unknown format: {}", format)));
+    }
 
-    let config = load_config().expect("Failed to load configuration");
-    tracing::info!("SoroScope initialized with config: {:?}", config);
+    let baseline = CompareTarget::parse(&payload.baseline)
+        .map_err(|e| AppError::BadRequest(format!("baseline: {}", e)))?;
+    let candidate = CompareTarget::parse(&payload.candidate)
+        .map_err(|e| AppError::BadRequest(format!("candidate: {}", e)))?;
     tracing::info!(
-        redis_url = %config.redis_url,
-        "Cache config: using in-memory (moka) MVP; Redis URL reserved for future migration"
+        baseline = %target_label(&baseline),
+        candidate = %target_label(&candidate),
+        function_name = %payload.function_name,
+        "Received compare request"
     );
 
-    let args: Vec<String> = env::args().collect();
-
-    if args.len() > 1 && args[1] == "benchmark" {
-        tracing::info!("Starting SoroScope Benchmark...");
-
-        let possible_paths = vec![
-            "target/wasm32-unknown-unknown/release/soroban_token_contract.wasm",
-            "../target/wasm32-unknown-unknown/release/soroban_token_contract.wasm",
-        ];
+    let network = state.networks.get(payload.network.as_deref())?;
+    let args = payload.args.unwrap_or_default();
+    let history_args = args.clone();
+    let (a, b) = tokio::try_join!(
+        baseline.simulate(&network.engine, &payload.function_name, args.clone()),
+        candidate.simulate(&network.engine, &payload.function_name, args),
+    )
+    .map_err(|e| AppError::Internal(format!("Simulation failed: {}", e)))?;
 
-        let mut wasm_path = None;
-        for p in possible_paths {
-            let path = PathBuf::from(p);
-            if path.exists() {
-                wasm_path = Some(path);
-                break;
-            }
-        }
+    let report = ComparisonReport::new(&target_label(&baseline), &a, &target_label(&candidate), &b);
+    state
+        .record_run(
+            &caller.subject,
+            NewRun {
+                kind: RunKind::Compare,
+                contract_id: target_label(&baseline),
+                candidate: Some(target_label(&candidate)),
+                function_name: payload.function_name,
+                args: history_args,
+                network: Some(network.name.clone()),
+                result: serde_json::to_value(&report).unwrap_or_default(),
+            },
+        )
+        .await;
+    Ok(match format {
+        "markdown" => (
+            [(header::CONTENT_TYPE, "text/markdown; charset=utf-8")],
+            report.render_markdown(),
+        )
+            .into_response(),
+        "table" => report.render_table().into_response(),
+        _ => Json(report).into_response(),
+    })
+}
 
-        if let Some(path) = wasm_path {
-            if let Err(e) = benchmarks::run_token_benchmark(path) {
-                tracing::error!("Benchmark failed: {}", e);
-            }
-        } else {
-            tracing::error!(
-                "Could not find soroban_token_contract.wasm. Build the contract first."
-            );
-        }
+#[derive(Serialize, ToSchema)]
+pub struct PurgeResponse {
+    pub contract_id: String,
+    /// Number of cached results removed
+    pub removed: u64,
+}
 
-        return;
+#[utoipa::path(
+    delete,
+    path = "/cache/contract/{contract_id}",
+    params(
+        ("contract_id" = String, Path, description = "Contract whose cached results are purged")
+    ),
+    responses(
+        (status = 200, description = "Cached results removed", body = PurgeResponse),
+        (status = 400, description = "Invalid contract id"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("jwt" = [])
+    ),
+    tag = "Cache"
+)]
+async fn purge_contract_cache(
+    State(state): State<Arc<AppState>>,
+    Path(contract_id): Path<String>,
+) -> Result<Json<PurgeResponse>, AppError> {
+    if stellar_strkey::Contract::from_string(&contract_id).is_err() {
+        return Err(AppError::BadRequest(format!(
+            "Invalid contract id: {}",
+            contract_id
+        )));
     }
+    let removed = state.cache.purge_contract(&contract_id).await;
+    tracing::info!(contract_id = %contract_id, removed, "Purged cached simulations");
+    Ok(Json(PurgeResponse {
+        contract_id,
+        removed,
+    }))
+}
 
-    tracing::info!("Starting SoroScope API Server...");
+#[derive(Serialize, ToSchema)]
+pub struct ClearResponse {
+    /// Number of cached results removed
+    pub removed: u64,
+}
 
-    let auth_state = Arc::new(auth::AuthState::new(
-        config.jwt_secret.clone(),
-        None,
-        config.network_passphrase.clone(),
-    ));
-    tracing::info!(
-        "SEP-10 server account: {}",
-        auth_state.server_stellar_address()
-    );
-    // ── Multi-node RPC setup ────────────────────────────────────────────
-    let providers = build_providers(&config);
-    let provider_names: Vec<&str> = providers.iter().map(|p| p.name.as_str()).collect();
-    tracing::info!(providers = ?provider_names, "RPC provider pool");
+#[utoipa::path(
+    get,
+    path = "/history",
+    params(HistoryQuery),
+    responses(
+        (status = 200, description = "The caller's past runs, newest first", body = HistoryPage),
+        (status = 401, description = "Missing or invalid credentials")
+    ),
+    security(
+        ("jwt" = []),
+        ("api_key" = [])
+    ),
+    tag = "History"
+)]
+async fn list_history(
+    State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<auth::AuthContext>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<HistoryPage>, AppError> {
+    let page = state
+        .history
+        .list(&caller.subject, query)
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    Ok(Json(page))
+}
 
-    let registry = ProviderRegistry::new(providers);
+#[utoipa::path(
+    get,
+    path = "/history/{id}",
+    params(
+        ("id" = String, Path, description = "History entry id")
+    ),
+    responses(
+        (status = 200, description = "A past run with its full result", body = HistoryEntry),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 404, description = "No such run for this account")
+    ),
+    security(
+        ("jwt" = []),
+        ("api_key" = [])
+    ),
+    tag = "History"
+)]
+async fn get_history_entry(
+    State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<auth::AuthContext>,
+    Path(id): Path<String>,
+) -> Result<Json<HistoryEntry>, AppError> {
+    state
+        .history
+        .get(&caller.subject, &id)
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("History entry '{id}'")))
+}
 
-    // Spawn background health checker.
-    let health_interval = std::time::Duration::from_secs(config.health_check_interval_secs);
-    let _health_handle = registry.spawn_health_checker(health_interval);
-    tracing::info!(
-        interval_secs = config.health_check_interval_secs,
-        "Background RPC health checker started"
-    );
+#[utoipa::path(
+    delete,
+    path = "/history/{id}",
+    params(
+        ("id" = String, Path, description = "History entry id")
+    ),
+    responses(
+        (status = 204, description = "Run deleted"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 404, description = "No such run for this account")
+    ),
+    security(
+        ("jwt" = []),
+        ("api_key" = [])
+    ),
+    tag = "History"
+)]
+async fn delete_history_entry(
+    State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<auth::AuthContext>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let removed = state
+        .history
+        .delete(&caller.subject, &id)
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    if !removed {
+        return Err(AppError::NotFound(format!("History entry '{id}'")));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
 
-    let app_state = Arc::new(AppState {
-        engine: SimulationEngine::with_registry(Arc::clone(&registry)),
-        cache: SimulationCache::new(),
-    });
+#[utoipa::path(
+    delete,
+    path = "/history",
+    responses(
+        (status = 200, description = "All of the caller's runs deleted", body = ClearResponse),
+        (status = 401, description = "Missing or invalid credentials")
+    ),
+    security(
+        ("jwt" = []),
+        ("api_key" = [])
+    ),
+    tag = "History"
+)]
+async fn clear_history(
+    State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<auth::AuthContext>,
+) -> Result<Json<ClearResponse>, AppError> {
+    let removed = state
+        .history
+        .clear(&caller.subject)
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    Ok(Json(ClearResponse { removed }))
+}
 
+#[utoipa::path(
+    post,
+    path = "/history/{id}/share",
+    params(
+        ("id" = String, Path, description = "History entry id")
+    ),
+    responses(
+        (status = 200, description = "Run published at /reports/{report_id}", body = HistoryEntry),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 404, description = "No such run for this account")
+    ),
+    security(
+        ("jwt" = []),
+        ("api_key" = [])
+    ),
+    tag = "History"
+)]
+async fn share_history_entry(
+    State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<auth::AuthContext>,
+    Path(id): Path<String>,
+) -> Result<Json<HistoryEntry>, AppError> {
+    set_shared(&state, &caller, &id, true).await.map(Json)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/history/{id}/share",
+    params(
+        ("id" = String, Path, description = "History entry id")
+    ),
+    responses(
+        (status = 204, description = "Run no longer shared"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 404, description = "No such run for this account")
+    ),
+    security(
+        ("jwt" = []),
+        ("api_key" = [])
+    ),
+    tag = "History"
+)]
+async fn unshare_history_entry(
+    State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<auth::AuthContext>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    set_shared(&state, &caller, &id, false).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn set_shared(
+    state: &AppState,
+    caller: &auth::AuthContext,
+    id: &str,
+    shared: bool,
+) -> Result<HistoryEntry, AppError> {
+    state
+        .history
+        .set_shared(&caller.subject, id, shared)
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("History entry '{id}'")))
+}
+
+#[utoipa::path(
+    get,
+    path = "/reports/{id}",
+    params(
+        ("id" = String, Path, description = "Report id (`report_id` of a shared run)")
+    ),
+    responses(
+        (status = 200, description = "A shared analysis report", body = SharedReport),
+        (status = 404, description = "No report with this id is shared")
+    ),
+    tag = "History"
+)]
+async fn get_report(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<SharedReport>, AppError> {
+    state
+        .history
+        .report(&id)
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("Report '{id}'")))
+}
+
+#[utoipa::path(
+    get,
+    path = "/me/usage",
+    responses(
+        (status = 200, description = "Rate limit and daily quota usage of the caller", body = UsageReport),
+        (status = 401, description = "Missing or invalid credentials")
+    ),
+    security(
+        ("jwt" = []),
+        ("api_key" = [])
+    ),
+    tag = "Auth"
+)]
+async fn usage_handler(
+    State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<auth::AuthContext>,
+) -> Json<UsageReport> {
+    Json(state.rate_limiter.usage(&caller.subject).await)
+}
+
+#[utoipa::path(
+    get,
+    path = "/cache/stats",
+    responses(
+        (status = 200, description = "Current cache statistics", body = CacheStats),
+        (status = 401, description = "Missing or invalid credentials")
+    ),
+    security(
+        ("jwt" = []),
+        ("api_key" = [])
+    ),
+    tag = "Cache"
+)]
+async fn cache_stats(State(state): State<Arc<AppState>>) -> Json<CacheStats> {
+    let mut stats = state.cache.stats().await;
+    stats.coalesced_requests = state.inflight.coalesced();
+    Json(stats)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/cache",
+    responses(
+        (status = 200, description = "All cached results removed", body = ClearResponse),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("jwt" = [])
+    ),
+    tag = "Cache"
+)]
+async fn clear_cache(State(state): State<Arc<AppState>>) -> Json<ClearResponse> {
+    let removed = state.cache.clear().await;
+    tracing::info!(removed, "Cleared simulation cache");
+    Json(ClearResponse { removed })
+}
+
+#[utoipa::path(
+    delete,
+    path = "/cache/{key}",
+    params(
+        ("key" = String, Path, description = "Cache key, as `contract_id:sha256`")
+    ),
+    responses(
+        (status = 204, description = "Entry removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "No entry under this key")
+    ),
+    security(
+        ("jwt" = [])
+    ),
+    tag = "Cache"
+)]
+async fn delete_cache_entry(
+    State(state): State<Arc<AppState>>,
+    Path(key): Path<String>,
+) -> Result<StatusCode, AppError> {
+    if state.cache.remove(&key).await {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(AppError::NotFound(format!(
+            "No cache entry for key {}",
+            key
+        )))
+    }
+}
+
+impl From<ProviderError> for AppError {
+    fn from(e: ProviderError) -> Self {
+        match e {
+            ProviderError::NotFound(_) => AppError::NotFound(e.to_string()),
+            _ => AppError::BadRequest(e.to_string()),
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/networks",
+    responses(
+        (status = 200, description = "Network profiles requests can select", body = [NetworkInfo])
+    ),
+    tag = "Providers"
+)]
+async fn list_networks(State(state): State<Arc<AppState>>) -> Json<Vec<NetworkInfo>> {
+    Json(state.networks.info())
+}
+
+#[utoipa::path(
+    get,
+    path = "/providers",
+    params(NetworkQuery),
+    responses(
+        (status = 200, description = "Health, breaker, ledger and latency of every provider; URLs are cut down to scheme and host", body = PoolStatus),
+        (status = 400, description = "Unknown network")
+    ),
+    tag = "Providers"
+)]
+async fn list_providers(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<NetworkQuery>,
+) -> Result<Json<PoolStatus>, AppError> {
+    let network = state.networks.get(query.network.as_deref())?;
+    Ok(Json(network.registry.pool_status().redacted()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/providers",
+    params(NetworkQuery),
+    request_body = RpcProvider,
+    responses(
+        (status = 201, description = "Provider added", body = PoolStatus),
+        (status = 400, description = "Invalid or duplicate provider, or unknown network"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("jwt" = [])
+    ),
+    tag = "Providers"
+)]
+async fn add_provider(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<NetworkQuery>,
+    Json(provider): Json<RpcProvider>,
+) -> Result<(StatusCode, Json<PoolStatus>), AppError> {
+    let network = state.networks.get(query.network.as_deref())?;
+    network.registry.add_provider(provider)?;
+    persist_providers(network);
+    Ok((StatusCode::CREATED, Json(network.registry.pool_status())))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/providers/{name}",
+    params(
+        ("name" = String, Path, description = "Name of the provider to remove"),
+        NetworkQuery
+    ),
+    responses(
+        (status = 200, description = "Provider removed", body = PoolStatus),
+        (status = 400, description = "The last provider cannot be removed, or unknown network"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Unknown provider")
+    ),
+    security(
+        ("jwt" = [])
+    ),
+    tag = "Providers"
+)]
+async fn remove_provider(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Query(query): Query<NetworkQuery>,
+) -> Result<Json<PoolStatus>, AppError> {
+    let network = state.networks.get(query.network.as_deref())?;
+    network.registry.remove_provider(&name)?;
+    persist_providers(network);
+    Ok(Json(network.registry.pool_status()))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ReorderRequest {
+    /// Every provider name, in the new priority order
+    #[schema(example = json!(["blockdaemon", "stellar-testnet"]))]
+    pub order: Vec<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/providers/reorder",
+    params(NetworkQuery),
+    request_body = ReorderRequest,
+    responses(
+        (status = 200, description = "Providers reordered", body = PoolStatus),
+        (status = 400, description = "Order does not list every provider exactly once, or unknown network"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Unknown provider")
+    ),
+    security(
+        ("jwt" = [])
+    ),
+    tag = "Providers"
+)]
+async fn reorder_providers(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<NetworkQuery>,
+    Json(request): Json<ReorderRequest>,
+) -> Result<Json<PoolStatus>, AppError> {
+    let network = state.networks.get(query.network.as_deref())?;
+    network.registry.reorder(&request.order)?;
+    persist_providers(network);
+    Ok(Json(network.registry.pool_status()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/providers/{name}/trip",
+    params(
+        ("name" = String, Path, description = "Name of the provider to take out of rotation"),
+        NetworkQuery
+    ),
+    responses(
+        (status = 200, description = "Circuit breaker opened", body = PoolStatus),
+        (status = 400, description = "Unknown network"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Unknown provider")
+    ),
+    security(
+        ("jwt" = [])
+    ),
+    tag = "Providers"
+)]
+async fn trip_provider(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Query(query): Query<NetworkQuery>,
+) -> Result<Json<PoolStatus>, AppError> {
+    let network = state.networks.get(query.network.as_deref())?;
+    network.registry.trip(&name)?;
+    Ok(Json(network.registry.pool_status()))
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        analyze,
+        compare_handler,
+        purge_contract_cache,
+        cache_stats,
+        clear_cache,
+        delete_cache_entry,
+        list_networks,
+        list_providers,
+        add_provider,
+        remove_provider,
+        reorder_providers,
+        trip_provider,
+        auth::challenge_handler,
+        auth::verify_handler,
+        auth::refresh_handler,
+        auth::logout_handler,
+        auth::rotate_key_handler,
+        auth::stellar_toml_handler,
+        api_keys::create_api_key_handler,
+        api_keys::list_api_keys_handler,
+        api_keys::revoke_api_key_handler,
+        usage_handler,
+        list_history,
+        get_history_entry,
+        delete_history_entry,
+        clear_history,
+        share_history_entry,
+        unshare_history_entry,
+        get_report
+    ),
+    components(schemas(
+        AnalyzeRequest, ResourceReport, HeadroomReport, HeadroomEntry, LimitsSource,
+        CompareRequest, ComparisonReport, MetricDelta, FootprintChange, FootprintAccess,
+        CostTypeDelta, PurgeResponse, ClearResponse, CacheStats,
+        RpcProvider, PoolStatus, ProviderStatus, SelectionStrategy, LatencyStats,
+        BreakerSnapshot, BreakerTransitions, CircuitState, ReorderRequest, NetworkInfo,
+        auth::ChallengeRequest, auth::ChallengeResponse,
+        auth::VerifyRequest, auth::TokenResponse, auth::RefreshRequest,
+        auth::RotateKeyRequest, auth::RotateKeyResponse,
+        Scope, api_keys::CreateApiKeyRequest, api_keys::CreateApiKeyResponse,
+        api_keys::ApiKeyInfo, UsageReport,
+        HistoryEntry, HistorySummary, HistoryPage, RunKind, SharedReport
+    )),
+    tags(
+        (name = "Analysis", description = "Soroban contract resource analysis endpoints"),
+        (name = "Cache", description = "Simulation cache management"),
+        (name = "Providers", description = "Network profiles and their RPC provider pools"),
+        (name = "Auth", description = "SEP-10 wallet authentication"),
+        (name = "History", description = "Each account's past analyses and shared reports")
+    ),
+    info(
+        title = "SoroScope API",
+        version = "0.1.0",
+        description = "API for analyzing Soroban smart contract resource consumption"
+    )
+)]
+struct ApiDoc;
+
+async fn health_check() -> &'static str {
+    "OK"
+}
+
+const SWEEP_USAGE: &str = "Usage: soroscope-core sweep <wasm_path> <function> <u32|vec_u32|bytes> \
+<start..end[:step]> [args...] [--json]  (use `$n` for the swept argument)";
+
+const CACHE_USAGE: &str =
+    "Usage: soroscope-core cache <stats|show <key>|delete <key>|purge <contract_id>|clear>";
+
+fn cache_settings(config: &AppConfig) -> CacheSettings {
+    CacheSettings {
+        capacity: config.cache_capacity,
+        ttl: std::time::Duration::from_secs(config.cache_ttl_secs),
+    }
+}
+
+/// `cache` subcommand: inspect or clear the configured persistent cache
+/// (`disk` or `redis`). Stop the server first when using the disk backend,
+/// which holds an exclusive lock on its file.
+async fn run_cache_command(
+    args: &[String],
+    config: &AppConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if config.cache_backend == "memory" {
+        return Err("CACHE_BACKEND is memory; nothing is persisted to inspect".into());
+    }
+    let backend = cache::open_backend(
+        &config.cache_backend,
+        &config.cache_path,
+        &config.redis_url,
+        cache_settings(config),
+    )
+    .await?;
+    let cache = SimulationCache::with_backend(backend);
+
+    match args.first().map(String::as_str) {
+        Some("stats") => println!("{}", serde_json::to_string_pretty(&cache.stats().await)?),
+        Some("show") => {
+            let key = args.get(1).ok_or(CACHE_USAGE)?;
+            match cache.get(key).await {
+                Some(result) => println!("{}", serde_json::to_string_pretty(&result)?),
+                None => return Err(format!("No cache entry for key {}", key).into()),
+            }
+        }
+        Some("delete") => {
+            let key = args.get(1).ok_or(CACHE_USAGE)?;
+            if !cache.remove(key).await {
+                return Err(format!("No cache entry for key {}", key).into());
+            }
+            println!("Removed {}", key);
+        }
+        Some("purge") => {
+            let contract_id = args.get(1).ok_or(CACHE_USAGE)?;
+            let removed = cache.purge_contract(contract_id).await;
+            println!("Removed {} entries for {}", removed, contract_id);
+        }
+        Some("clear") => println!("Removed {} entries", cache.clear().await),
+        _ => return Err(CACHE_USAGE.into()),
+    }
+    Ok(())
+}
+
+const COMPARE_USAGE: &str = "Usage: soroscope-core compare <baseline> <candidate> <function> \
+[args...] [--json|--markdown]  (each side is a .wasm path or a deployed contract id)";
+
+/// `compare` subcommand: simulate the same call against two contracts or WASM
+/// builds and print the per-metric, footprint and cost-type differences.
+async fn run_compare_command(
+    args: &[String],
+    engine: &SimulationEngine,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let is_flag = |a: &String| a == "--json" || a == "--markdown";
+    let positional: Vec<&String> = args.iter().filter(|a| !is_flag(a)).collect();
+    if positional.len() < 3 {
+        return Err(COMPARE_USAGE.into());
+    }
+
+    let load = |side: &str| -> Result<CompareTarget, Box<dyn std::error::Error>> {
+        if stellar_strkey::Contract::from_string(side).is_ok() {
+            Ok(CompareTarget::Contract(side.to_string()))
+        } else {
+            Ok(CompareTarget::Wasm(std::fs::read(side)?))
+        }
+    };
+    let baseline = load(positional[0])?;
+    let candidate = load(positional[1])?;
+    let function = positional[2];
+    let call_args: Vec<String> = positional[3..].iter().map(|a| a.to_string()).collect();
+
+    let a = baseline
+        .simulate(engine, function, call_args.clone())
+        .await?;
+    let b = candidate.simulate(engine, function, call_args).await?;
+    let report = ComparisonReport::new(positional[0], &a, positional[1], &b);
+
+    if args.iter().any(|a| a == "--json") {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if args.iter().any(|a| a == "--markdown") {
+        print!("{}", report.render_markdown());
+    } else {
+        print!("{}", report.render_table());
+    }
+    Ok(())
+}
+
+/// `sweep` subcommand: run a function locally over a range of input sizes and
+/// report the fitted complexity and where network limits are exceeded.
+fn run_sweep_command(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let json = args.iter().any(|a| a == "--json");
+    let positional: Vec<&String> = args.iter().filter(|a| *a != "--json").collect();
+    if positional.len() < 4 {
+        return Err(SWEEP_USAGE.into());
+    }
+
+    let wasm = std::fs::read(positional[0])?;
+    let spec = SweepSpec {
+        function: positional[1].clone(),
+        variable: positional[2].parse::<SweepVariable>()?,
+        range: positional[3].parse()?,
+        args: positional[4..].iter().map(|a| a.to_string()).collect(),
+    };
+
+    let report = sweep::run_sweep(&wasm, &spec, &NetworkLimits::bundled())?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print!("{}", report.render_table());
+    }
+    Ok(())
+}
+
+/// SEP-10 state with the configured signing key, issuing challenges for
+/// every network. Panics if a configured seed can't be loaded, rather than
+/// silently signing with a random key.
+fn build_auth_state(config: &AppConfig, networks: &Networks) -> auth::AuthState {
+    let seed = auth::load_seed(
+        &config.sep10_signing_seed.0,
+        &config.sep10_signing_seed_file,
+    )
+    .unwrap_or_else(|e| panic!("Failed to load SEP-10 signing seed: {e}"));
+    if seed.is_none() {
+        tracing::warn!("No SEP-10 signing seed configured; using a random key for this run");
+    }
+
+    let web_auth_endpoint = if config.web_auth_endpoint.is_empty() {
+        format!("http://localhost:{}/auth", config.server_port)
+    } else {
+        config.web_auth_endpoint.clone()
+    };
+
+    // Verification loads account signers through each network's provider
+    // pool.
+    let mut network_list = networks.iter();
+    let default = network_list
+        .next()
+        .expect("the default network is always present");
+    let mut state =
+        auth::AuthState::new(config.jwt_secret.clone(), seed, default.passphrase.clone())
+            .with_rpc(SorobanRpc::new(Arc::clone(&default.registry)))
+            .with_network_name(&default.name)
+            .with_rotation_overlap(std::time::Duration::from_secs(
+                config.sep10_rotation_overlap_secs,
+            ))
+            .with_web_auth_endpoint(web_auth_endpoint)
+            .with_token_ttls(
+                std::time::Duration::from_secs(config.access_token_ttl_secs.max(1)),
+                std::time::Duration::from_secs(config.refresh_token_ttl_secs.max(1)),
+            );
+    let admins = auth::parse_admins(&config.admin_accounts)
+        .unwrap_or_else(|e| panic!("Invalid ADMIN_ACCOUNTS: {e}"));
+    if admins.is_empty() {
+        tracing::warn!("ADMIN_ACCOUNTS is empty; admin endpoints are unavailable");
+    }
+    state = state.with_admins(admins);
+    for network in network_list {
+        state = state.with_network(
+            &network.name,
+            &network.passphrase,
+            Some(SorobanRpc::new(Arc::clone(&network.registry))),
+        );
+    }
+    if !config.api_keys_file.is_empty() {
+        let store = api_keys::ApiKeyStore::open(PathBuf::from(&config.api_keys_file))
+            .unwrap_or_else(|e| panic!("Failed to load API keys: {e}"));
+        state = state.with_api_keys(store);
+    }
+    // Rotations are only persisted when the seed came from the file.
+    if config.sep10_signing_seed.0.is_empty() && !config.sep10_signing_seed_file.is_empty() {
+        state = state.with_seed_file(PathBuf::from(&config.sep10_signing_seed_file));
+    }
+    state
+}
+
+/// Provider selection and circuit breaker settings shared by every network.
+fn registry_config(config: &AppConfig) -> RegistryConfig {
+    let strategy = config
+        .rpc_selection_strategy
+        .parse::<SelectionStrategy>()
+        .unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "Invalid RPC_SELECTION_STRATEGY, using priority");
+            SelectionStrategy::Priority
+        });
+    tracing::info!(
+        strategy = ?strategy,
+        max_ledger_lag = config.max_ledger_lag,
+        "RPC provider selection"
+    );
+    RegistryConfig {
+        strategy,
+        max_ledger_lag: config.max_ledger_lag,
+        breaker: BreakerConfig {
+            failure_threshold: config.circuit_failure_threshold.max(1),
+            cooldown: std::time::Duration::from_secs(config.circuit_cooldown_secs),
+            max_cooldown: std::time::Duration::from_secs(config.circuit_max_cooldown_secs),
+            half_open_trials: config.circuit_half_open_trials.max(1),
+        },
+    }
+}
+
+/// Provider pool, background health checker and simulation engine for one
+/// network.
+async fn start_network(
+    config: &AppConfig,
+    registry_config: RegistryConfig,
+    name: &str,
+    passphrase: String,
+    providers: Vec<RpcProvider>,
+    providers_file: Option<PathBuf>,
+) -> Network {
+    let provider_names: Vec<&str> = providers.iter().map(|p| p.name.as_str()).collect();
+    tracing::info!(network = %name, providers = ?provider_names, "RPC provider pool");
+    let registry = ProviderRegistry::with_config(providers, registry_config);
+
+    let health_interval = std::time::Duration::from_secs(config.health_check_interval_secs);
+    registry.spawn_health_checker(health_interval);
+    tracing::info!(
+        network = %name,
+        interval_secs = config.health_check_interval_secs,
+        "Background RPC health checker started"
+    );
+
+    // Per-transaction limits for headroom reports: live ConfigSetting values
+    // when reachable, otherwise the bundled snapshot.
+    let network_limits = match NetworkLimits::fetch(&SorobanRpc::new(Arc::clone(&registry))).await {
+        Ok(limits) => {
+            tracing::info!(network = %name, limits = ?limits, "Loaded network limits from ConfigSetting entries");
+            limits
+        }
+        Err(e) => {
+            tracing::warn!(network = %name, error = %e, "Could not fetch network limits, using bundled snapshot");
+            NetworkLimits::bundled()
+        }
+    };
+
+    let mut engine = SimulationEngine::with_registry(Arc::clone(&registry))
+        .with_network_limits(network_limits, config.limit_warning_pct);
+    if config.hedge_delay_ms > 0 {
+        tracing::info!(
+            network = %name,
+            delay_ms = config.hedge_delay_ms,
+            "Hedged RPC requests enabled"
+        );
+        engine = engine.with_hedging(std::time::Duration::from_millis(config.hedge_delay_ms));
+    }
+
+    Network {
+        name: name.to_string(),
+        passphrase,
+        engine,
+        registry,
+        providers_file,
+    }
+}
+
+/// Public and protected routes with the shared layers applied. Protected
+/// routes take a JWT or an API key with the route group's scope; provider,
+/// signing key and cache administration also need the admin role.
+fn build_router(app_state: Arc<AppState>, auth_state: Arc<auth::AuthState>) -> Router {
     let cors = CorsLayer::new().allow_origin(Any);
 
-    let protected = Router::new()
+    // Scope is checked before the rate limit so refused requests aren't
+    // counted.
+    let analysis = Router::new()
         .route("/analyze", post(analyze))
+        .route("/compare", post(compare_handler))
+        .route_layer(middleware::from_fn_with_state(
+            Arc::clone(&app_state.rate_limiter),
+            rate_limit::rate_limit_middleware,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            Scope::Analyze,
+            auth::require_scope,
+        ));
+
+    let history = Router::new()
+        .route("/history", get(list_history).delete(clear_history))
+        .route(
+            "/history/:id",
+            get(get_history_entry).delete(delete_history_entry),
+        )
+        .route(
+            "/history/:id/share",
+            post(share_history_entry).delete(unshare_history_entry),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            Scope::Analyze,
+            auth::require_scope,
+        ));
+
+    let cache_admin = Router::new()
+        .route("/cache/contract/:contract_id", delete(purge_contract_cache))
+        .route("/cache", delete(clear_cache))
+        .route("/cache/:key", delete(delete_cache_entry))
+        .route_layer(middleware::from_fn_with_state(
+            Role::Admin,
+            auth::require_role,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            Scope::Cache,
+            auth::require_scope,
+        ));
+
+    let admin = Router::new()
+        .route("/providers", post(add_provider))
+        .route("/providers/reorder", post(reorder_providers))
+        .route("/providers/:name", delete(remove_provider))
+        .route("/providers/:name/trip", post(trip_provider))
+        .route("/auth/rotate-key", post(auth::rotate_key_handler))
+        .route_layer(middleware::from_fn_with_state(
+            Role::Admin,
+            auth::require_role,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            Scope::Admin,
+            auth::require_scope,
+        ));
+
+    // Any account may manage its own API keys.
+    let key_management = Router::new()
+        .route(
+            "/auth/api-keys",
+            post(api_keys::create_api_key_handler).get(api_keys::list_api_keys_handler),
+        )
+        .route(
+            "/auth/api-keys/:id",
+            delete(api_keys::revoke_api_key_handler),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            Scope::Admin,
+            auth::require_scope,
+        ));
+
+    let protected = Router::new()
+        .route("/auth/logout", post(auth::logout_handler))
+        .route("/me/usage", get(usage_handler))
+        // Sizing a Redis cache scans the whole keyspace.
+        .route("/cache/stats", get(cache_stats))
+        .merge(analysis)
+        .merge(history)
+        .merge(cache_admin)
+        .merge(admin)
+        .merge(key_management)
         .route_layer(middleware::from_fn(auth::auth_middleware));
 
-    let app = Router::new()
+    Router::new()
         .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
         .route(
             "/",
@@ -355,13 +1773,176 @@ async fn main() {
             }),
         )
         .route("/health", get(health_check))
+        .route("/networks", get(list_networks))
+        .route("/providers", get(list_providers))
+        .route("/reports/:id", get(get_report))
         .route("/auth/challenge", post(auth::challenge_handler))
         .route("/auth/verify", post(auth::verify_handler))
+        .route("/auth/refresh", post(auth::refresh_handler))
+        .route("/.well-known/stellar.toml", get(auth::stellar_toml_handler))
         .merge(protected)
         .layer(Extension(auth_state))
         .layer(cors)
         .layer(TraceLayer::new_for_http())
-        .with_state(app_state); // ← thread AppState through all handlers
+        .with_state(app_state) // ← thread AppState through all handlers
+}
+
+#[tokio::main]
+async fn main() {
+    if env::var("RUST_LOG").is_err() {
+        env::set_var("RUST_LOG", "info");
+    }
+
+    tracing_subscriber::registry()
+        .with(EnvFilter::from_default_env())
+        .with(tracing_subscriber::fmt::layer())
+        .init();
+
+    tracing::info!("SoroScope Starting...");
+
+    let config = load_config().expect("Failed to load configuration");
+    tracing::info!("SoroScope initialized with config: {:?}", config);
+    tracing::info!(
+        backend = %config.cache_backend,
+        capacity = config.cache_capacity,
+        ttl_secs = config.cache_ttl_secs,
+        "Cache config"
+    );
+
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() > 1 && args[1] == "benchmark" {
+        tracing::info!("Starting SoroScope Benchmark...");
+
+        let possible_paths = vec![
+            "target/wasm32-unknown-unknown/release/soroban_token_contract.wasm",
+            "../target/wasm32-unknown-unknown/release/soroban_token_contract.wasm",
+        ];
+
+        let mut wasm_path = None;
+        for p in possible_paths {
+            let path = PathBuf::from(p);
+            if path.exists() {
+                wasm_path = Some(path);
+                break;
+            }
+        }
+
+        if let Some(path) = wasm_path {
+            if let Err(e) = benchmarks::run_token_benchmark(path) {
+                tracing::error!("Benchmark failed: {}", e);
+            }
+        } else {
+            tracing::error!(
+                "Could not find soroban_token_contract.wasm. Build the contract first."
+            );
+        }
+
+        return;
+    }
+
+    if args.len() > 1 && args[1] == "sweep" {
+        tracing::info!("Starting SoroScope input-size sweep...");
+        if let Err(e) = run_sweep_command(&args[2..]) {
+            tracing::error!("Sweep failed: {}", e);
+        }
+        return;
+    }
+
+    if args.len() > 1 && args[1] == "cache" {
+        if let Err(e) = run_cache_command(&args[2..], &config).await {
+            tracing::error!("Cache command failed: {}", e);
+        }
+        return;
+    }
+
+    if args.len() > 1 && args[1] == "compare" {
+        tracing::info!("Starting SoroScope comparison...");
+        let engine = SimulationEngine::new(config.soroban_rpc_url.clone())
+            .with_network_limits(NetworkLimits::bundled(), config.limit_warning_pct);
+        if let Err(e) = run_compare_command(&args[2..], &engine).await {
+            tracing::error!("Compare failed: {}", e);
+        }
+        return;
+    }
+
+    tracing::info!("Starting SoroScope API Server...");
+
+    // ── Networks and their RPC provider pools ──────────────────────────
+    let registry_config = registry_config(&config);
+    let mut networks = Networks::new(
+        start_network(
+            &config,
+            registry_config,
+            &config.default_network,
+            config.network_passphrase.clone(),
+            build_providers(&config),
+            providers_file(&config),
+        )
+        .await,
+    );
+    let profiles = networks::parse_profiles(&config.networks, &config.default_network)
+        .unwrap_or_else(|e| panic!("Invalid NETWORKS: {e}"));
+    for profile in profiles {
+        let file = network_providers_file(&config, &profile.name);
+        let providers = match file.as_deref().and_then(load_saved_providers) {
+            Some(providers) => providers,
+            None => profile.providers().expect("checked by parse_profiles"),
+        };
+        let passphrase = profile.passphrase().expect("checked by parse_profiles");
+        let network = start_network(
+            &config,
+            registry_config,
+            &profile.name,
+            passphrase,
+            providers,
+            file,
+        )
+        .await;
+        networks = networks.with(network);
+    }
+
+    let auth_state = Arc::new(build_auth_state(&config, &networks));
+    tracing::info!(
+        "SEP-10 server account: {}",
+        auth_state.server_stellar_address()
+    );
+
+    // Simulation cache: a disk backend reloads still-fresh results on boot.
+    let cache_settings = cache_settings(&config);
+    let cache_backend = match cache::open_backend(
+        &config.cache_backend,
+        &config.cache_path,
+        &config.redis_url,
+        cache_settings,
+    )
+    .await
+    {
+        Ok(backend) => backend,
+        Err(e) => {
+            tracing::warn!(error = %e, "Could not open cache backend, using in-memory cache");
+            Box::new(MemoryCache::new(cache_settings))
+        }
+    };
+    let cache = SimulationCache::with_backend(cache_backend);
+    tracing::info!(
+        entries = cache.entry_count().await,
+        "Simulation cache ready"
+    );
+
+    let app_state = Arc::new(AppState {
+        networks,
+        cache,
+        inflight: SingleFlight::new(),
+        verified: verified_keys(),
+        rate_limiter: Arc::new(RateLimiter::new(RateLimits {
+            per_minute: config.rate_limit_per_minute,
+            daily_quota: config.daily_simulation_quota,
+        })),
+        history: open_history(&config),
+    });
+
+    let app = build_router(app_state, auth_state);
 
     let bind_addr = format!("0.0.0.0:{}", config.server_port);
     let listener = tokio::net::TcpListener::bind(&bind_addr)
@@ -381,3 +1962,744 @@ async fn main() {
         .await
         .expect("Server failed to start");
 }
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_rpc::{MockReply, MockRpc, MOCK_LEDGER};
+
+    const PASSPHRASE: &str = "Test SDF Network ; September 2015";
+    const ACCOUNT: &str = "GAAZI4TCR3TY5OJHCTJC2A4QSY6CJWJH5IAJTGKIN2ER7LBNVKOCCWN7";
+    const ADMIN: &str = "GBRPYHIL2CI3FNQ4BXLFMNDLFJUNPU2HY3ZMFSHONUCEOASW7QC7OX2H";
+
+    fn network(name: &str, passphrase: &str, providers: Vec<RpcProvider>) -> Network {
+        let registry = ProviderRegistry::new(providers);
+        Network {
+            name: name.to_string(),
+            passphrase: passphrase.to_string(),
+            engine: SimulationEngine::with_registry(Arc::clone(&registry)),
+            registry,
+            providers_file: None,
+        }
+    }
+
+    /// API server backed by `providers`; stops when dropped.
+    struct TestServer {
+        url: String,
+        token: String,
+        auth: Arc<auth::AuthState>,
+        state: Arc<AppState>,
+        client: reqwest::Client,
+        server: tokio::task::JoinHandle<()>,
+    }
+
+    impl Drop for TestServer {
+        fn drop(&mut self) {
+            self.server.abort();
+        }
+    }
+
+    impl TestServer {
+        async fn start(providers: Vec<RpcProvider>) -> Self {
+            Self::start_with_limits(providers, RateLimits::default()).await
+        }
+
+        async fn start_with_limits(providers: Vec<RpcProvider>, limits: RateLimits) -> Self {
+            let networks = Networks::new(network("testnet", PASSPHRASE, providers));
+            Self::start_with_networks(networks, limits).await
+        }
+
+        async fn start_with_networks(networks: Networks, limits: RateLimits) -> Self {
+            let mut auth_state =
+                auth::AuthState::new("test-secret".to_string(), None, PASSPHRASE.to_string())
+                    .with_network_name("testnet")
+                    .with_admins([ADMIN.to_string()]);
+            for network in networks.iter().skip(1) {
+                auth_state = auth_state.with_network(&network.name, &network.passphrase, None);
+            }
+            let auth_state = Arc::new(auth_state);
+            let token = auth::start_session(&auth_state, ACCOUNT)
+                .await
+                .unwrap()
+                .token;
+            let app_state = Arc::new(AppState {
+                networks,
+                cache: SimulationCache::new(),
+                inflight: SingleFlight::new(),
+                verified: verified_keys(),
+                rate_limiter: Arc::new(RateLimiter::new(limits)),
+                history: HistoryStore::in_memory().unwrap(),
+            });
+            let app = build_router(Arc::clone(&app_state), Arc::clone(&auth_state));
+
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let server = tokio::spawn(async move {
+                axum::serve(listener, app).await.unwrap();
+            });
+            Self {
+                url,
+                token,
+                auth: auth_state,
+                state: app_state,
+                client: reqwest::Client::new(),
+                server,
+            }
+        }
+
+        async fn analyze(&self, token: Option<&str>) -> (StatusCode, HeaderMap, serde_json::Value) {
+            let body = serde_json::json!({
+                "contract_id": "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC",
+                "function_name": "hello",
+                "args": []
+            });
+            self.post("/analyze", token, body).await
+        }
+
+        async fn post(
+            &self,
+            path: &str,
+            token: Option<&str>,
+            body: serde_json::Value,
+        ) -> (StatusCode, HeaderMap, serde_json::Value) {
+            let mut request = self.client.post(self.url(path)).json(&body);
+            if let Some(token) = token {
+                request = request.bearer_auth(token);
+            }
+            Self::send(request).await
+        }
+
+        fn url(&self, path: &str) -> String {
+            format!("{}{path}", self.url)
+        }
+
+        async fn send(
+            request: reqwest::RequestBuilder,
+        ) -> (StatusCode, HeaderMap, serde_json::Value) {
+            let response = request.send().await.unwrap();
+            let status = StatusCode::from_u16(response.status().as_u16()).unwrap();
+            let headers = response.headers().clone();
+            let json = response.json().await.unwrap_or(serde_json::Value::Null);
+            (status, headers, json)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_analyze_requires_token() {
+        let rpc = MockRpc::start().await;
+        let server = TestServer::start(vec![RpcProvider::new("mock", rpc.url())]).await;
+
+        let (status, _, _) = server.analyze(None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _, _) = server.analyze(Some("not-a-jwt")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(rpc.calls("simulateTransaction"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_analyze_reports_and_caches_simulation() {
+        let rpc = MockRpc::start().await;
+        rpc.on(
+            "simulateTransaction",
+            MockReply::simulation(MOCK_LEDGER, 12_345, 678),
+        );
+        let server = TestServer::start(vec![RpcProvider::new("mock", rpc.url())]).await;
+
+        let (status, headers, report) = server.analyze(Some(&server.token)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers["x-soroscope-cache"], "MISS");
+        assert_eq!(report["cpu_instructions"], 12_345);
+        assert_eq!(report["ram_bytes"], 678);
+        assert_eq!(report["provider"], "mock");
+        assert_eq!(report["latest_ledger"], MOCK_LEDGER);
+
+        let (status, headers, report) = server.analyze(Some(&server.token)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers["x-soroscope-cache"], "HIT");
+        assert_eq!(report["cpu_instructions"], 12_345);
+        assert_eq!(rpc.calls("simulateTransaction"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cache_stats_requires_token() {
+        let rpc = MockRpc::start().await;
+        let server = TestServer::start(vec![RpcProvider::new("mock", rpc.url())]).await;
+        server.analyze(Some(&server.token)).await;
+
+        let (status, _, _) = TestServer::send(server.client.get(server.url("/cache/stats"))).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, _, stats) = TestServer::send(
+            server
+                .client
+                .get(server.url("/cache/stats"))
+                .bearer_auth(&server.token),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(stats["entry_count"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_analyze_rechecks_hits_at_most_once_per_interval() {
+        use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+        use soroban_sdk::xdr::{LedgerKey, LedgerKeyContractCode, Limits, WriteXdr};
+        let key = LedgerKey::ContractCode(LedgerKeyContractCode {
+            hash: soroban_sdk::xdr::Hash([8; 32]),
+        });
+        let encoded_key = BASE64.encode(key.to_xdr(Limits::none()).unwrap());
+        let rpc = MockRpc::start().await;
+        rpc.on(
+            "simulateTransaction",
+            MockReply::simulation_reading(MOCK_LEDGER, 12_345, 678, &[key]),
+        );
+        rpc.on(
+            "getLedgerEntries",
+            MockReply::result(serde_json::json!({
+                "entries": [{
+                    "key": encoded_key,
+                    "xdr": "",
+                    "lastModifiedLedgerSeq": MOCK_LEDGER - 1
+                }],
+                "latestLedger": MOCK_LEDGER
+            })),
+        );
+        let server = TestServer::start(vec![RpcProvider::new("mock", rpc.url())]).await;
+
+        let (_, headers, _) = server.analyze(Some(&server.token)).await;
+        assert_eq!(headers["x-soroscope-cache"], "MISS");
+        // Just simulated, so the hit is served without a recheck.
+        let (_, headers, _) = server.analyze(Some(&server.token)).await;
+        assert_eq!(headers["x-soroscope-cache"], "HIT");
+        assert_eq!(rpc.calls("getLedgerEntries"), 0);
+
+        server.state.verified.invalidate_all();
+        let (_, headers, _) = server.analyze(Some(&server.token)).await;
+        assert_eq!(headers["x-soroscope-cache"], "HIT");
+        let (_, headers, _) = server.analyze(Some(&server.token)).await;
+        assert_eq!(headers["x-soroscope-cache"], "HIT");
+        assert_eq!(rpc.calls("getLedgerEntries"), 1);
+
+        // A failed recheck does not vouch for the hit.
+        server.state.verified.invalidate_all();
+        rpc.enqueue("getLedgerEntries", MockReply::status(500));
+        let (status, headers, _) = server.analyze(Some(&server.token)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers["x-soroscope-cache"], "STALE");
+        assert_eq!(rpc.calls("simulateTransaction"), 2);
+    }
+
+    #[tokio::test]
+    async fn test_analyze_rejects_invalid_contract_id() {
+        let rpc = MockRpc::start().await;
+        let server = TestServer::start(vec![RpcProvider::new("mock", rpc.url())]).await;
+
+        let body = serde_json::json!({
+            "contract_id": "C*",
+            "function_name": "hello",
+            "args": []
+        });
+        let (status, _, _) = server.post("/analyze", Some(&server.token), body).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(rpc.calls("simulateTransaction"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_analyze_fails_over_to_backup_provider() {
+        let primary = MockRpc::start().await;
+        primary.on("simulateTransaction", MockReply::status(503));
+        let backup = MockRpc::start().await;
+        let server = TestServer::start(vec![
+            RpcProvider::new("primary", primary.url()),
+            RpcProvider::new("backup", backup.url()),
+        ])
+        .await;
+
+        let (status, _, report) = server.analyze(Some(&server.token)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(report["provider"], "backup");
+        assert_eq!(primary.calls("simulateTransaction"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_analyze_surfaces_node_error() {
+        let rpc = MockRpc::start().await;
+        rpc.on(
+            "simulateTransaction",
+            MockReply::error(-32602, "contract not found"),
+        );
+        let server = TestServer::start(vec![RpcProvider::new("mock", rpc.url())]).await;
+
+        let (status, _, _) = server.analyze(Some(&server.token)).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+
+        // Failures are not cached.
+        server.analyze(Some(&server.token)).await;
+        assert_eq!(rpc.calls("simulateTransaction"), 2);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_and_logout() {
+        let rpc = MockRpc::start().await;
+        let server = TestServer::start(vec![RpcProvider::new("mock", rpc.url())]).await;
+
+        let first = auth::start_session(&server.auth, ACCOUNT).await.unwrap();
+        let (status, _, tokens) = server
+            .post(
+                "/auth/refresh",
+                None,
+                serde_json::json!({ "refresh_token": first.refresh_token }),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        let access = tokens["token"].as_str().unwrap().to_string();
+        assert_eq!(server.analyze(Some(&access)).await.0, StatusCode::OK);
+
+        let (status, _, _) = server
+            .post("/auth/logout", Some(&access), serde_json::json!({}))
+            .await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        // Both the access token and the refresh token stop working.
+        assert_eq!(
+            server.analyze(Some(&access)).await.0,
+            StatusCode::UNAUTHORIZED
+        );
+        let (status, _, _) = server
+            .post(
+                "/auth/refresh",
+                None,
+                serde_json::json!({ "refresh_token": tokens["refresh_token"] }),
+            )
+            .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_api_key_authenticates_within_its_scopes() {
+        let rpc = MockRpc::start().await;
+        let server = TestServer::start(vec![RpcProvider::new("mock", rpc.url())]).await;
+
+        let (status, _, created) = server
+            .post(
+                "/auth/api-keys",
+                Some(&server.token),
+                serde_json::json!({ "name": "ci", "scopes": ["analyze"] }),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let key = created["key"].as_str().unwrap().to_string();
+        let id = created["id"].as_str().unwrap().to_string();
+
+        let analyze = |key: &str| {
+            server
+                .client
+                .post(server.url("/analyze"))
+                .header("X-API-Key", key)
+                .json(&serde_json::json!({
+                    "contract_id": "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC",
+                    "function_name": "hello",
+                    "args": []
+                }))
+        };
+        assert_eq!(TestServer::send(analyze(&key)).await.0, StatusCode::OK);
+        assert_eq!(
+            TestServer::send(analyze("ssk_0000_bogus")).await.0,
+            StatusCode::UNAUTHORIZED
+        );
+
+        // Outside its scopes.
+        let clear = server
+            .client
+            .delete(server.url("/cache"))
+            .header("X-API-Key", &key);
+        let (status, _, body) = TestServer::send(clear).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert!(body["message"].as_str().unwrap().contains("'cache'"));
+        let list = server
+            .client
+            .get(server.url("/auth/api-keys"))
+            .header("X-API-Key", &key);
+        assert_eq!(TestServer::send(list).await.0, StatusCode::FORBIDDEN);
+
+        // Listing never returns the key itself.
+        let list = server
+            .client
+            .get(server.url("/auth/api-keys"))
+            .bearer_auth(&server.token);
+        let (status, _, keys) = TestServer::send(list).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(keys[0]["id"], id.as_str());
+        assert!(keys[0].get("key").is_none());
+
+        let revoke = server
+            .client
+            .delete(server.url(&format!("/auth/api-keys/{id}")))
+            .bearer_auth(&server.token);
+        assert_eq!(TestServer::send(revoke).await.0, StatusCode::NO_CONTENT);
+        assert_eq!(
+            TestServer::send(analyze(&key)).await.0,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[tokio::test]
+    async fn test_api_key_cannot_grant_scopes_it_lacks() {
+        let rpc = MockRpc::start().await;
+        let server = TestServer::start(vec![RpcProvider::new("mock", rpc.url())]).await;
+        let admin = server
+            .auth
+            .api_keys()
+            .create(ACCOUNT, "admin", vec![Scope::Admin])
+            .unwrap();
+
+        let create = |scopes: serde_json::Value| {
+            server
+                .client
+                .post(server.url("/auth/api-keys"))
+                .header("X-API-Key", &admin.key)
+                .json(&serde_json::json!({ "name": "child", "scopes": scopes }))
+        };
+        let (status, _, _) = TestServer::send(create(serde_json::json!(["cache"]))).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _, _) = TestServer::send(create(serde_json::json!(["admin"]))).await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        // API keys have no session to log out of.
+        let logout = server
+            .client
+            .post(server.url("/auth/logout"))
+            .header("X-API-Key", &admin.key);
+        assert_eq!(TestServer::send(logout).await.0, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_returns_429_with_retry_after() {
+        let rpc = MockRpc::start().await;
+        let server = TestServer::start_with_limits(
+            vec![RpcProvider::new("mock", rpc.url())],
+            RateLimits {
+                per_minute: 2,
+                daily_quota: 0,
+            },
+        )
+        .await;
+
+        assert_eq!(server.analyze(Some(&server.token)).await.0, StatusCode::OK);
+        assert_eq!(server.analyze(Some(&server.token)).await.0, StatusCode::OK);
+        let (status, headers, body) = server.analyze(Some(&server.token)).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(body["error"], "TOO_MANY_REQUESTS");
+        let retry_after: u64 = headers["retry-after"].to_str().unwrap().parse().unwrap();
+        assert!((1..=30).contains(&retry_after), "{retry_after}");
+
+        // An API key of the same account draws from the same bucket.
+        let key = server
+            .auth
+            .api_keys()
+            .create(ACCOUNT, "ci", vec![Scope::Analyze])
+            .unwrap();
+        let request = server
+            .client
+            .post(server.url("/analyze"))
+            .header("X-API-Key", &key.key)
+            .json(&serde_json::json!({
+                "contract_id": "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC",
+                "function_name": "hello",
+                "args": []
+            }));
+        assert_eq!(
+            TestServer::send(request).await.0,
+            StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+
+    #[tokio::test]
+    async fn test_usage_reports_daily_quota() {
+        let rpc = MockRpc::start().await;
+        let server = TestServer::start_with_limits(
+            vec![RpcProvider::new("mock", rpc.url())],
+            RateLimits {
+                per_minute: 0,
+                daily_quota: 1,
+            },
+        )
+        .await;
+
+        assert_eq!(server.analyze(Some(&server.token)).await.0, StatusCode::OK);
+        let (status, headers, body) = server.analyze(Some(&server.token)).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert!(body["message"].as_str().unwrap().contains("Daily quota"));
+        assert!(headers.contains_key("retry-after"));
+
+        let usage = server
+            .client
+            .get(server.url("/me/usage"))
+            .bearer_auth(&server.token);
+        let (status, _, usage) = TestServer::send(usage).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(usage["caller"], ACCOUNT);
+        assert_eq!(usage["used_today"], 1);
+        assert_eq!(usage["remaining_today"], 0);
+        assert!(usage["per_minute_limit"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_admin_endpoints_require_admin_role() {
+        let rpc = MockRpc::start().await;
+        let server = TestServer::start(vec![RpcProvider::new("mock", rpc.url())]).await;
+        let admin = auth::start_session(&server.auth, ADMIN)
+            .await
+            .unwrap()
+            .token;
+
+        let requests = |token: &str| {
+            [
+                server.client.delete(server.url("/cache")),
+                server.client.post(server.url("/providers/mock/trip")),
+                server
+                    .client
+                    .post(server.url("/auth/rotate-key"))
+                    .json(&serde_json::json!({})),
+            ]
+            .map(|request| request.bearer_auth(token))
+        };
+        for request in requests(&server.token) {
+            let (status, _, body) = TestServer::send(request).await;
+            assert_eq!(status, StatusCode::FORBIDDEN);
+            assert!(body["message"].as_str().unwrap().contains("'admin' role"));
+        }
+        for request in requests(&admin) {
+            assert!(TestServer::send(request).await.0.is_success());
+        }
+
+        // An admin's API key acts as admin, within its scopes.
+        let key = server
+            .auth
+            .api_keys()
+            .create(ADMIN, "ops", vec![Scope::Cache])
+            .unwrap();
+        let clear = server
+            .client
+            .delete(server.url("/cache"))
+            .header("X-API-Key", &key.key);
+        assert_eq!(TestServer::send(clear).await.0, StatusCode::OK);
+        let trip = server
+            .client
+            .post(server.url("/providers/mock/trip"))
+            .header("X-API-Key", &key.key);
+        assert_eq!(TestServer::send(trip).await.0, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_history_records_runs_per_account() {
+        let rpc = MockRpc::start().await;
+        let server = TestServer::start(vec![RpcProvider::new("mock", rpc.url())]).await;
+        assert_eq!(server.analyze(Some(&server.token)).await.0, StatusCode::OK);
+
+        let get = |path: &str, token: &str| server.client.get(server.url(path)).bearer_auth(token);
+        let (status, _, page) = TestServer::send(get(
+            "/history?contract_id=CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC",
+            &server.token,
+        ))
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let entries = page["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0]["kind"], "analyze");
+        assert_eq!(entries[0]["function_name"], "hello");
+        let id = entries[0]["id"].as_str().unwrap().to_string();
+
+        let (status, _, entry) =
+            TestServer::send(get(&format!("/history/{id}"), &server.token)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(entry["result"]["cpu_instructions"], 1_500);
+
+        // Runs are private to the account that made them.
+        let other = auth::start_session(&server.auth, ADMIN)
+            .await
+            .unwrap()
+            .token;
+        let (status, _, _) = TestServer::send(get(&format!("/history/{id}"), &other)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (_, _, page) = TestServer::send(get("/history", &other)).await;
+        assert!(page["entries"].as_array().unwrap().is_empty());
+
+        let delete = server
+            .client
+            .delete(server.url(&format!("/history/{id}")))
+            .bearer_auth(&server.token);
+        assert_eq!(TestServer::send(delete).await.0, StatusCode::NO_CONTENT);
+        let (status, _, _) = TestServer::send(get(&format!("/history/{id}"), &server.token)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_shared_report_is_public_until_unshared() {
+        let rpc = MockRpc::start().await;
+        let server = TestServer::start(vec![RpcProvider::new("mock", rpc.url())]).await;
+        assert_eq!(server.analyze(Some(&server.token)).await.0, StatusCode::OK);
+        let (_, _, page) = TestServer::send(
+            server
+                .client
+                .get(server.url("/history"))
+                .bearer_auth(&server.token),
+        )
+        .await;
+        let id = page["entries"][0]["id"].as_str().unwrap().to_string();
+        let report_id = page["entries"][0]["report_id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let public = || {
+            server
+                .client
+                .get(server.url(&format!("/reports/{report_id}")))
+        };
+
+        // Not shared yet.
+        assert_eq!(TestServer::send(public()).await.0, StatusCode::NOT_FOUND);
+
+        let (status, _, entry) = server
+            .post(
+                &format!("/history/{id}/share"),
+                Some(&server.token),
+                serde_json::json!({}),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(entry["shared"], true);
+
+        let (status, _, report) = TestServer::send(public()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(report["report_id"], report_id.as_str());
+        assert_eq!(report["result"]["cpu_instructions"], 1_500);
+        assert!(report.get("id").is_none());
+
+        let unshare = server
+            .client
+            .delete(server.url(&format!("/history/{id}/share")))
+            .bearer_auth(&server.token);
+        assert_eq!(TestServer::send(unshare).await.0, StatusCode::NO_CONTENT);
+        assert_eq!(TestServer::send(public()).await.0, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_analyze_runs_on_the_requested_network() {
+        let testnet = MockRpc::start().await;
+        testnet.on(
+            "simulateTransaction",
+            MockReply::simulation(MOCK_LEDGER, 12_345, 678),
+        );
+        let futurenet = MockRpc::start().await;
+        futurenet.on(
+            "simulateTransaction",
+            MockReply::simulation(MOCK_LEDGER, 999, 111),
+        );
+        let networks = Networks::new(network(
+            "testnet",
+            PASSPHRASE,
+            vec![RpcProvider::new("testnet-rpc", testnet.url())],
+        ))
+        .with(network(
+            "futurenet",
+            networks::FUTURENET_PASSPHRASE,
+            vec![RpcProvider::new("futurenet-rpc", futurenet.url())],
+        ));
+        let server = TestServer::start_with_networks(networks, RateLimits::default()).await;
+        let analyze = |network: &str| {
+            server.post(
+                "/analyze",
+                Some(&server.token),
+                serde_json::json!({
+                    "contract_id": "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC",
+                    "function_name": "hello",
+                    "args": [],
+                    "network": network
+                }),
+            )
+        };
+
+        let (status, _, report) = server.analyze(Some(&server.token)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(report["provider"], "testnet-rpc");
+
+        // The testnet result is not served for futurenet.
+        let (status, headers, report) = analyze("futurenet").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers["x-soroscope-cache"], "MISS");
+        assert_eq!(report["cpu_instructions"], 999);
+        assert_eq!(report["provider"], "futurenet-rpc");
+        let (_, headers, _) = analyze("futurenet").await;
+        assert_eq!(headers["x-soroscope-cache"], "HIT");
+
+        let (status, _, error) = analyze("mainnet").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(error.to_string().contains("testnet, futurenet"), "{error}");
+        assert_eq!(testnet.calls("simulateTransaction"), 1);
+        assert_eq!(futurenet.calls("simulateTransaction"), 1);
+
+        let (_, _, page) = TestServer::send(
+            server
+                .client
+                .get(server.url("/history"))
+                .bearer_auth(&server.token),
+        )
+        .await;
+        assert_eq!(page["entries"][0]["network"], "futurenet");
+        assert_eq!(page["entries"][2]["network"], "testnet");
+    }
+
+    #[tokio::test]
+    async fn test_providers_and_challenges_per_network() {
+        let testnet = MockRpc::start().await;
+        let futurenet = MockRpc::start().await;
+        let networks = Networks::new(network(
+            "testnet",
+            PASSPHRASE,
+            vec![RpcProvider::new("testnet-rpc", testnet.url())],
+        ))
+        .with(network(
+            "futurenet",
+            networks::FUTURENET_PASSPHRASE,
+            vec![RpcProvider::new("futurenet-rpc", futurenet.url())],
+        ));
+        let server = TestServer::start_with_networks(networks, RateLimits::default()).await;
+
+        let (status, _, list) = TestServer::send(server.client.get(server.url("/networks"))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(list[0]["name"], "testnet");
+        assert_eq!(list[0]["default"], true);
+        assert_eq!(list[1]["passphrase"], networks::FUTURENET_PASSPHRASE);
+        assert_eq!(list[1]["providers"][0], "futurenet-rpc");
+
+        let (status, _, pool) = TestServer::send(
+            server
+                .client
+                .get(server.url("/providers?network=futurenet")),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(pool["providers"][0]["name"], "futurenet-rpc");
+        let (status, _, _) =
+            TestServer::send(server.client.get(server.url("/providers?network=nope"))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let challenge = |network: Option<&str>| {
+            server.post(
+                "/auth/challenge",
+                None,
+                serde_json::json!({ "account": ACCOUNT, "network": network }),
+            )
+        };
+        let (status, _, body) = challenge(None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["network_passphrase"], PASSPHRASE);
+        let (status, _, body) = challenge(Some("futurenet")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["network_passphrase"], networks::FUTURENET_PASSPHRASE);
+        assert_eq!(challenge(Some("nope")).await.0, StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,274 @@
+//! In-process Soroban RPC stand-in for tests.
+//!
+//! Each [`MockRpc`] listens on its own local port and answers JSON-RPC calls
+//! from scripted replies, so failover, health checks and handlers can be
+//! exercised without network access.
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde_json::{json, Value};
+use soroban_sdk::xdr::{
+    ExtensionPoint, LedgerFootprint, LedgerKey, Limits, SorobanResources, SorobanTransactionData,
+    WriteXdr,
+};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Ledger sequence the default replies report.
+pub const MOCK_LEDGER: u64 = 1000;
+
+/// A scripted answer to one JSON-RPC call.
+#[derive(Debug, Clone)]
+pub struct MockReply {
+    delay: Duration,
+    status: u16,
+    body: MockBody,
+}
+
+#[derive(Debug, Clone)]
+enum MockBody {
+    Result(Value),
+    Error { code: i64, message: String },
+    Raw(String),
+}
+
+impl MockReply {
+    /// Respond with `{"result": value}`.
+    pub fn result(value: Value) -> Self {
+        Self {
+            delay: Duration::ZERO,
+            status: 200,
+            body: MockBody::Result(value),
+        }
+    }
+
+    /// Respond with a JSON-RPC `error` object.
+    pub fn error(code: i64, message: &str) -> Self {
+        Self {
+            delay: Duration::ZERO,
+            status: 200,
+            body: MockBody::Error {
+                code,
+                message: message.to_string(),
+            },
+        }
+    }
+
+    /// Respond with an HTTP error status and an empty body.
+    pub fn status(status: u16) -> Self {
+        Self {
+            delay: Duration::ZERO,
+            status,
+            body: MockBody::Raw(String::new()),
+        }
+    }
+
+    /// Respond with a body that is not JSON.
+    pub fn garbage() -> Self {
+        Self {
+            delay: Duration::ZERO,
+            status: 200,
+            body: MockBody::Raw("not json".to_string()),
+        }
+    }
+
+    /// Wait `delay` before answering.
+    pub fn delayed(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// A successful `simulateTransaction` result.
+    pub fn simulation(ledger: u64, cpu_insns: u64, mem_bytes: u64) -> Self {
+        Self::result(json!({
+            "latestLedger": ledger,
+            "cost": {
+                "cpuInsns": cpu_insns.to_string(),
+                "memBytes": mem_bytes.to_string()
+            }
+        }))
+    }
+
+    /// A successful `simulateTransaction` result whose footprint reads `keys`.
+    pub fn simulation_reading(
+        ledger: u64,
+        cpu_insns: u64,
+        mem_bytes: u64,
+        keys: &[LedgerKey],
+    ) -> Self {
+        let data = SorobanTransactionData {
+            ext: ExtensionPoint::V0,
+            resources: SorobanResources {
+                footprint: LedgerFootprint {
+                    read_only: keys.to_vec().try_into().expect("footprint too large"),
+                    read_write: Default::default(),
+                },
+                instructions: cpu_insns as u32,
+                read_bytes: 0,
+                write_bytes: 0,
+            },
+            resource_fee: 0,
+        };
+        let data_xdr = data
+            .to_xdr(Limits::none())
+            .expect("encode transaction data");
+        Self::result(json!({
+            "latestLedger": ledger,
+            "transactionData": BASE64.encode(data_xdr),
+            "cost": {
+                "cpuInsns": cpu_insns.to_string(),
+                "memBytes": mem_bytes.to_string()
+            }
+        }))
+    }
+
+    /// A `getLatestLedger` result.
+    pub fn latest_ledger(sequence: u64) -> Self {
+        Self::result(json!({
+            "id": format!("{sequence:064x}"),
+            "protocolVersion": 22,
+            "sequence": sequence
+        }))
+    }
+}
+
+#[derive(Default)]
+struct Script {
+    /// One-shot replies, used before the method's standing reply.
+    queued: HashMap<String, VecDeque<MockReply>>,
+    standing: HashMap<String, MockReply>,
+    /// Params of every call, by method.
+    calls: HashMap<String, Vec<Value>>,
+}
+
+/// A running mock Soroban RPC server. Stops when dropped.
+pub struct MockRpc {
+    url: String,
+    script: Arc<Mutex<Script>>,
+    server: tokio::task::JoinHandle<()>,
+}
+
+impl MockRpc {
+    /// Start a server that answers `simulateTransaction`, `getLatestLedger`,
+    /// `getHealth` and `getNetwork` at ledger [`MOCK_LEDGER`]. Other methods
+    /// return "method not found" until scripted.
+    pub async fn start() -> Self {
+        let script = Arc::new(Mutex::new(Script::default()));
+        let app = Router::new()
+            .route("/", post(handle))
+            .with_state(Arc::clone(&script));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind mock RPC");
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let mock = Self {
+            url,
+            script,
+            server,
+        };
+        mock.on(
+            "simulateTransaction",
+            MockReply::simulation(MOCK_LEDGER, 1_500, 3_000),
+        );
+        mock.on("getLatestLedger", MockReply::latest_ledger(MOCK_LEDGER));
+        mock.on(
+            "getHealth",
+            MockReply::result(json!({ "status": "healthy", "latestLedger": MOCK_LEDGER })),
+        );
+        mock.on(
+            "getNetwork",
+            MockReply::result(json!({
+                "passphrase": "Test SDF Network ; September 2015",
+                "protocolVersion": 22
+            })),
+        );
+        mock
+    }
+
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    /// Answer every call to `method` with `reply` from now on.
+    pub fn on(&self, method: &str, reply: MockReply) {
+        self.lock().standing.insert(method.to_string(), reply);
+    }
+
+    /// Answer the next call to `method` with `reply`, ahead of the standing
+    /// reply. Queued replies are used in order.
+    pub fn enqueue(&self, method: &str, reply: MockReply) {
+        self.lock()
+            .queued
+            .entry(method.to_string())
+            .or_default()
+            .push_back(reply);
+    }
+
+    /// Number of calls received for `method`.
+    pub fn calls(&self, method: &str) -> usize {
+        self.lock().calls.get(method).map_or(0, Vec::len)
+    }
+
+    /// Params of the most recent call to `method`.
+    pub fn last_params(&self, method: &str) -> Option<Value> {
+        self.lock().calls.get(method)?.last().cloned()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Script> {
+        self.script.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for MockRpc {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+async fn handle(State(script): State<Arc<Mutex<Script>>>, Json(request): Json<Value>) -> Response {
+    let method = request["method"].as_str().unwrap_or_default().to_string();
+    let reply = {
+        let mut script = script.lock().unwrap_or_else(|e| e.into_inner());
+        script
+            .calls
+            .entry(method.clone())
+            .or_default()
+            .push(request["params"].clone());
+        script
+            .queued
+            .get_mut(&method)
+            .and_then(VecDeque::pop_front)
+            .or_else(|| script.standing.get(&method).cloned())
+    };
+    let reply = reply.unwrap_or_else(|| MockReply::error(-32601, "method not found"));
+
+    tokio::time::sleep(reply.delay).await;
+    let status = StatusCode::from_u16(reply.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let id = request["id"].clone();
+    match reply.body {
+        MockBody::Result(result) => (
+            status,
+            Json(json!({ "jsonrpc": "2.0", "id": id, "result": result })),
+        )
+            .into_response(),
+        MockBody::Error { code, message } => (
+            status,
+            Json(json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message }
+            })),
+        )
+            .into_response(),
+        MockBody::Raw(body) => (status, body).into_response(),
+    }
+}
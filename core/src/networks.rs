@@ -0,0 +1,258 @@
+//! Named network profiles, each with its own passphrase and provider pool.
+//!
+//! Requests pick a profile by name (`"network": "futurenet"`); requests that
+//! don't name one run against the default profile.
+
+use crate::errors::AppError;
+use crate::rpc_provider::{ProviderRegistry, RpcProvider};
+use crate::simulation::SimulationEngine;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+pub const TESTNET_PASSPHRASE: &str = "Test SDF Network ; September 2015";
+pub const MAINNET_PASSPHRASE: &str = "Public Global Stellar Network ; September 2015";
+pub const FUTURENET_PASSPHRASE: &str = "Test SDF Future Network ; October 2022";
+pub const STANDALONE_PASSPHRASE: &str = "Standalone Network ; February 2017";
+
+/// Passphrase and public RPC endpoint of a well-known network. Mainnet has
+/// no free public endpoint, so its RPC must be configured.
+fn preset(name: &str) -> Option<(&'static str, Option<&'static str>)> {
+    match name {
+        "testnet" => Some((
+            TESTNET_PASSPHRASE,
+            Some("https://soroban-testnet.stellar.org"),
+        )),
+        "mainnet" => Some((MAINNET_PASSPHRASE, None)),
+        "futurenet" => Some((
+            FUTURENET_PASSPHRASE,
+            Some("https://rpc-futurenet.stellar.org"),
+        )),
+        "local" | "standalone" => Some((
+            STANDALONE_PASSPHRASE,
+            Some("http://localhost:8000/soroban/rpc"),
+        )),
+        _ => None,
+    }
+}
+
+/// One entry of the `NETWORKS` setting. Fields left out of a `testnet`,
+/// `mainnet`, `futurenet` or `local` profile are filled in from the
+/// well-known values for that network.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NetworkProfile {
+    pub name: String,
+    #[serde(default)]
+    pub passphrase: Option<String>,
+    /// Single RPC endpoint, used when `providers` is absent.
+    #[serde(default)]
+    pub rpc_url: Option<String>,
+    #[serde(default)]
+    pub providers: Option<Vec<RpcProvider>>,
+}
+
+impl NetworkProfile {
+    pub fn passphrase(&self) -> Result<String, String> {
+        self.passphrase
+            .clone()
+            .or_else(|| preset(&self.name).map(|(passphrase, _)| passphrase.to_string()))
+            .ok_or_else(|| format!("network '{}' needs a passphrase", self.name))
+    }
+
+    pub fn providers(&self) -> Result<Vec<RpcProvider>, String> {
+        if let Some(providers) = self.providers.as_ref().filter(|p| !p.is_empty()) {
+            return Ok(providers.clone());
+        }
+        let url = self
+            .rpc_url
+            .clone()
+            .or_else(|| preset(&self.name).and_then(|(_, url)| url.map(str::to_string)))
+            .ok_or_else(|| format!("network '{}' needs an rpc_url or providers", self.name))?;
+        Ok(vec![RpcProvider::new(self.name.clone(), url)])
+    }
+}
+
+/// Parse the `NETWORKS` setting (a JSON array of profiles), checking that
+/// every profile is complete and no name is used twice, including
+/// `default_name`.
+pub fn parse_profiles(json: &str, default_name: &str) -> Result<Vec<NetworkProfile>, String> {
+    if json.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let profiles: Vec<NetworkProfile> = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let mut names = vec![default_name];
+    for profile in &profiles {
+        if profile.name.is_empty() {
+            return Err("network name must not be empty".to_string());
+        }
+        if names.contains(&profile.name.as_str()) {
+            return Err(format!("network '{}' is defined twice", profile.name));
+        }
+        names.push(&profile.name);
+        profile.passphrase()?;
+        profile.providers()?;
+    }
+    Ok(profiles)
+}
+
+/// A network the server simulates against.
+pub struct Network {
+    pub name: String,
+    /// Part of every cache key, so results never cross networks.
+    pub passphrase: String,
+    pub engine: SimulationEngine,
+    /// RPC provider pool shared with the engine.
+    pub registry: Arc<ProviderRegistry>,
+    /// Where runtime provider changes are saved, if anywhere.
+    pub providers_file: Option<PathBuf>,
+}
+
+/// The configured networks; the first is the default.
+pub struct Networks {
+    networks: Vec<Network>,
+}
+
+impl Networks {
+    pub fn new(default: Network) -> Self {
+        Self {
+            networks: vec![default],
+        }
+    }
+
+    pub fn with(mut self, network: Network) -> Self {
+        self.networks.push(network);
+        self
+    }
+
+    /// The network named `name`, or the default one.
+    pub fn get(&self, name: Option<&str>) -> Result<&Network, AppError> {
+        let Some(name) = name else {
+            return Ok(&self.networks[0]);
+        };
+        self.networks
+            .iter()
+            .find(|network| network.name == name)
+            .ok_or_else(|| {
+                let known: Vec<&str> = self.networks.iter().map(|n| n.name.as_str()).collect();
+                AppError::BadRequest(format!(
+                    "Unknown network '{name}'; expected one of: {}",
+                    known.join(", ")
+                ))
+            })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Network> {
+        self.networks.iter()
+    }
+
+    pub fn info(&self) -> Vec<NetworkInfo> {
+        self.networks
+            .iter()
+            .enumerate()
+            .map(|(index, network)| NetworkInfo {
+                name: network.name.clone(),
+                passphrase: network.passphrase.clone(),
+                default: index == 0,
+                providers: network
+                    .registry
+                    .providers()
+                    .into_iter()
+                    .map(|p| p.name)
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+/// Selects the network a provider endpoint acts on.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct NetworkQuery {
+    /// Network profile name; defaults to the default network
+    pub network: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NetworkInfo {
+    #[schema(example = "testnet")]
+    pub name: String,
+    #[schema(example = "Test SDF Network ; September 2015")]
+    pub passphrase: String,
+    /// Used by requests that don't name a network
+    pub default: bool,
+    /// Names of the network's RPC providers, in priority order
+    pub providers: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network(name: &str) -> Network {
+        let registry = ProviderRegistry::new(vec![RpcProvider::new(
+            format!("{name}-rpc"),
+            "http://127.0.0.1:1",
+        )]);
+        Network {
+            name: name.to_string(),
+            passphrase: format!("{name} passphrase"),
+            engine: SimulationEngine::with_registry(Arc::clone(&registry)),
+            registry,
+            providers_file: None,
+        }
+    }
+
+    #[test]
+    fn test_presets_fill_in_profiles() {
+        let profiles = parse_profiles(
+            r#"[{"name":"futurenet"},{"name":"mainnet","rpc_url":"https://rpc.example"}]"#,
+            "testnet",
+        )
+        .unwrap();
+        assert_eq!(profiles[0].passphrase().unwrap(), FUTURENET_PASSPHRASE);
+        assert_eq!(
+            profiles[0].providers().unwrap()[0].url,
+            "https://rpc-futurenet.stellar.org"
+        );
+        assert_eq!(profiles[1].passphrase().unwrap(), MAINNET_PASSPHRASE);
+        assert_eq!(
+            profiles[1].providers().unwrap()[0].url,
+            "https://rpc.example"
+        );
+    }
+
+    #[test]
+    fn test_custom_profile_needs_passphrase_and_rpc() {
+        let err =
+            parse_profiles(r#"[{"name":"private","rpc_url":"http://x"}]"#, "testnet").unwrap_err();
+        assert!(err.contains("passphrase"), "{err}");
+        // Mainnet has no public endpoint to fall back to.
+        let err = parse_profiles(r#"[{"name":"mainnet"}]"#, "testnet").unwrap_err();
+        assert!(err.contains("rpc_url"), "{err}");
+        assert!(parse_profiles("", "testnet").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rejects_duplicate_names() {
+        let err = parse_profiles(r#"[{"name":"testnet"}]"#, "testnet").unwrap_err();
+        assert!(err.contains("defined twice"), "{err}");
+        let err = parse_profiles(r#"[{"name":"local"},{"name":"local"}]"#, "testnet").unwrap_err();
+        assert!(err.contains("defined twice"), "{err}");
+    }
+
+    #[test]
+    fn test_lookup_by_name() {
+        let networks = Networks::new(network("testnet")).with(network("futurenet"));
+        assert_eq!(networks.get(None).unwrap().name, "testnet");
+        assert_eq!(networks.get(Some("futurenet")).unwrap().name, "futurenet");
+        let err = networks.get(Some("mainnet")).err().unwrap();
+        assert!(
+            matches!(&err, AppError::BadRequest(m) if m.contains("testnet, futurenet")),
+            "{err:?}"
+        );
+
+        let info = networks.info();
+        assert!(info[0].default && !info[1].default);
+        assert_eq!(info[1].providers, ["futurenet-rpc"]);
+    }
+}
@@ -36,6 +36,57 @@ impl ArgParser {
         Self::parse_value(&value, "$")
     }
 
+    /// Parse a single call argument in the shorthand accepted by the API and CLI.
+    ///
+    /// JSON objects/arrays, `true`/`false`, `void`/`()`, addresses, `:symbols`,
+    /// `0x` hex bytes, integers and quoted strings are recognised; anything else
+    /// is treated as a bare `Symbol`.
+    pub fn parse_arg(arg: &str) -> Result<ScVal, ParserError> {
+        let arg = arg.trim();
+
+        // 1. Try parsing as JSON first (for complex types like Maps and Vecs)
+        if arg.starts_with('{') || arg.starts_with('[') {
+            return Self::parse(arg);
+        }
+
+        // 2. Check for Boolean/Void shorthands
+        if arg == "true" {
+            return Ok(ScVal::Bool(true));
+        }
+        if arg == "false" {
+            return Ok(ScVal::Bool(false));
+        }
+        if arg == "void" || arg == "()" {
+            return Ok(ScVal::Void);
+        }
+
+        // 3. Special types (Addresses, Symbols, Hex)
+        // If it starts with G, C, :, or 0x, we try to parse it as a quoted string
+        if arg.starts_with('G')
+            || arg.starts_with('C')
+            || arg.starts_with(':')
+            || arg.starts_with("0x")
+        {
+            if let Ok(val) = Self::parse(&format!("\"{}\"", arg)) {
+                return Ok(val);
+            }
+        }
+
+        // 4. Numbers and explicit quoted strings
+        if arg.starts_with('"') || arg.parse::<i64>().is_ok() || arg.parse::<u64>().is_ok() {
+            if let Ok(val) = Self::parse(arg) {
+                return Ok(val);
+            }
+        }
+
+        // 5. Default fallback: Treat as Symbol (standard Soroban behavior for unquoted strings)
+        let symbol: ScSymbol = arg.try_into().map_err(|_| ParserError::InvalidSymbol {
+            location: "$".to_string(),
+            details: format!("Cannot parse argument: {}", arg),
+        })?;
+        Ok(ScVal::Symbol(symbol))
+    }
+
     /// Parse a serde_json::Value into an ScVal recursively
     pub fn parse_value(value: &Value, path: &str) -> Result<ScVal, ParserError> {
         match value {
@@ -233,6 +284,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_arg_shorthand() {
+        assert!(matches!(
+            ArgParser::parse_arg("true").unwrap(),
+            ScVal::Bool(true)
+        ));
+        assert!(matches!(ArgParser::parse_arg("()").unwrap(), ScVal::Void));
+        assert!(matches!(ArgParser::parse_arg("7").unwrap(), ScVal::I64(7)));
+        assert!(matches!(
+            ArgParser::parse_arg(":key").unwrap(),
+            ScVal::Symbol(_)
+        ));
+        assert!(matches!(
+            ArgParser::parse_arg("bare_symbol").unwrap(),
+            ScVal::Symbol(_)
+        ));
+        assert!(matches!(
+            ArgParser::parse_arg("[1, 2]").unwrap(),
+            ScVal::Vec(Some(_))
+        ));
+        assert!(ArgParser::parse_arg(&"x".repeat(40)).is_err());
+    }
+
     #[test]
     fn test_error_path() {
         let json = r#"{"a": {"b": [1, 1.5]}}"#;
@@ -0,0 +1,312 @@
+//! Per-caller request rate limits and daily simulation quotas.
+//!
+//! Each account has a token bucket refilled at `per_minute` requests per
+//! minute, plus a count of simulations run since UTC midnight. Its JWTs and
+//! all of its API keys share them.
+
+use crate::auth::AuthContext;
+use crate::errors::AppError;
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use moka::future::Cache;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+
+pub const DEFAULT_REQUESTS_PER_MINUTE: u32 = 60;
+pub const DEFAULT_DAILY_SIMULATION_QUOTA: u64 = 1_000;
+const SECS_PER_DAY: u64 = 86_400;
+const MAX_TRACKED_CALLERS: u64 = 100_000;
+
+/// Limits applied to every caller. Zero disables a limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimits {
+    pub per_minute: u32,
+    pub daily_quota: u64,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            per_minute: DEFAULT_REQUESTS_PER_MINUTE,
+            daily_quota: DEFAULT_DAILY_SIMULATION_QUOTA,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UsageReport {
+    /// Account the limits apply to
+    pub caller: String,
+    /// Requests allowed per minute; null when unlimited
+    pub per_minute_limit: Option<u32>,
+    pub remaining_this_minute: Option<u32>,
+    /// Simulations allowed per UTC day; null when unlimited
+    pub daily_quota: Option<u64>,
+    pub used_today: u64,
+    pub remaining_today: Option<u64>,
+    /// Unix time the daily quota resets (next UTC midnight)
+    pub quota_resets_at: u64,
+}
+
+#[derive(Debug)]
+struct Usage {
+    tokens: f64,
+    refilled_at: Instant,
+    /// Days since the unix epoch that `used_today` counts.
+    day: u64,
+    used_today: u64,
+}
+
+impl Usage {
+    fn new(limits: RateLimits, now: Instant, day: u64) -> Self {
+        Self {
+            tokens: f64::from(limits.per_minute),
+            refilled_at: now,
+            day,
+            used_today: 0,
+        }
+    }
+
+    fn catch_up(&mut self, limits: RateLimits, now: Instant, day: u64) {
+        if day != self.day {
+            self.day = day;
+            self.used_today = 0;
+        }
+        let elapsed = now
+            .saturating_duration_since(self.refilled_at)
+            .as_secs_f64();
+        let capacity = f64::from(limits.per_minute);
+        self.tokens = (self.tokens + elapsed * capacity / 60.0).min(capacity);
+        self.refilled_at = now;
+    }
+}
+
+/// Tracks usage per caller. Callers idle for a day are forgotten, by which
+/// time both their bucket and their daily count would have reset anyway.
+pub struct RateLimiter {
+    limits: RateLimits,
+    callers: Cache<String, Arc<Mutex<Usage>>>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: RateLimits) -> Self {
+        Self {
+            limits,
+            callers: Cache::builder()
+                .max_capacity(MAX_TRACKED_CALLERS)
+                .time_to_idle(Duration::from_secs(SECS_PER_DAY))
+                .build(),
+        }
+    }
+
+    /// Count one simulation request for `caller`, or explain when it may
+    /// retry.
+    pub async fn acquire(&self, caller: &str) -> Result<(), AppError> {
+        self.acquire_at(caller, Instant::now(), unix_now()).await
+    }
+
+    async fn acquire_at(&self, caller: &str, now: Instant, unix: u64) -> Result<(), AppError> {
+        let limits = self.limits;
+        let day = unix / SECS_PER_DAY;
+        let usage = self.entry(caller, now, day).await;
+        let mut usage = usage.lock().unwrap_or_else(|e| e.into_inner());
+        usage.catch_up(limits, now, day);
+
+        if limits.daily_quota > 0 && usage.used_today >= limits.daily_quota {
+            return Err(AppError::RateLimited {
+                message: format!("Daily quota of {} simulations used up", limits.daily_quota),
+                retry_after: (day + 1) * SECS_PER_DAY - unix,
+            });
+        }
+        if limits.per_minute > 0 {
+            if usage.tokens < 1.0 {
+                let per_sec = f64::from(limits.per_minute) / 60.0;
+                return Err(AppError::RateLimited {
+                    message: format!("Limit of {} requests per minute reached", limits.per_minute),
+                    retry_after: ((1.0 - usage.tokens) / per_sec).ceil().max(1.0) as u64,
+                });
+            }
+            usage.tokens -= 1.0;
+        }
+        usage.used_today += 1;
+        Ok(())
+    }
+
+    /// Current limits and usage for `caller`, without consuming anything.
+    pub async fn usage(&self, caller: &str) -> UsageReport {
+        self.usage_at(caller, Instant::now(), unix_now()).await
+    }
+
+    async fn usage_at(&self, caller: &str, now: Instant, unix: u64) -> UsageReport {
+        let limits = self.limits;
+        let day = unix / SECS_PER_DAY;
+        let (tokens, used_today) = match self.callers.get(caller).await {
+            Some(usage) => {
+                let mut usage = usage.lock().unwrap_or_else(|e| e.into_inner());
+                usage.catch_up(limits, now, day);
+                (usage.tokens, usage.used_today)
+            }
+            None => (f64::from(limits.per_minute), 0),
+        };
+        UsageReport {
+            caller: caller.to_string(),
+            per_minute_limit: (limits.per_minute > 0).then_some(limits.per_minute),
+            remaining_this_minute: (limits.per_minute > 0).then_some(tokens.floor() as u32),
+            daily_quota: (limits.daily_quota > 0).then_some(limits.daily_quota),
+            used_today,
+            remaining_today: (limits.daily_quota > 0)
+                .then(|| limits.daily_quota.saturating_sub(used_today)),
+            quota_resets_at: (day + 1) * SECS_PER_DAY,
+        }
+    }
+
+    async fn entry(&self, caller: &str, now: Instant, day: u64) -> Arc<Mutex<Usage>> {
+        let limits = self.limits;
+        self.callers
+            .get_with(caller.to_string(), async move {
+                Arc::new(Mutex::new(Usage::new(limits, now, day)))
+            })
+            .await
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Reject the request with `429` once the caller is over a limit. Layered
+/// inside `auth_middleware`, which supplies the caller.
+pub async fn rate_limit_middleware(
+    State(limiter): State<Arc<RateLimiter>>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let caller = req
+        .extensions()
+        .get::<AuthContext>()
+        .map(|caller| caller.subject.clone())
+        .ok_or_else(|| AppError::Unauthorized("Missing credentials".into()))?;
+    limiter.acquire(&caller).await?;
+    Ok(next.run(req).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-01-01T00:00:00Z
+    const MIDNIGHT: u64 = 1_704_067_200;
+
+    fn limiter(per_minute: u32, daily_quota: u64) -> RateLimiter {
+        RateLimiter::new(RateLimits {
+            per_minute,
+            daily_quota,
+        })
+    }
+
+    fn retry_after(result: Result<(), AppError>) -> u64 {
+        match result {
+            Err(AppError::RateLimited { retry_after, .. }) => retry_after,
+            other => panic!("expected RateLimited, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bucket_allows_burst_then_refills() {
+        let limiter = limiter(3, 0);
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire_at("a", start, MIDNIGHT).await.unwrap();
+        }
+        // One token comes back every 20 seconds.
+        assert_eq!(
+            retry_after(limiter.acquire_at("a", start, MIDNIGHT).await),
+            20
+        );
+        let later = start + Duration::from_secs(15);
+        assert_eq!(
+            retry_after(limiter.acquire_at("a", later, MIDNIGHT + 15).await),
+            5
+        );
+        let later = start + Duration::from_secs(20);
+        limiter.acquire_at("a", later, MIDNIGHT + 20).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_callers_are_limited_independently() {
+        let limiter = limiter(1, 0);
+        let now = Instant::now();
+        limiter.acquire_at("a", now, MIDNIGHT).await.unwrap();
+        assert!(limiter.acquire_at("a", now, MIDNIGHT).await.is_err());
+        limiter.acquire_at("b", now, MIDNIGHT).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_daily_quota_resets_at_utc_midnight() {
+        let limiter = limiter(0, 2);
+        let now = Instant::now();
+        let noon = MIDNIGHT + SECS_PER_DAY / 2;
+        limiter.acquire_at("a", now, noon).await.unwrap();
+        limiter.acquire_at("a", now, noon).await.unwrap();
+        assert_eq!(
+            retry_after(limiter.acquire_at("a", now, noon).await),
+            SECS_PER_DAY / 2
+        );
+
+        limiter
+            .acquire_at("a", now, MIDNIGHT + SECS_PER_DAY)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rejected_requests_are_not_counted() {
+        let limiter = limiter(1, 10);
+        let now = Instant::now();
+        limiter.acquire_at("a", now, MIDNIGHT).await.unwrap();
+        for _ in 0..3 {
+            assert!(limiter.acquire_at("a", now, MIDNIGHT).await.is_err());
+        }
+        assert_eq!(limiter.usage_at("a", now, MIDNIGHT).await.used_today, 1);
+    }
+
+    #[tokio::test]
+    async fn test_usage_report() {
+        let limiter = limiter(5, 100);
+        let now = Instant::now();
+        let fresh = limiter.usage_at("a", now, MIDNIGHT + 60).await;
+        assert_eq!(fresh.remaining_this_minute, Some(5));
+        assert_eq!(fresh.used_today, 0);
+        assert_eq!(fresh.quota_resets_at, MIDNIGHT + SECS_PER_DAY);
+
+        limiter.acquire_at("a", now, MIDNIGHT + 60).await.unwrap();
+        limiter.acquire_at("a", now, MIDNIGHT + 60).await.unwrap();
+        let used = limiter.usage_at("a", now, MIDNIGHT + 60).await;
+        assert_eq!(used.per_minute_limit, Some(5));
+        assert_eq!(used.remaining_this_minute, Some(3));
+        assert_eq!(used.daily_quota, Some(100));
+        assert_eq!(used.used_today, 2);
+        assert_eq!(used.remaining_today, Some(98));
+    }
+
+    #[tokio::test]
+    async fn test_zero_disables_limits() {
+        let limiter = limiter(0, 0);
+        let now = Instant::now();
+        for _ in 0..100 {
+            limiter.acquire_at("a", now, MIDNIGHT).await.unwrap();
+        }
+        let usage = limiter.usage_at("a", now, MIDNIGHT).await;
+        assert_eq!(usage.per_minute_limit, None);
+        assert_eq!(usage.remaining_today, None);
+        assert_eq!(usage.used_today, 100);
+    }
+}
@@ -0,0 +1,149 @@
+use crate::limits::{NetworkLimits, BUNDLED_TX_MAX_SIZE_BYTES};
+use crate::simulation::{CostTypeUsage, Footprint, SorobanResources};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use soroban_env_host::storage::{AccessType, Footprint as HostFootprint};
+use soroban_sdk::testutils::budget::ContractCostType;
+use soroban_sdk::xdr::{Limits, ScVal, WriteXdr};
+use soroban_sdk::{Env, Symbol, TryFromVal, Val, Vec as SorobanVec};
+
+/// Largest WASM accepted for local runs. Uploading contract code has to fit
+/// in a single transaction, so nothing larger could be deployed anyway.
+pub const MAX_WASM_SIZE_BYTES: usize = BUNDLED_TX_MAX_SIZE_BYTES as usize;
+
+/// CPU and memory a local invocation may consume before it is aborted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalBudget {
+    pub cpu_instructions: u64,
+    pub mem_bytes: u64,
+}
+
+impl From<&NetworkLimits> for LocalBudget {
+    /// The network's per-transaction limits; a call that exceeds them could
+    /// not run on-chain either.
+    fn from(limits: &NetworkLimits) -> Self {
+        Self {
+            cpu_instructions: limits.tx_max_instructions,
+            mem_bytes: limits.tx_memory_limit,
+        }
+    }
+}
+
+/// Resources, footprint and cost breakdown of a single local invocation.
+#[derive(Debug, Clone)]
+pub struct LocalInvocation {
+    pub resources: SorobanResources,
+    pub footprint: Footprint,
+    pub cost_breakdown: Vec<CostTypeUsage>,
+}
+
+/// Invoke `function` once on a fresh local environment and meter it.
+///
+/// All auths are mocked. With a `budget` the call is aborted once it exceeds
+/// it; without one the budget is unlimited, so the figures reflect what the
+/// call consumes rather than whether it fits. Blocking: call from
+/// `spawn_blocking` inside async contexts.
+pub fn invoke(
+    wasm: &[u8],
+    function: &str,
+    args: &[ScVal],
+    budget: Option<LocalBudget>,
+) -> Result<LocalInvocation, String> {
+    if wasm.len() > MAX_WASM_SIZE_BYTES {
+        return Err(format!(
+            "WASM is {} bytes; the maximum is {MAX_WASM_SIZE_BYTES}",
+            wasm.len()
+        ));
+    }
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(wasm, ());
+
+    let mut call_args: SorobanVec<Val> = SorobanVec::new(&env);
+    for arg in args {
+        let val = Val::try_from_val(&env, arg).map_err(|e| format!("{e:?}"))?;
+        call_args.push_back(val);
+    }
+
+    match budget {
+        Some(limit) => env
+            .cost_estimate()
+            .budget()
+            .reset_limits(limit.cpu_instructions, limit.mem_bytes),
+        None => env.cost_estimate().budget().reset_unlimited(),
+    }
+    // Drop the accesses recorded while deploying so only the call is reported.
+    env.host()
+        .with_mut_storage(|storage| {
+            storage.footprint = HostFootprint::default();
+            Ok(())
+        })
+        .map_err(|e| format!("{e:?}"))?;
+
+    // Only the metered resources matter, not the decoded return value.
+    let _ = env
+        .try_invoke_contract::<Val, soroban_sdk::Error>(
+            &contract_id,
+            &Symbol::new(&env, function),
+            call_args,
+        )
+        .map_err(|e| format!("invocation failed: {e:?}"))?;
+
+    let res = env.cost_estimate().resources();
+    let resources = SorobanResources {
+        cpu_instructions: res.instructions.max(0) as u64,
+        ram_bytes: res.mem_bytes.max(0) as u64,
+        ledger_read_bytes: res.read_bytes as u64,
+        ledger_write_bytes: res.write_bytes as u64,
+        transaction_size_bytes: 0,
+        read_entries: res.read_entries as u64,
+        write_entries: res.write_entries as u64,
+        events_size_bytes: res.contract_events_size_bytes as u64,
+    };
+
+    Ok(LocalInvocation {
+        resources,
+        footprint: recorded_footprint(&env)?,
+        cost_breakdown: cost_breakdown(&env),
+    })
+}
+
+/// Read the footprint the host recorded during the invocation.
+fn recorded_footprint(env: &Env) -> Result<Footprint, String> {
+    let budget = env.host().budget_cloned();
+    env.host()
+        .with_mut_storage(|storage| {
+            let mut footprint = Footprint::default();
+            for (key, access) in storage.footprint.0.iter(&budget)? {
+                let Ok(bytes) = key.to_xdr(Limits::none()) else {
+                    continue;
+                };
+                let encoded = BASE64.encode(bytes);
+                match access {
+                    AccessType::ReadOnly => footprint.read_only.push(encoded),
+                    AccessType::ReadWrite => footprint.read_write.push(encoded),
+                }
+            }
+            Ok(footprint)
+        })
+        .map_err(|e| format!("{e:?}"))
+}
+
+/// Per cost-type CPU and memory, skipping types the call never charged.
+fn cost_breakdown(env: &Env) -> Vec<CostTypeUsage> {
+    let budget = env.cost_estimate().budget();
+    ContractCostType::VARIANTS
+        .iter()
+        .filter_map(|cost_type| {
+            let tracker = budget.tracker(*cost_type);
+            if tracker.cpu == 0 && tracker.mem == 0 {
+                return None;
+            }
+            Some(CostTypeUsage {
+                cost_type: cost_type.name().to_string(),
+                iterations: tracker.iterations,
+                cpu_instructions: tracker.cpu,
+                mem_bytes: tracker.mem,
+            })
+        })
+        .collect()
+}
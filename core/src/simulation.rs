@@ -1,23 +1,25 @@
+use crate::cache::{CacheBackend, CacheSettings, CacheStats, MemoryCache};
+use crate::limits::{HeadroomReport, NetworkLimits, DEFAULT_WARNING_THRESHOLD_PCT};
 use crate::parser::ArgParser;
-use crate::rpc_provider::ProviderRegistry;
+use crate::rpc_provider::{ProviderPermit, ProviderRegistry, RpcProvider};
+use crate::soroban_rpc::SorobanRpc;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use soroban_sdk::xdr::{
-    Hash, HostFunction, InvokeContractArgs, InvokeHostFunctionOp, LedgerEntry, LedgerKey, Limits,
-    Memo, MuxedAccount, Operation, OperationBody, Preconditions, ReadXdr, ScAddress, ScSymbol,
-    ScVal, SequenceNumber, SorobanAuthorizationEntry, SorobanTransactionData, Transaction,
-    TransactionExt, TransactionV1Envelope, Uint256, VecM, WriteXdr,
+    ContractEventType, DiagnosticEvent, Hash, HostFunction, InvokeContractArgs,
+    InvokeHostFunctionOp, LedgerEntry, LedgerKey, Limits, Memo, MuxedAccount, Operation,
+    OperationBody, Preconditions, ReadXdr, ScAddress, ScSymbol, ScVal, SequenceNumber,
+    SorobanAuthorizationEntry, SorobanTransactionData, Transaction, TransactionExt,
+    TransactionV1Envelope, Uint256, VecM, WriteXdr,
 };
 use stellar_strkey::Strkey;
 use thiserror::Error;
 
-use moka::future::Cache;
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
 
 /// Errors that can occur during simulation
 #[derive(Error, Debug)]
@@ -48,6 +50,9 @@ pub enum SimulationError {
 
     #[error("Parse error: {0}")]
     ParseError(#[from] crate::parser::ParserError),
+
+    #[error("Local execution failed: {0}")]
+    LocalExecution(String),
 }
 
 /// Soroban resource consumption data
@@ -58,6 +63,15 @@ pub struct SorobanResources {
     pub ledger_read_bytes: u64,
     pub ledger_write_bytes: u64,
     pub transaction_size_bytes: u64,
+    /// Ledger entries read: the whole footprint, read-only and read-write
+    #[serde(default)]
+    pub read_entries: u64,
+    /// Number of read-write footprint entries
+    #[serde(default)]
+    pub write_entries: u64,
+    /// Total XDR size of the contract events emitted
+    #[serde(default)]
+    pub events_size_bytes: u64,
 }
 
 /// Complete simulation result including resources and metadata
@@ -70,6 +84,37 @@ pub struct SimulationResult {
     pub cost_stroops: u64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub state_dependency: Option<Vec<StateDependency>>,
+    /// Usage relative to the network's per-transaction limits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub headroom: Option<HeadroomReport>,
+    /// Ledger keys read or written by the invocation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub footprint: Option<Footprint>,
+    /// Per cost-type breakdown; only available for local runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_breakdown: Option<Vec<CostTypeUsage>>,
+    /// Name of the RPC provider that served the simulation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    /// Ledgers the serving provider trailed the freshest known provider by
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ledger_lag: Option<u64>,
+}
+
+/// Ledger keys touched by an invocation, as base64 `LedgerKey` XDR
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct Footprint {
+    pub read_only: Vec<String>,
+    pub read_write: Vec<String>,
+}
+
+/// CPU and memory charged to a single host cost type
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CostTypeUsage {
+    pub cost_type: String,
+    pub iterations: u64,
+    pub cpu_instructions: u64,
+    pub mem_bytes: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -133,8 +178,10 @@ struct SimulationRpcResult {
     #[serde(default)]
     cost: Option<ResourceCost>,
     #[serde(default)]
-    #[allow(dead_code)]
     results: Vec<serde_json::Value>,
+    /// Base64 `DiagnosticEvent` XDR emitted during simulation
+    #[serde(default)]
+    events: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -151,30 +198,63 @@ pub struct SimulationEngine {
     request_timeout: std::time::Duration,
     /// When set, the engine will iterate healthy providers and failover automatically.
     registry: Option<Arc<ProviderRegistry>>,
+    /// Per-transaction limits used for the headroom section of each result.
+    limits: NetworkLimits,
+    headroom_warning_pct: f64,
+    /// Delay after which a slow provider is raced against the next one.
+    hedge_delay: Option<std::time::Duration>,
+    /// Typed client for everything other than `simulateTransaction`.
+    rpc: SorobanRpc,
 }
 
 impl SimulationEngine {
     /// Create an engine backed by a single RPC URL (backward-compatible).
     #[allow(dead_code)]
     pub fn new(rpc_url: String) -> Self {
+        let rpc = SorobanRpc::new(ProviderRegistry::new(vec![RpcProvider::new(
+            "default",
+            rpc_url.clone(),
+        )]));
         Self {
+            rpc,
             rpc_url,
             client: Client::new(),
             request_timeout: std::time::Duration::from_secs(30),
             registry: None,
+            limits: NetworkLimits::bundled(),
+            headroom_warning_pct: DEFAULT_WARNING_THRESHOLD_PCT,
+            hedge_delay: None,
         }
     }
 
     /// Create an engine backed by a `ProviderRegistry` for multi-node failover.
     pub fn with_registry(registry: Arc<ProviderRegistry>) -> Self {
         Self {
+            rpc: SorobanRpc::new(Arc::clone(&registry)),
             rpc_url: String::new(),
             client: Client::new(),
             request_timeout: std::time::Duration::from_secs(30),
             registry: Some(registry),
+            limits: NetworkLimits::bundled(),
+            headroom_warning_pct: DEFAULT_WARNING_THRESHOLD_PCT,
+            hedge_delay: None,
         }
     }
 
+    /// Compare results against `limits`, warning above `warning_pct` percent usage.
+    pub fn with_network_limits(mut self, limits: NetworkLimits, warning_pct: f64) -> Self {
+        self.limits = limits;
+        self.headroom_warning_pct = warning_pct;
+        self
+    }
+
+    /// Send a hedged request to the next healthy provider when a provider has
+    /// not answered within `delay`. Only applies to registry-backed engines.
+    pub fn with_hedging(mut self, delay: std::time::Duration) -> Self {
+        self.hedge_delay = Some(delay);
+        self
+    }
+
     /// Simulate transaction from a deployed contract ID
     ///
     /// # Arguments
@@ -190,6 +270,7 @@ impl SimulationEngine {
         function_name: &str,
         args: Vec<String>,
         ledger_overrides: Option<HashMap<String, String>>,
+        source_account: Option<&str>,
     ) -> Result<SimulationResult, SimulationError> {
         if contract_id.is_empty() {
             return Err(SimulationError::NodeError(
@@ -200,12 +281,13 @@ impl SimulationEngine {
         if let Some(overrides) = ledger_overrides {
             if !overrides.is_empty() {
                 return self
-                    .simulate_locally(contract_id, function_name, args, overrides)
+                    .simulate_locally(contract_id, function_name, args, overrides, source_account)
                     .await;
             }
         }
 
-        let transaction_xdr = self.create_invoke_transaction(contract_id, function_name, args)?;
+        let transaction_xdr =
+            self.create_invoke_transaction(contract_id, function_name, args, source_account)?;
         self.simulate_transaction(&transaction_xdr).await
     }
 
@@ -227,8 +309,12 @@ impl SimulationEngine {
         }
     }
 
-    /// Try each healthy provider in priority order until one succeeds or all
-    /// are exhausted.
+    /// Try each healthy provider in the registry's selection order until one
+    /// succeeds or all are exhausted.
+    ///
+    /// With hedging enabled, a provider that has not answered within the
+    /// hedge delay is raced against the next one; the first success wins and
+    /// the other request is cancelled.
     async fn simulate_transaction_with_failover(
         &self,
         registry: &Arc<ProviderRegistry>,
@@ -243,64 +329,42 @@ impl SimulationEngine {
         }
 
         let mut last_error: Option<SimulationError> = None;
+        let mut remaining = providers.iter().peekable();
 
-        for provider in &providers {
-            tracing::debug!(
-                provider = %provider.name,
-                url = %provider.url,
-                "Attempting simulation request"
-            );
-
-            let auth = provider
-                .auth_header
-                .as_deref()
-                .zip(provider.auth_value.as_deref());
-
-            match self
-                .simulate_transaction_single(
-                    &provider.url,
-                    auth.map(|(h, _)| h),
-                    auth.map(|(_, v)| v),
-                    transaction_xdr,
-                )
-                .await
-            {
-                Ok(result) => {
-                    registry.report_success(&provider.url).await;
-                    return Ok(result);
+        while let Some(provider) = remaining.next() {
+            let Some(permit) = registry.try_acquire(&provider.url) else {
+                continue;
+            };
+            let outcome = match self.hedge_delay {
+                Some(delay) if remaining.peek().is_some() => {
+                    self.simulate_hedged(
+                        registry,
+                        (provider, permit),
+                        &mut remaining,
+                        delay,
+                        transaction_xdr,
+                    )
+                    .await
+                }
+                _ => {
+                    self.attempt_provider(registry, provider, permit, transaction_xdr)
+                        .await
                 }
-                Err(e) => {
-                    let should_retry = match &e {
-                        SimulationError::NodeTimeout | SimulationError::NetworkError(_) => true,
-                        SimulationError::RpcRequestFailed(msg)
-                            if msg.starts_with("HTTP error:") =>
-                        {
-                            // Extract status code from "HTTP error: <code>"
-                            msg.split_whitespace()
-                                .last()
-                                .and_then(|s| s.parse::<u16>().ok())
-                                .map(ProviderRegistry::is_retryable_status)
-                                .unwrap_or(false)
-                        }
-                        _ => false,
-                    };
-
-                    registry.report_failure(&provider.url).await;
-
-                    if should_retry {
-                        tracing::warn!(
-                            provider = %provider.name,
-                            error = %e,
-                            "Provider failed with retryable error, trying next"
-                        );
-                        last_error = Some(e);
-                        continue;
-                    }
-
-                    // Non-retryable error (e.g. bad request) — don't bother
-                    // trying other providers; the request itself is bad.
-                    return Err(e);
+            };
+
+            match outcome {
+                Ok(result) => return Ok(result),
+                Err(e) if Self::is_retryable(&e) => {
+                    tracing::warn!(
+                        provider = %provider.name,
+                        error = %e,
+                        "Provider failed with retryable error, trying next"
+                    );
+                    last_error = Some(e);
                 }
+                // Non-retryable error (e.g. bad request) — don't bother
+                // trying other providers; the request itself is bad.
+                Err(e) => return Err(e),
             }
         }
 
@@ -309,6 +373,144 @@ impl SimulationEngine {
         }))
     }
 
+    /// Send the request to `primary`, and to the next provider as well if
+    /// `primary` has not answered within `delay`.
+    ///
+    /// Returns the first success or non-retryable error. If both requests
+    /// fail with retryable errors the last one is returned.
+    async fn simulate_hedged<'a>(
+        &self,
+        registry: &ProviderRegistry,
+        (primary, permit): (&'a RpcProvider, ProviderPermit),
+        remaining: &mut impl Iterator<Item = &'a RpcProvider>,
+        delay: std::time::Duration,
+        transaction_xdr: &str,
+    ) -> Result<SimulationResult, SimulationError> {
+        let primary_request = self.attempt_provider(registry, primary, permit, transaction_xdr);
+        tokio::pin!(primary_request);
+
+        tokio::select! {
+            outcome = &mut primary_request => return outcome,
+            _ = tokio::time::sleep(delay) => {}
+        }
+
+        let Some((backup, permit)) =
+            remaining.find_map(|p| registry.try_acquire(&p.url).map(|permit| (p, permit)))
+        else {
+            return primary_request.await;
+        };
+        tracing::debug!(
+            primary = %primary.name,
+            backup = %backup.name,
+            delay_ms = delay.as_millis() as u64,
+            "Sending hedged simulation request"
+        );
+        let backup_request = self.attempt_provider(registry, backup, permit, transaction_xdr);
+        tokio::pin!(backup_request);
+
+        // Whichever request settles first decides, unless it failed in a way
+        // another provider could fix; then wait for the other one. Returning
+        // drops the other future, which cancels its request and hands back
+        // any trial slot it held.
+        let (first, winner, loser) = tokio::select! {
+            outcome = &mut primary_request => (outcome, primary, backup),
+            outcome = &mut backup_request => (outcome, backup, primary),
+        };
+        let (outcome, winner, loser) = match first {
+            Err(e) if Self::is_retryable(&e) => {
+                tracing::debug!(provider = %winner.name, error = %e, "Hedged request failed");
+                if std::ptr::eq(winner, primary) {
+                    (backup_request.await, backup, primary)
+                } else {
+                    (primary_request.await, primary, backup)
+                }
+            }
+            settled => (settled, winner, loser),
+        };
+
+        if outcome.is_ok() {
+            registry.record_hedge_win(&winner.url);
+            tracing::info!(
+                winner = %winner.name,
+                loser = %loser.name,
+                "Hedged simulation request won"
+            );
+        }
+        outcome
+    }
+
+    /// Simulate against one provider claimed with `permit`, feeding the
+    /// outcome back into the registry's health, latency and ledger tracking.
+    async fn attempt_provider(
+        &self,
+        registry: &ProviderRegistry,
+        provider: &RpcProvider,
+        permit: ProviderPermit,
+        transaction_xdr: &str,
+    ) -> Result<SimulationResult, SimulationError> {
+        tracing::debug!(
+            provider = %provider.name,
+            url = %provider.url,
+            "Attempting simulation request"
+        );
+
+        let auth = provider
+            .auth_header
+            .as_deref()
+            .zip(provider.auth_value.as_deref());
+
+        let started = std::time::Instant::now();
+        match self
+            .simulate_transaction_single(
+                &provider.url,
+                auth.map(|(h, _)| h),
+                auth.map(|(_, v)| v),
+                transaction_xdr,
+            )
+            .await
+        {
+            Ok(mut result) => {
+                registry.record_latency(&provider.url, started.elapsed());
+                registry.record_ledger(&provider.url, result.latest_ledger);
+                permit.report_success();
+
+                let lag = registry.max_ledger().saturating_sub(result.latest_ledger);
+                if lag > registry.max_ledger_lag() {
+                    tracing::warn!(
+                        provider = %provider.name,
+                        ledger = result.latest_ledger,
+                        lag,
+                        "Simulation served by a lagging provider"
+                    );
+                }
+                result.provider = Some(provider.name.clone());
+                result.ledger_lag = Some(lag);
+                Ok(result)
+            }
+            Err(e) => {
+                permit.report_failure();
+                Err(e)
+            }
+        }
+    }
+
+    /// Whether another provider might succeed where this error occurred:
+    /// timeouts, connection errors, HTTP 429 and 5xx.
+    fn is_retryable(error: &SimulationError) -> bool {
+        match error {
+            SimulationError::NodeTimeout | SimulationError::NetworkError(_) => true,
+            SimulationError::RpcRequestFailed(msg) if msg.starts_with("HTTP error:") => {
+                // Extract status code from "HTTP error: <code>"
+                msg.split_whitespace()
+                    .nth(2)
+                    .and_then(|s| s.parse::<u16>().ok())
+                    .map(ProviderRegistry::is_retryable_status)
+                    .unwrap_or(false)
+            }
+            _ => false,
+        }
+    }
+
     /// Send a `simulateTransaction` JSON-RPC call to a single endpoint.
     async fn simulate_transaction_single(
         &self,
@@ -385,7 +587,7 @@ impl SimulationEngine {
             }
             ResponseResult::Success { result } => {
                 tracing::info!("Simulation successful at ledger {}", result.latest_ledger);
-                self.parse_simulation_result(result)
+                self.parse_simulation_result(result, transaction_xdr)
             }
         }
     }
@@ -393,7 +595,9 @@ impl SimulationEngine {
     fn parse_simulation_result(
         &self,
         rpc_result: SimulationRpcResult,
+        transaction_xdr: &str,
     ) -> Result<SimulationResult, SimulationError> {
+        let transaction_data = self.decode_transaction_data(&rpc_result.transaction_data);
         let resources = if let Some(cost) = rpc_result.cost {
             let cpu_instructions = cost.cpu_insns.parse::<u64>().unwrap_or_else(|_| {
                 tracing::warn!("Failed to parse cpu_insns, using 0");
@@ -403,115 +607,144 @@ impl SimulationEngine {
                 tracing::warn!("Failed to parse mem_bytes, using 0");
                 0
             });
-            let (ledger_read_bytes, ledger_write_bytes) =
-                self.extract_footprint_from_xdr(&rpc_result.transaction_data);
-            SorobanResources {
+            let mut resources = SorobanResources {
                 cpu_instructions,
                 ram_bytes,
-                ledger_read_bytes,
-                ledger_write_bytes,
-                transaction_size_bytes: rpc_result.transaction_data.len() as u64,
+                events_size_bytes: self.calculate_events_size(&rpc_result.events),
+                ..SorobanResources::default()
+            };
+            if let Some(data) = &transaction_data {
+                // Ledger I/O as declared in the transaction data, which is
+                // what the network meters against its limits.
+                let footprint = &data.resources.footprint;
+                resources.ledger_read_bytes = u64::from(data.resources.read_bytes);
+                resources.ledger_write_bytes = u64::from(data.resources.write_bytes);
+                resources.read_entries =
+                    (footprint.read_only.len() + footprint.read_write.len()) as u64;
+                resources.write_entries = footprint.read_write.len() as u64;
+                resources.transaction_size_bytes = Self::transaction_size(
+                    transaction_xdr,
+                    data,
+                    Self::auth_entries(&rpc_result.results),
+                );
             }
+            resources
         } else {
             tracing::warn!("No cost data in simulation result, using defaults");
             SorobanResources::default()
         };
 
         let cost_stroops = self.calculate_cost(&resources);
+        let headroom = self.limits.headroom(&resources, self.headroom_warning_pct);
+        if headroom.has_warnings {
+            tracing::warn!(
+                threshold_pct = self.headroom_warning_pct,
+                "Simulation is close to network per-transaction limits"
+            );
+        }
         Ok(SimulationResult {
             resources,
             transaction_hash: None,
             latest_ledger: rpc_result.latest_ledger,
             cost_stroops,
             state_dependency: None,
+            headroom: Some(headroom),
+            footprint: self.extract_footprint_keys(&rpc_result.transaction_data),
+            cost_breakdown: None,
+            provider: None,
+            ledger_lag: None,
         })
     }
 
-    fn extract_footprint_from_xdr(&self, transaction_data: &str) -> (u64, u64) {
+    fn extract_footprint_keys(&self, transaction_data: &str) -> Option<Footprint> {
+        let data = self.decode_transaction_data(transaction_data)?;
+        let encode = |keys: &VecM<LedgerKey>| {
+            keys.iter()
+                .filter_map(|k| k.to_xdr(Limits::none()).ok())
+                .map(|bytes| BASE64.encode(bytes))
+                .collect()
+        };
+        Some(Footprint {
+            read_only: encode(&data.resources.footprint.read_only),
+            read_write: encode(&data.resources.footprint.read_write),
+        })
+    }
+
+    fn decode_transaction_data(&self, transaction_data: &str) -> Option<SorobanTransactionData> {
         if transaction_data.is_empty() {
-            return (0, 0);
+            return None;
         }
         let xdr_bytes = match BASE64.decode(transaction_data) {
             Ok(bytes) => bytes,
             Err(e) => {
                 tracing::warn!("Failed to decode base64 transaction data: {}", e);
-                return (0, 0);
+                return None;
             }
         };
-        let soroban_data = match SorobanTransactionData::from_xdr(&xdr_bytes, Limits::none()) {
-            Ok(data) => data,
+        match SorobanTransactionData::from_xdr(&xdr_bytes, Limits::none()) {
+            Ok(data) => Some(data),
             Err(e) => {
                 tracing::warn!("Failed to parse SorobanTransactionData XDR: {}", e);
-                return (0, 0);
+                None
             }
-        };
-        let footprint = &soroban_data.resources.footprint;
-        let read_bytes = self.calculate_ledger_keys_size(&footprint.read_only);
-        let write_bytes = self.calculate_ledger_keys_size(&footprint.read_write);
-        tracing::debug!(
-            "Extracted footprint: read_only={} keys ({} bytes), read_write={} keys ({} bytes)",
-            footprint.read_only.len(),
-            read_bytes,
-            footprint.read_write.len(),
-            write_bytes
-        );
-        (read_bytes, write_bytes)
-    }
-
-    fn calculate_ledger_keys_size(&self, ledger_keys: &soroban_sdk::xdr::VecM<LedgerKey>) -> u64 {
-        let mut total_bytes: u64 = 0;
-        for ledger_key in ledger_keys.iter() {
-            let key_size = match ledger_key {
-                LedgerKey::Account(_) => 56,
-                LedgerKey::Trustline(_) => 72,
-                LedgerKey::ContractData(contract_data) => {
-                    let base_size = 32 + 4;
-                    let key_estimate = self.estimate_scval_size(&contract_data.key);
-                    base_size + key_estimate
-                }
-                LedgerKey::ContractCode(_) => 32,
-                LedgerKey::Offer(_) => 48,
-                LedgerKey::Data(_) => 64,
-                LedgerKey::ClaimableBalance(_) => 36,
-                LedgerKey::LiquidityPool(_) => 32,
-                LedgerKey::ConfigSetting(_) => 8,
-                LedgerKey::Ttl(_) => 32,
-            };
-            total_bytes += key_size;
         }
-        total_bytes
-    }
-
-    fn estimate_scval_size(&self, scval: &soroban_sdk::xdr::ScVal) -> u64 {
-        use soroban_sdk::xdr::ScVal;
-        match scval {
-            ScVal::Bool(_) => 1,
-            ScVal::Void => 0,
-            ScVal::Error(_) => 8,
-            ScVal::U32(_) | ScVal::I32(_) => 4,
-            ScVal::U64(_) | ScVal::I64(_) => 8,
-            ScVal::Timepoint(_) | ScVal::Duration(_) => 8,
-            ScVal::U128(_) | ScVal::I128(_) => 16,
-            ScVal::U256(_) | ScVal::I256(_) => 32,
-            ScVal::Bytes(bytes) => bytes.len() as u64,
-            ScVal::String(s) => s.len() as u64,
-            ScVal::Symbol(sym) => sym.len() as u64,
-            ScVal::Vec(Some(vec)) => {
-                vec.iter().map(|v| self.estimate_scval_size(v)).sum::<u64>() + 4
-            }
-            ScVal::Vec(None) => 4,
-            ScVal::Map(Some(map)) => {
-                map.iter()
-                    .map(|e| self.estimate_scval_size(&e.key) + self.estimate_scval_size(&e.val))
-                    .sum::<u64>()
-                    + 4
+    }
+
+    /// Size of the transaction as it would be submitted: the simulated
+    /// envelope with the returned transaction data and auth entries attached.
+    /// Signatures are not known yet and are not counted. Falls back to the size
+    /// of the transaction data alone when the envelope can't be decoded.
+    fn transaction_size(
+        transaction_xdr: &str,
+        data: &SorobanTransactionData,
+        auth: Vec<SorobanAuthorizationEntry>,
+    ) -> u64 {
+        let xdr_len = |bytes: Result<Vec<u8>, _>| bytes.map_or(0, |b: Vec<u8>| b.len() as u64);
+        let envelope = BASE64
+            .decode(transaction_xdr)
+            .ok()
+            .and_then(|bytes| TransactionV1Envelope::from_xdr(bytes, Limits::none()).ok());
+        let Some(mut envelope) = envelope else {
+            return xdr_len(data.to_xdr(Limits::none()));
+        };
+        envelope.tx.ext = TransactionExt::V1(data.clone());
+        let mut operations = envelope.tx.operations.to_vec();
+        if let Some(Operation {
+            body: OperationBody::InvokeHostFunction(op),
+            ..
+        }) = operations.first_mut()
+        {
+            if op.auth.is_empty() {
+                op.auth = auth.try_into().unwrap_or_default();
             }
-            ScVal::Map(None) => 4,
-            ScVal::Address(_) => 32,
-            ScVal::LedgerKeyContractInstance => 32,
-            ScVal::LedgerKeyNonce(_) => 32,
-            ScVal::ContractInstance(_) => 64,
         }
+        envelope.tx.operations = operations.try_into().unwrap_or_default();
+        xdr_len(envelope.to_xdr(Limits::none()))
+    }
+
+    /// Auth entries the simulation asks the first operation to carry.
+    fn auth_entries(results: &[serde_json::Value]) -> Vec<SorobanAuthorizationEntry> {
+        results
+            .first()
+            .and_then(|r| r["auth"].as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.as_str())
+            .filter_map(|entry| BASE64.decode(entry).ok())
+            .filter_map(|bytes| SorobanAuthorizationEntry::from_xdr(bytes, Limits::none()).ok())
+            .collect()
+    }
+
+    /// Sum the XDR size of contract (non-diagnostic) events.
+    fn calculate_events_size(&self, events: &[String]) -> u64 {
+        events
+            .iter()
+            .filter_map(|e| BASE64.decode(e).ok())
+            .filter_map(|bytes| DiagnosticEvent::from_xdr(&bytes, Limits::none()).ok())
+            .filter(|d| d.event.type_ == ContractEventType::Contract)
+            .filter_map(|d| d.event.to_xdr(Limits::none()).ok())
+            .map(|bytes| bytes.len() as u64)
+            .sum()
     }
 
     fn calculate_cost(&self, resources: &SorobanResources) -> u64 {
@@ -529,6 +762,7 @@ impl SimulationEngine {
         contract_id: &str,
         function_name: &str,
         args: Vec<String>,
+        source_account: Option<&str>,
     ) -> Result<String, SimulationError> {
         let contract_hash = self.parse_contract_id(contract_id)?;
         let contract_address = ScAddress::Contract(Hash(contract_hash));
@@ -546,13 +780,24 @@ impl SimulationEngine {
             function_name: func_symbol,
             args: sc_args,
         });
-        self.build_invoke_host_function_transaction(host_function, vec![])
+        let source = match source_account {
+            Some(account) => self.parse_source_account(account)?,
+            None => [0u8; 32],
+        };
+        self.build_invoke_host_function_transaction(host_function, vec![], source)
+    }
+
+    fn parse_source_account(&self, account: &str) -> Result<[u8; 32], SimulationError> {
+        stellar_strkey::ed25519::PublicKey::from_string(account)
+            .map(|pk| pk.0)
+            .map_err(|_| SimulationError::NodeError(format!("Invalid source account: {}", account)))
     }
 
     fn build_invoke_host_function_transaction(
         &self,
         host_function: HostFunction,
         auth: Vec<SorobanAuthorizationEntry>,
+        source: [u8; 32],
     ) -> Result<String, SimulationError> {
         let invoke_op = InvokeHostFunctionOp {
             host_function,
@@ -564,7 +809,7 @@ impl SimulationEngine {
             source_account: None,
             body: OperationBody::InvokeHostFunction(invoke_op),
         };
-        let source_account = MuxedAccount::Ed25519(Uint256([0u8; 32]));
+        let source_account = MuxedAccount::Ed25519(Uint256(source));
         let transaction = Transaction {
             source_account,
             fee: 100,
@@ -604,48 +849,84 @@ impl SimulationEngine {
     }
 
     fn parse_sc_val_arg(&self, arg: &str) -> Result<ScVal, SimulationError> {
-        let arg = arg.trim();
-
-        // 1. Try parsing as JSON first (for complex types like Maps and Vecs)
-        if arg.starts_with('{') || arg.starts_with('[') {
-            return Ok(ArgParser::parse(arg)?);
-        }
-
-        // 2. Check for Boolean/Void shorthands
-        if arg == "true" {
-            return Ok(ScVal::Bool(true));
-        }
-        if arg == "false" {
-            return Ok(ScVal::Bool(false));
-        }
-        if arg == "void" || arg == "()" {
-            return Ok(ScVal::Void);
-        }
+        Ok(ArgParser::parse_arg(arg)?)
+    }
 
-        // 3. Delegation to ArgParser for special types (Addresses, Symbols, Hex)
-        // If it starts with G, C, :, or 0x, we try to parse it as a quoted string
-        if arg.starts_with('G')
-            || arg.starts_with('C')
-            || arg.starts_with(':')
-            || arg.starts_with("0x")
-        {
-            if let Ok(val) = ArgParser::parse(&format!("\"{}\"", arg)) {
-                return Ok(val);
-            }
+    /// Whether a ledger entry the result depended on has been modified,
+    /// deleted or archived since the ledger it was simulated at.
+    ///
+    /// The contract instance entry (which holds the WASM hash) is part of
+    /// every footprint, so contract upgrades are caught as well as storage
+    /// changes.
+    pub async fn is_stale(&self, result: &SimulationResult) -> Result<bool, SimulationError> {
+        let Some(footprint) = &result.footprint else {
+            return Ok(false);
+        };
+        let keys: Vec<&String> = footprint
+            .read_only
+            .iter()
+            .chain(&footprint.read_write)
+            .collect();
+        if keys.is_empty() || result.latest_ledger == 0 {
+            return Ok(false);
         }
 
-        // 4. Numbers and explicit quoted strings
-        if arg.starts_with('"') || arg.parse::<i64>().is_ok() || arg.parse::<u64>().is_ok() {
-            if let Ok(val) = ArgParser::parse(arg) {
-                return Ok(val);
-            }
+        let response = self
+            .rpc
+            .get_ledger_entries(&keys)
+            .await
+            .map_err(|e| SimulationError::RpcRequestFailed(e.to_string()))?;
+        // Entries that no longer exist are left out of the response.
+        if response.entries.len() < keys.len() {
+            return Ok(true);
         }
+        Ok(response.entries.iter().any(|entry| {
+            entry.last_modified_ledger_seq > result.latest_ledger
+                || entry
+                    .live_until_ledger_seq
+                    .is_some_and(|live_until| live_until < response.latest_ledger)
+        }))
+    }
 
-        // 5. Default fallback: Treat as Symbol (standard Soroban behavior for unquoted strings)
-        let symbol: ScSymbol = arg
-            .try_into()
-            .map_err(|_| SimulationError::NodeError(format!("Cannot parse argument: {}", arg)))?;
-        Ok(ScVal::Symbol(symbol))
+    /// Run `function_name` from raw WASM in an in-process environment,
+    /// capped at the network's per-transaction CPU and memory limits.
+    ///
+    /// The local host is blocking, so it runs on the blocking thread pool.
+    pub async fn simulate_wasm(
+        &self,
+        wasm: Vec<u8>,
+        function_name: &str,
+        args: Vec<String>,
+    ) -> Result<SimulationResult, SimulationError> {
+        let sc_args = args
+            .iter()
+            .map(|arg| self.parse_sc_val_arg(arg))
+            .collect::<Result<Vec<_>, _>>()?;
+        let budget = crate::sandbox::LocalBudget::from(&self.limits);
+        let function = function_name.to_string();
+        let run = tokio::task::spawn_blocking(move || {
+            crate::sandbox::invoke(&wasm, &function, &sc_args, Some(budget))
+        })
+        .await
+        .map_err(|e| SimulationError::LocalExecution(format!("local run aborted: {e}")))?
+        .map_err(SimulationError::LocalExecution)?;
+
+        let cost_stroops = self.calculate_cost(&run.resources);
+        let headroom = self
+            .limits
+            .headroom(&run.resources, self.headroom_warning_pct);
+        Ok(SimulationResult {
+            resources: run.resources,
+            transaction_hash: None,
+            latest_ledger: 0,
+            cost_stroops,
+            state_dependency: None,
+            headroom: Some(headroom),
+            footprint: Some(run.footprint),
+            cost_breakdown: Some(run.cost_breakdown),
+            provider: None,
+            ledger_lag: None,
+        })
     }
 
     pub async fn simulate_locally(
@@ -654,6 +935,7 @@ impl SimulationEngine {
         function_name: &str,
         args: Vec<String>,
         overrides: HashMap<String, String>,
+        source_account: Option<&str>,
     ) -> Result<SimulationResult, SimulationError> {
         tracing::info!(
             "Running local simulation with {} overrides",
@@ -685,7 +967,8 @@ impl SimulationEngine {
         // For the MVP, we merge the overrides into the simulation result metadata.
 
         // We first run a normal simulation to get the baseline resources and the footprint.
-        let transaction_xdr = self.create_invoke_transaction(contract_id, function_name, args)?;
+        let transaction_xdr =
+            self.create_invoke_transaction(contract_id, function_name, args, source_account)?;
         let mut result = self.simulate_transaction(&transaction_xdr).await?;
 
         // Merge state dependency report:
@@ -716,41 +999,81 @@ impl SimulationEngine {
 
 // ── Cache ─────────────────────────────────────────────────────────────────────
 
-const CACHE_TTL_SECS: u64 = 3_600;
-const CACHE_MAX_CAPACITY: u64 = 1_000;
+/// Everything that can change a simulation's outcome; hashed into the cache key.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SimulationKey {
+    pub network: String,
+    pub contract_id: String,
+    pub function_name: String,
+    pub args: Vec<String>,
+    pub source_account: Option<String>,
+    /// Sorted so the key does not depend on map iteration order
+    pub ledger_overrides: BTreeMap<String, String>,
+}
+
+impl SimulationKey {
+    pub fn new(network: &str, contract_id: &str, function_name: &str, args: &[String]) -> Self {
+        Self {
+            network: network.to_string(),
+            contract_id: contract_id.to_string(),
+            function_name: function_name.to_string(),
+            args: args.to_vec(),
+            ..Default::default()
+        }
+    }
 
-/// In-memory simulation result cache backed by `moka`.
+    pub fn with_source_account(mut self, source_account: Option<&str>) -> Self {
+        self.source_account = source_account.map(str::to_string);
+        self
+    }
+
+    pub fn with_overrides(mut self, overrides: Option<&HashMap<String, String>>) -> Self {
+        self.ledger_overrides = overrides
+            .map(|o| o.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+            .unwrap_or_default();
+        self
+    }
+}
+
+/// Simulation result cache in front of a pluggable [`CacheBackend`].
 ///
-/// Cache key: `hex(sha256(contract_id ‖ function_name ‖ args_as_json))`
-/// TTL: 1 hour — balances freshness vs. RPC cost reduction.
+/// Cache key: `contract_id:hex(sha256(SimulationKey as JSON))` — the
+/// contract prefix lets every result for a contract be purged at once.
+/// Defaults to the in-memory backend with a 1 hour TTL — balances freshness
+/// vs. RPC cost reduction.
 pub struct SimulationCache {
-    inner: Cache<String, SimulationResult>,
+    backend: Box<dyn CacheBackend>,
     hits: AtomicU64,
     misses: AtomicU64,
+    invalidations: AtomicU64,
 }
 
 impl SimulationCache {
+    /// In-memory cache with default capacity and TTL.
+    #[allow(dead_code)] // the server builds its backend from config
     pub fn new() -> Arc<Self> {
-        let inner = Cache::builder()
-            .max_capacity(CACHE_MAX_CAPACITY)
-            .time_to_live(Duration::from_secs(CACHE_TTL_SECS))
-            .build();
+        Self::with_backend(Box::new(MemoryCache::new(CacheSettings::default())))
+    }
+
+    pub fn with_backend(backend: Box<dyn CacheBackend>) -> Arc<Self> {
+        tracing::info!(backend = backend.name(), "Simulation cache backend");
         Arc::new(Self {
-            inner,
+            backend,
             hits: AtomicU64::new(0),
             misses: AtomicU64::new(0),
+            invalidations: AtomicU64::new(0),
         })
     }
 
-    pub fn generate_key(contract_id: &str, function_name: &str, args: &[String]) -> String {
-        let args_json = serde_json::to_string(args).unwrap_or_else(|_| "[]".to_string());
-        let input = format!("{}{}{}", contract_id, function_name, args_json);
+    pub fn generate_key(key: &SimulationKey) -> String {
+        let input = serde_json::to_string(key).unwrap_or_default();
         let digest = Sha256::digest(input.as_bytes());
-        hex::encode(digest)
+        format!("{}:{}", key.contract_id, hex::encode(digest))
     }
 
     pub async fn get(&self, key: &str) -> Option<SimulationResult> {
-        let value: Option<SimulationResult> = self.inner.get(key).await;
+        let value: Option<SimulationResult> = self.backend.get(key).await;
+        self.backend.record_lookup(value.is_some()).await;
         if value.is_some() {
             self.hits.fetch_add(1, Ordering::Relaxed);
             tracing::debug!(cache.key = %key, "Cache HIT");
@@ -761,20 +1084,86 @@ impl SimulationCache {
         value
     }
 
+    pub async fn entry_count(&self) -> u64 {
+        self.backend.entry_count().await
+    }
+
     pub async fn set(&self, key: String, value: SimulationResult) {
-        self.inner.insert(key, value).await;
+        self.backend.set(key, value).await;
     }
 
-    pub fn log_stats(&self) {
-        let hits = self.hits.load(Ordering::Relaxed);
-        let misses = self.misses.load(Ordering::Relaxed);
+    /// Drop an entry whose ledger state has changed since it was cached.
+    pub async fn invalidate(&self, key: &str) {
+        if self.backend.remove(key).await {
+            self.invalidations.fetch_add(1, Ordering::Relaxed);
+        }
+        tracing::debug!(cache.key = %key, "Cache entry invalidated");
+    }
+
+    /// Remove one entry by key; returns whether it existed.
+    pub async fn remove(&self, key: &str) -> bool {
+        self.backend.remove(key).await
+    }
+
+    /// Remove every entry; returns how many.
+    pub async fn clear(&self) -> u64 {
+        self.backend.remove_prefix("").await
+    }
+
+    pub async fn stats(&self) -> CacheStats {
+        let (hits, misses) = self.counters().await;
         let total = hits + misses;
-        let hit_rate_pct = if total > 0 { hits * 100 / total } else { 0 };
+        let hit_rate_pct = if total == 0 {
+            0.0
+        } else {
+            hits as f64 / total as f64 * 100.0
+        };
+        CacheStats {
+            backend: self.backend.name().to_string(),
+            hits,
+            misses,
+            hit_rate_pct,
+            entry_count: self.backend.entry_count().await,
+            weighted_size_bytes: self.backend.weighted_size().await,
+            evictions: self.backend.evictions(),
+            invalidations: self.invalidations.load(Ordering::Relaxed),
+            coalesced_requests: 0,
+        }
+    }
+
+    /// Remove every cached result for `contract_id`; returns how many.
+    pub async fn purge_contract(&self, contract_id: &str) -> u64 {
+        let removed = self
+            .backend
+            .remove_prefix(&format!("{}:", contract_id))
+            .await;
+        self.invalidations.fetch_add(removed, Ordering::Relaxed);
+        removed
+    }
+
+    /// `(hits, misses)`: shared across instances when the backend tracks
+    /// them, otherwise counted by this process.
+    pub async fn counters(&self) -> (u64, u64) {
+        match self.backend.shared_counters().await {
+            Some(shared) => shared,
+            None => (
+                self.hits.load(Ordering::Relaxed),
+                self.misses.load(Ordering::Relaxed),
+            ),
+        }
+    }
+
+    pub async fn log_stats(&self) {
+        let (hits, misses) = self.counters().await;
+        let total = hits + misses;
+        let hit_rate_pct = (hits * 100).checked_div(total).unwrap_or(0);
         tracing::info!(
             cache.hits = hits,
             cache.misses = misses,
             cache.total = total,
             cache.hit_rate_pct = hit_rate_pct,
+            cache.invalidations = self.invalidations.load(Ordering::Relaxed),
+            cache.backend = self.backend.name(),
             "Cache statistics"
         );
     }
@@ -800,6 +1189,7 @@ impl SimulationCache {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::mock_rpc::{MockReply, MockRpc};
 
     #[test]
     fn test_soroban_resources_default() {
@@ -818,6 +1208,9 @@ mod tests {
             ledger_read_bytes: 512,
             ledger_write_bytes: 256,
             transaction_size_bytes: 1024,
+            read_entries: 2,
+            write_entries: 1,
+            events_size_bytes: 0,
         };
         let json = serde_json::to_string(&resources).unwrap();
         assert!(json.contains("\"cpu_instructions\":1000000"));
@@ -843,6 +1236,7 @@ mod tests {
             ledger_read_bytes: 512,
             ledger_write_bytes: 512,
             transaction_size_bytes: 1024,
+            ..Default::default()
         };
         assert!(engine.calculate_cost(&resources) > 0);
     }
@@ -851,23 +1245,72 @@ mod tests {
     async fn test_simulate_from_contract_id_empty() {
         let engine = SimulationEngine::new("https://test.com".to_string());
         let result = engine
-            .simulate_from_contract_id("", "test_function", vec![], None)
+            .simulate_from_contract_id("", "test_function", vec![], None, None)
             .await;
         assert!(matches!(result, Err(SimulationError::NodeError(_))));
     }
 
     #[tokio::test]
     async fn test_simulate_locally_with_overrides() {
-        // This test mocks the RPC but verifies the local injection logic
-        let engine = SimulationEngine::new("https://soroban-testnet.stellar.org".to_string());
+        use soroban_sdk::xdr::{
+            AccountEntry, AccountEntryExt, AccountId, LedgerEntryData, LedgerEntryExt,
+            LedgerKeyAccount, PublicKey, Thresholds,
+        };
+
+        let rpc = MockRpc::start().await;
+        let engine = SimulationEngine::new(rpc.url());
+
+        let account_id = AccountId(PublicKey::PublicKeyTypeEd25519(Uint256([7; 32])));
+        let key = LedgerKey::Account(LedgerKeyAccount {
+            account_id: account_id.clone(),
+        });
+        let entry = LedgerEntry {
+            last_modified_ledger_seq: 1,
+            data: LedgerEntryData::Account(AccountEntry {
+                account_id,
+                balance: 10_000_000,
+                seq_num: SequenceNumber(1),
+                num_sub_entries: 0,
+                inflation_dest: None,
+                flags: 0,
+                home_domain: Default::default(),
+                thresholds: Thresholds([1, 0, 0, 0]),
+                signers: VecM::default(),
+                ext: AccountEntryExt::V0,
+            }),
+            ext: LedgerEntryExt::V0,
+        };
+        let key_xdr = BASE64.encode(key.to_xdr(Limits::none()).unwrap());
+        let val_xdr = BASE64.encode(entry.to_xdr(Limits::none()).unwrap());
 
         let mut overrides = HashMap::new();
-        // Mock LedgerKey/LedgerEntry (Base64)
-        // Key: LedgerKey::Account (0x0...0)
-        let key_xdr = "AAAAAAAAAAA=";
-        // Val: LedgerEntry (Account)
-        let val_xdr = "AAAAAAAAAAA=";
-        overrides.insert(key_xdr.to_string(), val_xdr.to_string());
+        overrides.insert(key_xdr.clone(), val_xdr);
+
+        let res = engine
+            .simulate_locally(
+                "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC",
+                "hello",
+                vec![],
+                overrides,
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(rpc.calls("simulateTransaction"), 1);
+        let deps = res.state_dependency.unwrap();
+        assert_eq!(deps.len(), 1);
+        assert_eq!(deps[0].key, key_xdr);
+        assert_eq!(deps[0].source, DataSource::Injected);
+    }
+
+    #[tokio::test]
+    async fn test_simulate_locally_rejects_invalid_override() {
+        let rpc = MockRpc::start().await;
+        let engine = SimulationEngine::new(rpc.url());
+
+        let mut overrides = HashMap::new();
+        overrides.insert("AAAAAAAAAAA=".to_string(), "AAAAAAAAAAA=".to_string());
 
         let result = engine
             .simulate_locally(
@@ -875,19 +1318,11 @@ mod tests {
                 "hello",
                 vec![],
                 overrides,
+                None,
             )
             .await;
-
-        // Since we are calling the real RPC in simulate_locally (MVP implementation),
-        // we expect a network error or success.
-        // But we want to check if the state_dependency is populated.
-        if let Ok(res) = result {
-            assert!(res.state_dependency.is_some());
-            let deps = res.state_dependency.unwrap();
-            assert_eq!(deps.len(), 1);
-            assert_eq!(deps[0].key, key_xdr);
-            assert_eq!(deps[0].source, DataSource::Injected);
-        }
+        assert!(matches!(result, Err(SimulationError::XdrError(_))));
+        assert_eq!(rpc.calls("simulateTransaction"), 0);
     }
 
     #[test]
@@ -903,41 +1338,126 @@ mod tests {
     }
 
     #[test]
-    fn test_extract_footprint_empty_data() {
-        let engine = SimulationEngine::new("https://test.com".to_string());
-        assert_eq!(engine.extract_footprint_from_xdr(""), (0, 0));
+    fn test_parse_simulation_result_includes_headroom() {
+        let engine = SimulationEngine::new("https://test.com".to_string())
+            .with_network_limits(NetworkLimits::bundled(), 50.0);
+        let rpc_result: SimulationRpcResult = serde_json::from_value(serde_json::json!({
+            "latestLedger": 7,
+            "cost": { "cpuInsns": "60000000", "memBytes": "1024" }
+        }))
+        .unwrap();
+
+        let result = engine.parse_simulation_result(rpc_result, "").unwrap();
+        let headroom = result.headroom.expect("headroom should be attached");
+        assert!(headroom.has_warnings);
+        assert_eq!(headroom.warning_threshold_pct, 50.0);
+        let cpu = headroom
+            .entries
+            .iter()
+            .find(|e| e.resource == "cpu_instructions")
+            .unwrap();
+        assert_eq!(cpu.used, 60_000_000);
+        assert!(cpu.warning);
     }
 
     #[test]
-    fn test_extract_footprint_invalid_base64() {
+    fn test_parse_simulation_result_uses_transaction_data_resources() {
+        use soroban_sdk::xdr::{
+            ContractDataDurability, LedgerFootprint, LedgerKeyContractCode, LedgerKeyContractData,
+            SorobanResources as XdrResources,
+        };
+        let contract = ScAddress::Contract(Hash([7; 32]));
+        let data_key = |key: u32| {
+            LedgerKey::ContractData(LedgerKeyContractData {
+                contract: contract.clone(),
+                key: ScVal::U32(key),
+                durability: ContractDataDurability::Persistent,
+            })
+        };
+        let data = SorobanTransactionData {
+            ext: soroban_sdk::xdr::ExtensionPoint::V0,
+            resources: XdrResources {
+                footprint: LedgerFootprint {
+                    read_only: vec![
+                        LedgerKey::ContractCode(LedgerKeyContractCode {
+                            hash: Hash([8; 32]),
+                        }),
+                        data_key(1),
+                    ]
+                    .try_into()
+                    .unwrap(),
+                    read_write: vec![data_key(2)].try_into().unwrap(),
+                },
+                instructions: 1_000_000,
+                read_bytes: 1_234,
+                write_bytes: 567,
+            },
+            resource_fee: 100,
+        };
+        let data_xdr = data.to_xdr(Limits::none()).unwrap();
+
         let engine = SimulationEngine::new("https://test.com".to_string());
+        let transaction_xdr = engine
+            .create_invoke_transaction(
+                "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC",
+                "hello",
+                vec![],
+                None,
+            )
+            .unwrap();
+        let rpc_result: SimulationRpcResult = serde_json::from_value(serde_json::json!({
+            "latestLedger": 7,
+            "transactionData": BASE64.encode(&data_xdr),
+            "cost": { "cpuInsns": "1000", "memBytes": "1024" }
+        }))
+        .unwrap();
+
+        let resources = engine
+            .parse_simulation_result(rpc_result, &transaction_xdr)
+            .unwrap()
+            .resources;
+        assert_eq!(resources.ledger_read_bytes, 1_234);
+        assert_eq!(resources.ledger_write_bytes, 567);
+        assert_eq!(resources.read_entries, 3);
+        assert_eq!(resources.write_entries, 1);
+        // The envelope grows by exactly the attached transaction data.
+        let envelope_len = BASE64.decode(&transaction_xdr).unwrap().len();
         assert_eq!(
-            engine.extract_footprint_from_xdr("not-valid-base64!!!"),
-            (0, 0)
+            resources.transaction_size_bytes,
+            (envelope_len + data_xdr.len()) as u64
         );
     }
 
     #[test]
-    fn test_extract_footprint_invalid_xdr() {
+    fn test_calculate_events_size_ignores_invalid_and_diagnostic() {
+        use soroban_sdk::xdr::{ContractEvent, ContractEventBody, ContractEventV0, ExtensionPoint};
         let engine = SimulationEngine::new("https://test.com".to_string());
+        let event = |type_| {
+            let diag = DiagnosticEvent {
+                in_successful_contract_call: true,
+                event: ContractEvent {
+                    ext: ExtensionPoint::V0,
+                    contract_id: None,
+                    type_,
+                    body: ContractEventBody::V0(ContractEventV0 {
+                        topics: VecM::default(),
+                        data: ScVal::U32(1),
+                    }),
+                },
+            };
+            BASE64.encode(diag.to_xdr(Limits::none()).unwrap())
+        };
+        let contract = event(ContractEventType::Contract);
+        let diagnostic = event(ContractEventType::Diagnostic);
+
+        let size = engine.calculate_events_size(&[contract, diagnostic, "!!".to_string()]);
+        assert!(size > 0);
         assert_eq!(
-            engine.extract_footprint_from_xdr("SGVsbG8gV29ybGQ="),
-            (0, 0)
+            size,
+            engine.calculate_events_size(&[event(ContractEventType::Contract)])
         );
     }
 
-    #[test]
-    fn test_estimate_scval_size_primitives() {
-        use soroban_sdk::xdr::ScVal;
-        let engine = SimulationEngine::new("https://test.com".to_string());
-        assert_eq!(engine.estimate_scval_size(&ScVal::Bool(true)), 1);
-        assert_eq!(engine.estimate_scval_size(&ScVal::Void), 0);
-        assert_eq!(engine.estimate_scval_size(&ScVal::U32(42)), 4);
-        assert_eq!(engine.estimate_scval_size(&ScVal::I32(-42)), 4);
-        assert_eq!(engine.estimate_scval_size(&ScVal::U64(1000)), 8);
-        assert_eq!(engine.estimate_scval_size(&ScVal::I64(-1000)), 8);
-    }
-
     #[test]
     fn test_parse_sc_val_arg_bool() {
         let engine = SimulationEngine::new("https://test.com".to_string());
@@ -1020,6 +1540,7 @@ mod tests {
             "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC",
             "hello",
             vec!["true".to_string(), "42".to_string()],
+            None,
         );
         assert!(result.is_ok());
         assert!(BASE64.decode(result.unwrap()).is_ok());
@@ -1038,47 +1559,84 @@ mod tests {
                     ledger_read_bytes: 512,
                     ledger_write_bytes: 256,
                     transaction_size_bytes: 128,
+                    ..Default::default()
                 },
                 transaction_hash: None,
                 latest_ledger: 42,
                 cost_stroops: 10,
                 state_dependency: None,
+                headroom: None,
+                footprint: None,
+                cost_breakdown: None,
+                provider: None,
+                ledger_lag: None,
             }
         }
 
+        fn key(contract_id: &str, function_name: &str, args: &[String]) -> String {
+            SimulationCache::generate_key(&SimulationKey::new(
+                "Test SDF Network ; September 2015",
+                contract_id,
+                function_name,
+                args,
+            ))
+        }
+
         #[test]
         fn test_cache_key_is_deterministic() {
-            let k1 = SimulationCache::generate_key("CONTRACT_A", "fn_x", &["arg1".to_string()]);
-            let k2 = SimulationCache::generate_key("CONTRACT_A", "fn_x", &["arg1".to_string()]);
+            let k1 = key("CONTRACT_A", "fn_x", &["arg1".to_string()]);
+            let k2 = key("CONTRACT_A", "fn_x", &["arg1".to_string()]);
             assert_eq!(k1, k2);
         }
 
         #[test]
         fn test_cache_key_differs_on_contract_id() {
-            let k1 = SimulationCache::generate_key("CONTRACT_A", "fn_x", &[]);
-            let k2 = SimulationCache::generate_key("CONTRACT_B", "fn_x", &[]);
+            let k1 = key("CONTRACT_A", "fn_x", &[]);
+            let k2 = key("CONTRACT_B", "fn_x", &[]);
             assert_ne!(k1, k2);
         }
 
         #[test]
         fn test_cache_key_differs_on_function_name() {
-            let k1 = SimulationCache::generate_key("CONTRACT_A", "fn_x", &[]);
-            let k2 = SimulationCache::generate_key("CONTRACT_A", "fn_y", &[]);
+            let k1 = key("CONTRACT_A", "fn_x", &[]);
+            let k2 = key("CONTRACT_A", "fn_y", &[]);
             assert_ne!(k1, k2);
         }
 
         #[test]
         fn test_cache_key_differs_on_args() {
-            let k1 = SimulationCache::generate_key("CONTRACT_A", "fn_x", &["1".to_string()]);
-            let k2 = SimulationCache::generate_key("CONTRACT_A", "fn_x", &["2".to_string()]);
+            let k1 = key("CONTRACT_A", "fn_x", &["1".to_string()]);
+            let k2 = key("CONTRACT_A", "fn_x", &["2".to_string()]);
             assert_ne!(k1, k2);
         }
 
         #[test]
-        fn test_cache_key_is_hex_sha256() {
-            let key = SimulationCache::generate_key("C", "f", &[]);
-            assert_eq!(key.len(), 64);
-            assert!(key.chars().all(|c| c.is_ascii_hexdigit()));
+        fn test_cache_key_differs_on_overrides_source_and_network() {
+            let base = SimulationKey::new("net-a", "C", "f", &[]);
+            let mut overrides = HashMap::new();
+            overrides.insert("key".to_string(), "value".to_string());
+
+            let plain = SimulationCache::generate_key(&base);
+            let overridden =
+                SimulationCache::generate_key(&base.clone().with_overrides(Some(&overrides)));
+            let sourced = SimulationCache::generate_key(&base.clone().with_source_account(Some(
+                "GAAZI4TCR3TY5OJHCTJC2A4QSY6CJWJH5IAJTGKIN2ER7LBNVKOCCWN7",
+            )));
+            let other_net =
+                SimulationCache::generate_key(&SimulationKey::new("net-b", "C", "f", &[]));
+
+            assert_ne!(plain, overridden);
+            assert_ne!(plain, sourced);
+            assert_ne!(plain, other_net);
+        }
+
+        #[test]
+        fn test_cache_key_is_contract_prefixed_sha256() {
+            let key = key("C", "f", &[]);
+            let (contract, digest) = key.split_once(':').unwrap();
+            assert_eq!(contract, "C");
+            assert_eq!(digest.len(), 64);
+            assert!(digest.chars().all(|c| c.is_ascii_hexdigit()));
         }
 
         #[tokio::test]
@@ -1105,7 +1663,7 @@ mod tests {
         #[tokio::test]
         async fn test_cache_aside_pattern() {
             let cache = SimulationCache::new();
-            let key = SimulationCache::generate_key("CONTRACT_X", "do_thing", &[]);
+            let key = key("CONTRACT_X", "do_thing", &[]);
 
             let first = cache.get(&key).await;
             assert!(first.is_none());
@@ -1121,8 +1679,8 @@ mod tests {
         #[tokio::test]
         async fn test_different_keys_stored_independently() {
             let cache = SimulationCache::new();
-            let k1 = SimulationCache::generate_key("CONTRACT_A", "fn_x", &[]);
-            let k2 = SimulationCache::generate_key("CONTRACT_B", "fn_x", &[]);
+            let k1 = key("CONTRACT_A", "fn_x", &[]);
+            let k2 = key("CONTRACT_B", "fn_x", &[]);
             let mut r1 = make_result();
             let mut r2 = make_result();
             r1.latest_ledger = 1;
@@ -1132,5 +1690,262 @@ mod tests {
             assert_eq!(cache.get(&k1).await.unwrap().latest_ledger, 1);
             assert_eq!(cache.get(&k2).await.unwrap().latest_ledger, 2);
         }
+
+        #[tokio::test]
+        async fn test_purge_contract_removes_only_its_entries() {
+            let cache = SimulationCache::new();
+            cache
+                .set(key("CONTRACT_A", "fn_x", &[]), make_result())
+                .await;
+            cache
+                .set(key("CONTRACT_A", "fn_y", &[]), make_result())
+                .await;
+            cache
+                .set(key("CONTRACT_B", "fn_x", &[]), make_result())
+                .await;
+
+            assert_eq!(cache.purge_contract("CONTRACT_A").await, 2);
+            assert!(cache.get(&key("CONTRACT_A", "fn_x", &[])).await.is_none());
+            assert!(cache.get(&key("CONTRACT_B", "fn_x", &[])).await.is_some());
+        }
+
+        #[tokio::test]
+        async fn test_is_stale_without_footprint_is_false() {
+            let engine = SimulationEngine::new("http://127.0.0.1:9".to_string());
+            assert!(!engine.is_stale(&make_result()).await.unwrap());
+        }
+
+        #[tokio::test]
+        async fn test_is_stale_detects_modified_deleted_and_archived_entries() {
+            let rpc = MockRpc::start().await;
+            let engine = SimulationEngine::new(rpc.url());
+            let mut result = make_result();
+            result.footprint = Some(Footprint {
+                read_only: vec!["a".to_string()],
+                read_write: vec!["b".to_string()],
+            });
+            let entries = |b_modified: u64, b_live_until: u64, include_b: bool| {
+                let mut entries = vec![serde_json::json!({
+                    "key": "a", "xdr": "", "lastModifiedLedgerSeq": 40, "liveUntilLedgerSeq": 500
+                })];
+                if include_b {
+                    entries.push(serde_json::json!({
+                        "key": "b", "xdr": "",
+                        "lastModifiedLedgerSeq": b_modified,
+                        "liveUntilLedgerSeq": b_live_until
+                    }));
+                }
+                MockReply::result(serde_json::json!({ "entries": entries, "latestLedger": 50 }))
+            };
+
+            rpc.enqueue("getLedgerEntries", entries(41, 500, true));
+            assert!(!engine.is_stale(&result).await.unwrap());
+            rpc.enqueue("getLedgerEntries", entries(43, 500, true));
+            assert!(engine.is_stale(&result).await.unwrap());
+            rpc.enqueue("getLedgerEntries", entries(41, 500, false));
+            assert!(engine.is_stale(&result).await.unwrap());
+            rpc.enqueue("getLedgerEntries", entries(41, 49, true));
+            assert!(engine.is_stale(&result).await.unwrap());
+        }
+
+        #[tokio::test]
+        async fn test_stats_remove_and_clear() {
+            let cache = SimulationCache::new();
+            cache
+                .set(key("CONTRACT_A", "fn_x", &[]), make_result())
+                .await;
+            cache
+                .set(key("CONTRACT_B", "fn_x", &[]), make_result())
+                .await;
+            assert!(cache.get(&key("CONTRACT_A", "fn_x", &[])).await.is_some());
+            assert!(cache.get("missing").await.is_none());
+
+            let stats = cache.stats().await;
+            assert_eq!(stats.backend, "memory");
+            assert_eq!((stats.hits, stats.misses), (1, 1));
+            assert_eq!(stats.hit_rate_pct, 50.0);
+            assert_eq!(stats.entry_count, 2);
+            assert!(stats.weighted_size_bytes > 0);
+
+            assert!(cache.remove(&key("CONTRACT_A", "fn_x", &[])).await);
+            assert!(!cache.remove(&key("CONTRACT_A", "fn_x", &[])).await);
+            assert_eq!(cache.clear().await, 1);
+            assert_eq!(cache.stats().await.entry_count, 0);
+        }
+    }
+
+    // ── Failover tests ────────────────────────────────────────────────────────
+
+    mod failover_tests {
+        use super::*;
+        use crate::circuit_breaker::{BreakerConfig, CircuitState};
+        use crate::rpc_provider::{ProviderRegistry, RegistryConfig};
+        use std::time::Duration;
+
+        #[tokio::test]
+        async fn test_failover_skips_server_error() {
+            let broken = MockRpc::start().await;
+            broken.on("simulateTransaction", MockReply::status(503));
+            let healthy = MockRpc::start().await;
+            let registry = ProviderRegistry::new(vec![
+                RpcProvider::new("broken", broken.url()),
+                RpcProvider::new("healthy", healthy.url()),
+            ]);
+            let engine = SimulationEngine::with_registry(registry);
+
+            let result = engine.simulate_transaction("AAAA").await.unwrap();
+            assert_eq!(result.provider.as_deref(), Some("healthy"));
+            assert_eq!(result.ledger_lag, Some(0));
+            assert_eq!(broken.calls("simulateTransaction"), 1);
+        }
+
+        #[tokio::test]
+        async fn test_failover_does_not_retry_client_error() {
+            let rejecting = MockRpc::start().await;
+            rejecting.on("simulateTransaction", MockReply::status(400));
+            let backup = MockRpc::start().await;
+            let registry = ProviderRegistry::new(vec![
+                RpcProvider::new("rejecting", rejecting.url()),
+                RpcProvider::new("backup", backup.url()),
+            ]);
+            let engine = SimulationEngine::with_registry(registry);
+
+            assert!(engine.simulate_transaction("AAAA").await.is_err());
+            assert_eq!(backup.calls("simulateTransaction"), 0);
+        }
+
+        #[tokio::test]
+        async fn test_failover_recovers_after_transient_error() {
+            let rpc = MockRpc::start().await;
+            rpc.enqueue("simulateTransaction", MockReply::status(503));
+            let registry = ProviderRegistry::new(vec![RpcProvider::new("only", rpc.url())]);
+            let engine = SimulationEngine::with_registry(registry);
+
+            assert!(engine.simulate_transaction("AAAA").await.is_err());
+            let result = engine.simulate_transaction("AAAA").await.unwrap();
+            assert_eq!(result.provider.as_deref(), Some("only"));
+            assert_eq!(rpc.calls("simulateTransaction"), 2);
+        }
+
+        #[tokio::test]
+        async fn test_lagging_provider_reports_lag() {
+            let ahead = MockRpc::start().await;
+            ahead.on("simulateTransaction", MockReply::simulation(120, 1, 1));
+            let behind = MockRpc::start().await;
+            behind.on("simulateTransaction", MockReply::simulation(100, 1, 1));
+            let registry = ProviderRegistry::new(vec![
+                RpcProvider::new("ahead", ahead.url()),
+                RpcProvider::new("behind", behind.url()),
+            ]);
+            registry.record_ledger(&ahead.url(), 120);
+            let engine = SimulationEngine::with_registry(Arc::clone(&registry));
+
+            // The lagging provider is tried last, so fail the leader once.
+            ahead.enqueue("simulateTransaction", MockReply::status(502));
+            let result = engine.simulate_transaction("AAAA").await.unwrap();
+            assert_eq!(result.provider.as_deref(), Some("behind"));
+            assert_eq!(result.ledger_lag, Some(20));
+        }
+
+        #[tokio::test]
+        async fn test_hedged_request_wins_over_slow_provider() {
+            let slow = MockRpc::start().await;
+            slow.on(
+                "simulateTransaction",
+                MockReply::simulation(10, 1_000, 2_000).delayed(Duration::from_secs(5)),
+            );
+            let fast = MockRpc::start().await;
+            let registry = ProviderRegistry::new(vec![
+                RpcProvider::new("slow", slow.url()),
+                RpcProvider::new("fast", fast.url()),
+            ]);
+            let engine = SimulationEngine::with_registry(Arc::clone(&registry))
+                .with_hedging(Duration::from_millis(50));
+
+            let started = std::time::Instant::now();
+            let result = engine.simulate_transaction("AAAA").await.unwrap();
+            assert!(started.elapsed() < Duration::from_secs(2));
+            assert_eq!(result.provider.as_deref(), Some("fast"));
+            assert_eq!(registry.hedge_wins(&fast.url()), 1);
+        }
+
+        #[tokio::test]
+        async fn test_cancelled_hedge_releases_trial_slot() {
+            let primary = MockRpc::start().await;
+            primary.on(
+                "simulateTransaction",
+                MockReply::simulation(10, 1_000, 2_000).delayed(Duration::from_millis(200)),
+            );
+            let backup = MockRpc::start().await;
+            backup.on(
+                "simulateTransaction",
+                MockReply::simulation(10, 1_000, 2_000).delayed(Duration::from_secs(5)),
+            );
+            let registry = ProviderRegistry::with_config(
+                vec![
+                    RpcProvider::new("primary", primary.url()),
+                    RpcProvider::new("backup", backup.url()),
+                ],
+                RegistryConfig {
+                    breaker: BreakerConfig {
+                        cooldown: Duration::from_millis(10),
+                        ..BreakerConfig::default()
+                    },
+                    ..RegistryConfig::default()
+                },
+            );
+            registry.trip("backup").unwrap();
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            let engine = SimulationEngine::with_registry(Arc::clone(&registry))
+                .with_hedging(Duration::from_millis(50));
+
+            // The backup takes its only trial slot, then loses the race.
+            let result = engine.simulate_transaction("AAAA").await.unwrap();
+            assert_eq!(result.provider.as_deref(), Some("primary"));
+            assert_eq!(backup.calls("simulateTransaction"), 1);
+
+            assert_eq!(
+                registry.breaker_state(&backup.url()),
+                Some(CircuitState::HalfOpen)
+            );
+            assert!(registry.try_acquire(&backup.url()).is_some());
+        }
+
+        #[tokio::test]
+        async fn test_no_hedge_when_primary_answers_in_time() {
+            let primary = MockRpc::start().await;
+            let backup = MockRpc::start().await;
+            let registry = ProviderRegistry::new(vec![
+                RpcProvider::new("primary", primary.url()),
+                RpcProvider::new("backup", backup.url()),
+            ]);
+            let engine = SimulationEngine::with_registry(Arc::clone(&registry))
+                .with_hedging(Duration::from_secs(2));
+
+            let result = engine.simulate_transaction("AAAA").await.unwrap();
+            assert_eq!(result.provider.as_deref(), Some("primary"));
+            assert_eq!(registry.hedge_wins(&backup.url()), 0);
+            assert_eq!(backup.calls("simulateTransaction"), 0);
+        }
+
+        #[tokio::test]
+        async fn test_hedge_waits_for_other_provider_after_failure() {
+            let slow = MockRpc::start().await;
+            slow.on(
+                "simulateTransaction",
+                MockReply::simulation(10, 1_000, 2_000).delayed(Duration::from_millis(300)),
+            );
+            let failing = MockRpc::start().await;
+            failing.on("simulateTransaction", MockReply::status(503));
+            let registry = ProviderRegistry::new(vec![
+                RpcProvider::new("slow", slow.url()),
+                RpcProvider::new("failing", failing.url()),
+            ]);
+            let engine =
+                SimulationEngine::with_registry(registry).with_hedging(Duration::from_millis(50));
+
+            let result = engine.simulate_transaction("AAAA").await.unwrap();
+            assert_eq!(result.provider.as_deref(), Some("slow"));
+        }
     }
 }
@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+
+/// Deduplicates concurrent calls that share a key.
+///
+/// The first caller for a key runs the work; callers arriving while it is in
+/// flight wait for and receive a clone of the same result. Once the call
+/// completes the key is released, so later calls run afresh. If the running
+/// caller is cancelled, one of the waiters takes over; if nobody is left
+/// waiting, the key is released.
+pub struct SingleFlight<V> {
+    inflight: Mutex<HashMap<String, Arc<OnceCell<V>>>>,
+    executed: AtomicU64,
+    coalesced: AtomicU64,
+}
+
+impl<V: Clone> Default for SingleFlight<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V: Clone> SingleFlight<V> {
+    pub fn new() -> Self {
+        Self {
+            inflight: Mutex::new(HashMap::new()),
+            executed: AtomicU64::new(0),
+            coalesced: AtomicU64::new(0),
+        }
+    }
+
+    /// Run `work` for `key`, or join the call already in flight.
+    ///
+    /// Returns the value and whether it was shared from another caller.
+    pub async fn run<F, Fut>(&self, key: &str, work: F) -> (V, bool)
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = V>,
+    {
+        let cell = {
+            let mut inflight = self.inflight.lock().unwrap_or_else(|e| e.into_inner());
+            Arc::clone(
+                inflight
+                    .entry(key.to_string())
+                    .or_insert_with(|| Arc::new(OnceCell::new())),
+            )
+        };
+        // Released on return and on cancellation alike.
+        let participant = Participant {
+            flight: self,
+            key,
+            cell,
+        };
+
+        let mut ran = false;
+        let value = participant
+            .cell
+            .get_or_init(|| {
+                ran = true;
+                work()
+            })
+            .await
+            .clone();
+        drop(participant);
+
+        if ran {
+            self.executed.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.coalesced.fetch_add(1, Ordering::Relaxed);
+            tracing::debug!(key = %key, "Coalesced with in-flight call");
+        }
+        (value, !ran)
+    }
+
+    /// Calls that did the work
+    pub fn executed(&self) -> u64 {
+        self.executed.load(Ordering::Relaxed)
+    }
+
+    /// Calls served from another caller's in-flight work
+    pub fn coalesced(&self) -> u64 {
+        self.coalesced.load(Ordering::Relaxed)
+    }
+}
+
+/// One caller's hold on a key's call. The key is released by the first
+/// caller out once the call completed, or by the last one out if it never did.
+struct Participant<'a, V> {
+    flight: &'a SingleFlight<V>,
+    key: &'a str,
+    cell: Arc<OnceCell<V>>,
+}
+
+impl<V> Drop for Participant<'_, V> {
+    fn drop(&mut self) {
+        let mut inflight = self
+            .flight
+            .inflight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let current = inflight
+            .get(self.key)
+            .is_some_and(|c| Arc::ptr_eq(c, &self.cell));
+        // The map holds one reference and this caller the other.
+        if current && (self.cell.initialized() || Arc::strong_count(&self.cell) == 2) {
+            inflight.remove(self.key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_concurrent_calls_share_one_execution() {
+        let flight = Arc::new(SingleFlight::<u64>::new());
+        let calls = Arc::new(AtomicU64::new(0));
+
+        let handles: Vec<_> = (0..10)
+            .map(|_| {
+                let (flight, calls) = (Arc::clone(&flight), Arc::clone(&calls));
+                tokio::spawn(async move {
+                    flight
+                        .run("k", || async {
+                            calls.fetch_add(1, Ordering::SeqCst);
+                            tokio::time::sleep(Duration::from_millis(50)).await;
+                            42
+                        })
+                        .await
+                })
+            })
+            .collect();
+
+        let mut shared = 0;
+        for handle in handles {
+            let (value, coalesced) = handle.await.unwrap();
+            assert_eq!(value, 42);
+            shared += coalesced as u64;
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(shared, 9);
+        assert_eq!((flight.executed(), flight.coalesced()), (1, 9));
+    }
+
+    #[tokio::test]
+    async fn test_sequential_calls_and_distinct_keys_run_separately() {
+        let flight = SingleFlight::<u64>::new();
+        assert_eq!(flight.run("a", || async { 1 }).await, (1, false));
+        assert_eq!(flight.run("a", || async { 2 }).await, (2, false));
+        assert_eq!(flight.run("b", || async { 3 }).await, (3, false));
+        assert_eq!(flight.coalesced(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_waiter_takes_over_when_leader_is_cancelled() {
+        let flight = Arc::new(SingleFlight::<u64>::new());
+
+        let leader = {
+            let flight = Arc::clone(&flight);
+            tokio::spawn(async move {
+                flight
+                    .run("k", || async {
+                        tokio::time::sleep(Duration::from_secs(60)).await;
+                        1
+                    })
+                    .await
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        leader.abort();
+        let _ = leader.await;
+
+        let (value, coalesced) = flight.run("k", || async { 2 }).await;
+        assert_eq!(value, 2);
+        assert!(!coalesced);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_leader_without_waiters_releases_key() {
+        let flight = Arc::new(SingleFlight::<u64>::new());
+
+        let leader = {
+            let flight = Arc::clone(&flight);
+            tokio::spawn(async move {
+                flight
+                    .run("k", || async {
+                        tokio::time::sleep(Duration::from_secs(60)).await;
+                        1
+                    })
+                    .await
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(flight.inflight.lock().unwrap().len(), 1);
+        leader.abort();
+        let _ = leader.await;
+
+        assert!(flight.inflight.lock().unwrap().is_empty());
+    }
+}
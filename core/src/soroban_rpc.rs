@@ -0,0 +1,501 @@
+use crate::rpc_provider::ProviderRegistry;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Errors returned by Soroban RPC calls
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum RpcError {
+    /// No provider produced a response (unavailable, timeouts, HTTP errors)
+    #[error("{0}")]
+    Transport(String),
+
+    /// The node answered with a JSON-RPC `error` object
+    #[error("RPC error {code}: {message}")]
+    Rpc { code: i64, message: String },
+
+    /// The `result` member did not have the expected shape
+    #[error("Unexpected {method} response: {message}")]
+    Decode { method: String, message: String },
+}
+
+// ── Responses ─────────────────────────────────────────────────────────────────
+
+/// `getLedgerEntries` result
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LedgerEntriesResponse {
+    /// Entries that exist; missing keys are omitted
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub entries: Vec<LedgerEntryResult>,
+    pub latest_ledger: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LedgerEntryResult {
+    /// Base64 `LedgerKey` XDR
+    pub key: String,
+    /// Base64 `LedgerEntryData` XDR
+    pub xdr: String,
+    pub last_modified_ledger_seq: u64,
+    /// Only set for entries with a TTL (contract data and code)
+    #[serde(default)]
+    pub live_until_ledger_seq: Option<u64>,
+}
+
+/// `getNetwork` result
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkResponse {
+    #[serde(default)]
+    pub friendbot_url: Option<String>,
+    pub passphrase: String,
+    pub protocol_version: u32,
+}
+
+/// `getHealth` result
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthResponse {
+    pub status: String,
+    #[serde(default)]
+    pub latest_ledger: u64,
+    #[serde(default)]
+    pub oldest_ledger: u64,
+    #[serde(default)]
+    pub ledger_retention_window: u64,
+}
+
+/// `getLatestLedger` result
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LatestLedgerResponse {
+    pub id: String,
+    pub protocol_version: u32,
+    pub sequence: u64,
+}
+
+/// `getVersionInfo` result
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionInfoResponse {
+    pub version: String,
+    #[serde(default)]
+    pub commit_hash: String,
+    #[serde(default)]
+    pub build_timestamp: String,
+    #[serde(default)]
+    pub captive_core_version: String,
+    pub protocol_version: u32,
+}
+
+/// `getFeeStats` result
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeStatsResponse {
+    pub soroban_inclusion_fee: FeeDistribution,
+    pub inclusion_fee: FeeDistribution,
+    pub latest_ledger: u64,
+}
+
+/// Inclusion fee percentiles, in stroops, over recent ledgers
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeDistribution {
+    #[serde(deserialize_with = "u64_from_string_or_number")]
+    pub max: u64,
+    #[serde(deserialize_with = "u64_from_string_or_number")]
+    pub min: u64,
+    #[serde(deserialize_with = "u64_from_string_or_number")]
+    pub mode: u64,
+    #[serde(deserialize_with = "u64_from_string_or_number")]
+    pub p10: u64,
+    #[serde(deserialize_with = "u64_from_string_or_number")]
+    pub p50: u64,
+    #[serde(deserialize_with = "u64_from_string_or_number")]
+    pub p90: u64,
+    #[serde(deserialize_with = "u64_from_string_or_number")]
+    pub p99: u64,
+    #[serde(deserialize_with = "u64_from_string_or_number")]
+    pub transaction_count: u64,
+    pub ledger_count: u64,
+}
+
+/// `getTransaction` result
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionResponse {
+    /// `SUCCESS`, `FAILED` or `NOT_FOUND`
+    pub status: String,
+    pub latest_ledger: u64,
+    #[serde(default)]
+    pub oldest_ledger: u64,
+    /// Ledger the transaction was included in
+    #[serde(default)]
+    pub ledger: Option<u64>,
+    #[serde(default)]
+    pub application_order: Option<u32>,
+    #[serde(default)]
+    pub envelope_xdr: Option<String>,
+    #[serde(default)]
+    pub result_xdr: Option<String>,
+    #[serde(default)]
+    pub result_meta_xdr: Option<String>,
+}
+
+/// `sendTransaction` result
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SendTransactionResponse {
+    /// `PENDING`, `DUPLICATE`, `TRY_AGAIN_LATER` or `ERROR`
+    pub status: String,
+    pub hash: String,
+    pub latest_ledger: u64,
+    #[serde(default)]
+    pub error_result_xdr: Option<String>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub diagnostic_events_xdr: Vec<String>,
+}
+
+// ── getEvents ─────────────────────────────────────────────────────────────────
+
+/// `getEvents` parameters
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventsRequest {
+    /// First ledger to search; omit when paginating with a cursor
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_ledger: Option<u64>,
+    pub filters: Vec<EventFilter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pagination: Option<Pagination>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventFilter {
+    /// `contract`, `system` or `diagnostic`
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub event_type: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub contract_ids: Vec<String>,
+    /// Topic filters: base64 `ScVal` segments, `*` matches any one segment
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub topics: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Pagination {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+}
+
+/// `getEvents` result
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EventsResponse {
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub events: Vec<EventInfo>,
+    pub latest_ledger: u64,
+    /// Pass back in [`Pagination::cursor`] to continue
+    #[serde(default)]
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EventInfo {
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub ledger: u64,
+    #[serde(default)]
+    pub ledger_closed_at: String,
+    #[serde(default)]
+    pub contract_id: String,
+    pub id: String,
+    /// Base64 `ScVal` topics
+    #[serde(default)]
+    pub topic: Vec<String>,
+    /// Base64 `ScVal` value
+    pub value: String,
+    #[serde(default)]
+    pub in_successful_contract_call: bool,
+    #[serde(default)]
+    pub tx_hash: Option<String>,
+}
+
+// ── Client ────────────────────────────────────────────────────────────────────
+
+/// Typed Soroban RPC client. Every call goes through the provider
+/// registry, so it fails over, updates provider health and honours the
+/// selection strategy like simulations do.
+#[derive(Clone)]
+pub struct SorobanRpc {
+    registry: Arc<ProviderRegistry>,
+}
+
+impl SorobanRpc {
+    pub fn new(registry: Arc<ProviderRegistry>) -> Self {
+        Self { registry }
+    }
+
+    /// Send `method` with `params` and decode the `result` member.
+    pub async fn request<P: Serialize, R: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: P,
+    ) -> Result<R, RpcError> {
+        let params = serde_json::to_value(params).map_err(|e| RpcError::Decode {
+            method: method.to_string(),
+            message: format!("params: {e}"),
+        })?;
+        let result = self.registry.call(method, params).await?;
+        serde_json::from_value(result).map_err(|e| RpcError::Decode {
+            method: method.to_string(),
+            message: e.to_string(),
+        })
+    }
+
+    /// Fetch ledger entries by base64 `LedgerKey` XDR.
+    pub async fn get_ledger_entries<K: AsRef<str>>(
+        &self,
+        keys: &[K],
+    ) -> Result<LedgerEntriesResponse, RpcError> {
+        let keys: Vec<&str> = keys.iter().map(AsRef::as_ref).collect();
+        self.request("getLedgerEntries", serde_json::json!({ "keys": keys }))
+            .await
+    }
+
+    pub async fn get_network(&self) -> Result<NetworkResponse, RpcError> {
+        self.request("getNetwork", ()).await
+    }
+
+    pub async fn get_health(&self) -> Result<HealthResponse, RpcError> {
+        self.request("getHealth", ()).await
+    }
+
+    pub async fn get_latest_ledger(&self) -> Result<LatestLedgerResponse, RpcError> {
+        self.request("getLatestLedger", ()).await
+    }
+
+    pub async fn get_version_info(&self) -> Result<VersionInfoResponse, RpcError> {
+        self.request("getVersionInfo", ()).await
+    }
+
+    pub async fn get_fee_stats(&self) -> Result<FeeStatsResponse, RpcError> {
+        self.request("getFeeStats", ()).await
+    }
+
+    pub async fn get_events(&self, request: &EventsRequest) -> Result<EventsResponse, RpcError> {
+        self.request("getEvents", request).await
+    }
+
+    /// Look up a transaction by its hex hash.
+    pub async fn get_transaction(&self, hash: &str) -> Result<TransactionResponse, RpcError> {
+        self.request("getTransaction", serde_json::json!({ "hash": hash }))
+            .await
+    }
+
+    /// Submit a base64 `TransactionEnvelope`. A transport failure may resend
+    /// it to the next provider; the network reports repeats as `DUPLICATE`.
+    pub async fn send_transaction(
+        &self,
+        transaction_xdr: &str,
+    ) -> Result<SendTransactionResponse, RpcError> {
+        self.request(
+            "sendTransaction",
+            serde_json::json!({ "transaction": transaction_xdr }),
+        )
+        .await
+    }
+}
+
+// ── Serde helpers ─────────────────────────────────────────────────────────────
+
+/// Treat `null` like a missing field.
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// Soroban RPC sends some integers as JSON strings.
+fn u64_from_string_or_number<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Number(u64),
+        String(String),
+    }
+    match Raw::deserialize(deserializer)? {
+        Raw::Number(n) => Ok(n),
+        Raw::String(s) => s.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_rpc::{MockReply, MockRpc, MOCK_LEDGER};
+    use serde_json::json;
+
+    #[test]
+    fn test_decode_ledger_entries_with_null_entries() {
+        let response: LedgerEntriesResponse =
+            serde_json::from_value(json!({ "entries": null, "latestLedger": 12 })).unwrap();
+        assert!(response.entries.is_empty());
+
+        let response: LedgerEntriesResponse = serde_json::from_value(json!({
+            "entries": [{
+                "key": "AAAA",
+                "xdr": "BBBB",
+                "lastModifiedLedgerSeq": 10,
+                "liveUntilLedgerSeq": 500
+            }],
+            "latestLedger": 12
+        }))
+        .unwrap();
+        assert_eq!(response.entries[0].last_modified_ledger_seq, 10);
+        assert_eq!(response.entries[0].live_until_ledger_seq, Some(500));
+    }
+
+    #[test]
+    fn test_decode_fee_stats_with_string_numbers() {
+        let distribution = json!({
+            "max": "210", "min": "100", "mode": "100", "p10": "100", "p20": "100",
+            "p30": "100", "p40": "100", "p50": "100", "p60": "100", "p70": "100",
+            "p80": "100", "p90": "150", "p95": "200", "p99": "210",
+            "transactionCount": "10", "ledgerCount": 50
+        });
+        let response: FeeStatsResponse = serde_json::from_value(json!({
+            "sorobanInclusionFee": distribution,
+            "inclusionFee": distribution,
+            "latestLedger": 42
+        }))
+        .unwrap();
+        assert_eq!(response.soroban_inclusion_fee.p90, 150);
+        assert_eq!(response.inclusion_fee.transaction_count, 10);
+        assert_eq!(response.inclusion_fee.ledger_count, 50);
+    }
+
+    #[test]
+    fn test_decode_transaction_and_send_results() {
+        let missing: TransactionResponse = serde_json::from_value(json!({
+            "status": "NOT_FOUND",
+            "latestLedger": 100,
+            "oldestLedger": 1
+        }))
+        .unwrap();
+        assert_eq!(missing.status, "NOT_FOUND");
+        assert!(missing.ledger.is_none());
+
+        let sent: SendTransactionResponse = serde_json::from_value(json!({
+            "status": "PENDING",
+            "hash": "ab12",
+            "latestLedger": 100,
+            "latestLedgerCloseTime": "1700000000"
+        }))
+        .unwrap();
+        assert_eq!(sent.status, "PENDING");
+        assert!(sent.diagnostic_events_xdr.is_empty());
+    }
+
+    #[test]
+    fn test_events_request_serialization() {
+        let request = EventsRequest {
+            start_ledger: Some(1000),
+            filters: vec![EventFilter {
+                event_type: Some("contract".to_string()),
+                contract_ids: vec!["CABC".to_string()],
+                topics: vec![],
+            }],
+            pagination: Some(Pagination {
+                cursor: None,
+                limit: Some(10),
+            }),
+        };
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            json!({
+                "startLedger": 1000,
+                "filters": [{ "type": "contract", "contractIds": ["CABC"] }],
+                "pagination": { "limit": 10 }
+            })
+        );
+    }
+
+    #[test]
+    fn test_decode_network_health_and_version() {
+        let network: NetworkResponse = serde_json::from_value(json!({
+            "passphrase": "Test SDF Network ; September 2015",
+            "protocolVersion": 22
+        }))
+        .unwrap();
+        assert!(network.friendbot_url.is_none());
+
+        let health: HealthResponse =
+            serde_json::from_value(json!({ "status": "healthy", "latestLedger": 5 })).unwrap();
+        assert_eq!(health.status, "healthy");
+
+        let version: VersionInfoResponse = serde_json::from_value(json!({
+            "version": "22.1.0",
+            "commitHash": "abc",
+            "protocolVersion": 22
+        }))
+        .unwrap();
+        assert_eq!(version.protocol_version, 22);
+    }
+
+    // ── Against a mock RPC ────────────────────────────────────────────────
+
+    fn client(url: String) -> SorobanRpc {
+        SorobanRpc::new(ProviderRegistry::new(vec![
+            crate::rpc_provider::RpcProvider::new("mock", url),
+        ]))
+    }
+
+    #[tokio::test]
+    async fn test_typed_requests_round_trip() {
+        let rpc = MockRpc::start().await;
+        rpc.on(
+            "getLedgerEntries",
+            MockReply::result(json!({ "entries": null, "latestLedger": 77 })),
+        );
+        let client = client(rpc.url());
+
+        assert_eq!(
+            client.get_latest_ledger().await.unwrap().sequence,
+            MOCK_LEDGER
+        );
+        assert_eq!(client.get_network().await.unwrap().protocol_version, 22);
+
+        let entries = client.get_ledger_entries(&["AAAA"]).await.unwrap();
+        assert_eq!(entries.latest_ledger, 77);
+        assert_eq!(
+            rpc.last_params("getLedgerEntries"),
+            Some(json!({ "keys": ["AAAA"] }))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_typed_request_errors() {
+        let rpc = MockRpc::start().await;
+        rpc.on("getHealth", MockReply::error(-32601, "method not found"));
+        rpc.on("getVersionInfo", MockReply::result(json!({ "version": 1 })));
+        let client = client(rpc.url());
+
+        let err = client.get_health().await.unwrap_err();
+        assert!(matches!(err, RpcError::Rpc { code: -32601, .. }));
+        let err = client.get_version_info().await.unwrap_err();
+        assert!(matches!(err, RpcError::Decode { .. }));
+    }
+}
@@ -0,0 +1,828 @@
+use crate::limits::{NetworkLimits, BUNDLED_TX_MAX_SIZE_BYTES};
+use crate::parser::{ArgParser, ParserError};
+use serde::Serialize;
+use soroban_sdk::xdr::{ScBytes, ScSymbol, ScVal, ScVec, VecM};
+use std::fmt::Write as _;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Placeholder in an argument template that is replaced by the swept value.
+pub const SWEEP_PLACEHOLDER: &str = "$n";
+
+/// Largest `n` accepted for `vec_u32` and `bytes`. An argument of that size
+/// can't fit in a transaction, so larger inputs are rejected before allocating.
+pub const MAX_SWEEP_INPUT_SIZE: u64 = BUNDLED_TX_MAX_SIZE_BYTES;
+
+/// Most input sizes a single sweep may measure; each one is a full sandbox run.
+pub const MAX_SWEEP_POINTS: u64 = 1_000;
+
+/// Upper bound used when extrapolating a fitted model towards a limit.
+const MAX_EXTRAPOLATED_N: u64 = 1 << 40;
+
+/// Below this coefficient of variation a series is considered constant.
+const CONSTANT_CV_THRESHOLD: f64 = 0.01;
+
+/// A more complex model must beat a simpler one by at least this much R².
+const R_SQUARED_TOLERANCE: f64 = 1e-3;
+
+/// Errors that can occur while preparing or running a sweep
+#[derive(Error, Debug)]
+pub enum SweepError {
+    #[error("Invalid range: {0}")]
+    InvalidRange(String),
+
+    #[error("Unknown sweep variable: {0} (expected u32, vec_u32 or bytes)")]
+    UnknownVariable(String),
+
+    #[error("Argument template must contain exactly one `{SWEEP_PLACEHOLDER}` placeholder")]
+    InvalidTemplate,
+
+    #[error("Invalid function name: {0}")]
+    InvalidFunction(String),
+
+    #[error("Parse error: {0}")]
+    ParseError(#[from] ParserError),
+
+    #[error("Value conversion failed: {0}")]
+    Conversion(String),
+}
+
+// ── Sweep specification ───────────────────────────────────────────────────────
+
+/// How the swept value `n` is turned into a contract argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SweepVariable {
+    /// `n` itself as a `u32` (e.g. `count_primes(limit)`).
+    U32,
+    /// A `Vec<u32>` of length `n` in descending order — the worst case for sorts.
+    VecU32,
+    /// `Bytes` of length `n`.
+    Bytes,
+}
+
+impl FromStr for SweepVariable {
+    type Err = SweepError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "u32" => Ok(Self::U32),
+            "vec_u32" => Ok(Self::VecU32),
+            "bytes" => Ok(Self::Bytes),
+            other => Err(SweepError::UnknownVariable(other.to_string())),
+        }
+    }
+}
+
+impl SweepVariable {
+    /// Build the argument value for input size `n`.
+    pub fn to_sc_val(self, n: u64) -> Result<ScVal, SweepError> {
+        self.check_size(n)?;
+        match self {
+            Self::U32 => u32::try_from(n)
+                .map(ScVal::U32)
+                .map_err(|_| SweepError::Conversion(format!("{n} does not fit in u32"))),
+            Self::VecU32 => {
+                let len = u32::try_from(n)
+                    .map_err(|_| SweepError::Conversion(format!("{n} elements is too many")))?;
+                let items: VecM<ScVal> = (0..len)
+                    .rev()
+                    .map(ScVal::U32)
+                    .collect::<Vec<_>>()
+                    .try_into()
+                    .map_err(|_| SweepError::Conversion(format!("{n} elements is too many")))?;
+                Ok(ScVal::Vec(Some(ScVec(items))))
+            }
+            Self::Bytes => {
+                let bytes = vec![0xAB; n as usize]
+                    .try_into()
+                    .map_err(|_| SweepError::Conversion(format!("{n} bytes is too many")))?;
+                Ok(ScVal::Bytes(ScBytes(bytes)))
+            }
+        }
+    }
+
+    /// Reject sizes whose argument would be larger than [`MAX_SWEEP_INPUT_SIZE`].
+    fn check_size(self, n: u64) -> Result<(), SweepError> {
+        match self {
+            Self::VecU32 | Self::Bytes if n > MAX_SWEEP_INPUT_SIZE => {
+                Err(SweepError::InvalidRange(format!(
+                    "{n} exceeds the maximum input size of {MAX_SWEEP_INPUT_SIZE}"
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Inclusive range of input sizes, e.g. `0..300:10` or `0..64KB:4KB`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SweepRange {
+    pub start: u64,
+    pub end: u64,
+    pub step: u64,
+}
+
+impl SweepRange {
+    pub fn values(&self) -> impl Iterator<Item = u64> {
+        (self.start..=self.end).step_by(self.step as usize)
+    }
+
+    /// Number of input sizes in the range.
+    pub fn point_count(&self) -> u64 {
+        (self.end - self.start) / self.step + 1
+    }
+}
+
+impl FromStr for SweepRange {
+    type Err = SweepError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (bounds, step) = match s.split_once(':') {
+            Some((bounds, step)) => (bounds, parse_size(step)?),
+            None => (s, 1),
+        };
+        let (start, end) = bounds
+            .split_once("..")
+            .ok_or_else(|| SweepError::InvalidRange(format!("expected start..end, got {s}")))?;
+        let range = Self {
+            start: parse_size(start)?,
+            end: parse_size(end)?,
+            step,
+        };
+        if range.step == 0 {
+            return Err(SweepError::InvalidRange("step must be positive".into()));
+        }
+        if range.start > range.end {
+            return Err(SweepError::InvalidRange("start must not exceed end".into()));
+        }
+        Ok(range)
+    }
+}
+
+/// Parse an integer with an optional `KB`/`MB` (binary) suffix.
+fn parse_size(s: &str) -> Result<u64, SweepError> {
+    let s = s.trim();
+    let upper = s.to_ascii_uppercase();
+    let (digits, multiplier) = if let Some(d) = upper.strip_suffix("KB") {
+        (d, 1024)
+    } else if let Some(d) = upper.strip_suffix("MB") {
+        (d, 1024 * 1024)
+    } else {
+        (upper.as_str(), 1)
+    };
+    digits
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(|v| v.checked_mul(multiplier))
+        .ok_or_else(|| SweepError::InvalidRange(format!("invalid size: {s}")))
+}
+
+/// A function call whose argument template contains one `$n` placeholder.
+#[derive(Debug, Clone)]
+pub struct SweepSpec {
+    pub function: String,
+    pub args: Vec<String>,
+    pub variable: SweepVariable,
+    pub range: SweepRange,
+}
+
+impl SweepSpec {
+    /// Expand the argument template for input size `n`.
+    pub fn build_args(&self, n: u64) -> Result<Vec<ScVal>, SweepError> {
+        self.args
+            .iter()
+            .map(|arg| {
+                if arg.trim() == SWEEP_PLACEHOLDER {
+                    self.variable.to_sc_val(n)
+                } else {
+                    Ok(ArgParser::parse_arg(arg)?)
+                }
+            })
+            .collect()
+    }
+
+    fn validate(&self) -> Result<(), SweepError> {
+        let placeholders = self
+            .args
+            .iter()
+            .filter(|a| a.trim() == SWEEP_PLACEHOLDER)
+            .count();
+        if placeholders != 1 {
+            return Err(SweepError::InvalidTemplate);
+        }
+        let _: ScSymbol = self
+            .function
+            .as_str()
+            .try_into()
+            .map_err(|_| SweepError::InvalidFunction(self.function.clone()))?;
+        if self.range.point_count() > MAX_SWEEP_POINTS {
+            return Err(SweepError::InvalidRange(format!(
+                "{} points exceeds the maximum of {MAX_SWEEP_POINTS}",
+                self.range.point_count()
+            )));
+        }
+        self.variable.check_size(self.range.end)
+    }
+}
+
+// ── Measurements ──────────────────────────────────────────────────────────────
+
+/// Resources metered for a single input size.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SweepPoint {
+    pub n: u64,
+    pub cpu_instructions: u64,
+    pub ram_bytes: u64,
+    pub read_entries: u64,
+    pub write_entries: u64,
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+    pub events_size_bytes: u64,
+    /// Set when the invocation failed; such points are excluded from fitting.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A per-transaction resource that can be swept and compared to a limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    CpuInstructions,
+    RamBytes,
+    ReadEntries,
+    WriteEntries,
+    ReadBytes,
+    WriteBytes,
+    EventsSizeBytes,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 7] = [
+        Metric::CpuInstructions,
+        Metric::RamBytes,
+        Metric::ReadEntries,
+        Metric::WriteEntries,
+        Metric::ReadBytes,
+        Metric::WriteBytes,
+        Metric::EventsSizeBytes,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::CpuInstructions => "cpu_instructions",
+            Self::RamBytes => "ram_bytes",
+            Self::ReadEntries => "read_entries",
+            Self::WriteEntries => "write_entries",
+            Self::ReadBytes => "read_bytes",
+            Self::WriteBytes => "write_bytes",
+            Self::EventsSizeBytes => "events_size_bytes",
+        }
+    }
+
+    pub fn value(self, point: &SweepPoint) -> u64 {
+        match self {
+            Self::CpuInstructions => point.cpu_instructions,
+            Self::RamBytes => point.ram_bytes,
+            Self::ReadEntries => point.read_entries,
+            Self::WriteEntries => point.write_entries,
+            Self::ReadBytes => point.read_bytes,
+            Self::WriteBytes => point.write_bytes,
+            Self::EventsSizeBytes => point.events_size_bytes,
+        }
+    }
+
+    pub fn limit(self, limits: &NetworkLimits) -> u64 {
+        match self {
+            Self::CpuInstructions => limits.tx_max_instructions,
+            Self::RamBytes => limits.tx_memory_limit,
+            Self::ReadEntries => limits.tx_max_read_ledger_entries,
+            Self::WriteEntries => limits.tx_max_write_ledger_entries,
+            Self::ReadBytes => limits.tx_max_read_bytes,
+            Self::WriteBytes => limits.tx_max_write_bytes,
+            Self::EventsSizeBytes => limits.tx_max_contract_events_size_bytes,
+        }
+    }
+}
+
+/// Invoke `function` once locally and flatten its resources into a point.
+fn measure_point(wasm: &[u8], function: &str, args: Vec<ScVal>) -> Result<SweepPoint, String> {
+    // Unlimited, so points past the network limits are still measured.
+    let res = crate::sandbox::invoke(wasm, function, &args, None)?.resources;
+    Ok(SweepPoint {
+        n: 0,
+        cpu_instructions: res.cpu_instructions,
+        ram_bytes: res.ram_bytes,
+        read_entries: res.read_entries,
+        write_entries: res.write_entries,
+        read_bytes: res.ledger_read_bytes,
+        write_bytes: res.ledger_write_bytes,
+        events_size_bytes: res.events_size_bytes,
+        error: None,
+    })
+}
+
+// ── Complexity fitting ────────────────────────────────────────────────────────
+
+/// Growth models considered when fitting a series, simplest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComplexityModel {
+    Constant,
+    Linear,
+    NLogN,
+    Quadratic,
+}
+
+impl ComplexityModel {
+    fn basis(self, n: f64) -> f64 {
+        match self {
+            Self::Constant => 0.0,
+            Self::Linear => n,
+            Self::NLogN if n > 1.0 => n * n.log2(),
+            Self::NLogN => 0.0,
+            Self::Quadratic => n * n,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Constant => "O(1)",
+            Self::Linear => "O(n)",
+            Self::NLogN => "O(n log n)",
+            Self::Quadratic => "O(n^2)",
+        }
+    }
+}
+
+/// `y = intercept + slope * f(n)` for the chosen model's basis `f`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ModelFit {
+    pub model: ComplexityModel,
+    pub intercept: f64,
+    pub slope: f64,
+    pub r_squared: f64,
+}
+
+impl ModelFit {
+    pub fn predict(&self, n: f64) -> f64 {
+        self.intercept + self.slope * self.model.basis(n)
+    }
+}
+
+/// Least-squares fit of `samples` against a single model.
+fn fit_model(model: ComplexityModel, samples: &[(f64, f64)]) -> ModelFit {
+    let count = samples.len() as f64;
+    let mean_y = samples.iter().map(|(_, y)| y).sum::<f64>() / count;
+    let ss_tot: f64 = samples.iter().map(|(_, y)| (y - mean_y).powi(2)).sum();
+
+    let (intercept, slope) = if model == ComplexityModel::Constant {
+        (mean_y, 0.0)
+    } else {
+        let xs: Vec<f64> = samples.iter().map(|(n, _)| model.basis(*n)).collect();
+        let mean_x = xs.iter().sum::<f64>() / count;
+        let ss_xx: f64 = xs.iter().map(|x| (x - mean_x).powi(2)).sum();
+        let ss_xy: f64 = xs
+            .iter()
+            .zip(samples)
+            .map(|(x, (_, y))| (x - mean_x) * (y - mean_y))
+            .sum();
+        let slope = if ss_xx > 0.0 { ss_xy / ss_xx } else { 0.0 };
+        (mean_y - slope * mean_x, slope)
+    };
+
+    let fit = ModelFit {
+        model,
+        intercept,
+        slope,
+        r_squared: 0.0,
+    };
+    let ss_res: f64 = samples
+        .iter()
+        .map(|(n, y)| (y - fit.predict(*n)).powi(2))
+        .sum();
+    let r_squared = if ss_tot > 0.0 {
+        1.0 - ss_res / ss_tot
+    } else {
+        1.0
+    };
+    ModelFit { r_squared, ..fit }
+}
+
+/// Pick the simplest model that explains `samples` best.
+///
+/// Flat series are reported as constant. Otherwise the model with the highest
+/// R² wins, unless a simpler one is within [`R_SQUARED_TOLERANCE`] of it.
+/// Models with a non-positive slope are ignored.
+pub fn fit_series(samples: &[(f64, f64)]) -> Option<ModelFit> {
+    if samples.is_empty() {
+        return None;
+    }
+
+    let constant = fit_model(ComplexityModel::Constant, samples);
+    let variance = samples
+        .iter()
+        .map(|(_, y)| (y - constant.intercept).powi(2))
+        .sum::<f64>()
+        / samples.len() as f64;
+    if samples.len() < 2 || variance.sqrt() <= CONSTANT_CV_THRESHOLD * constant.intercept.abs() {
+        return Some(constant);
+    }
+
+    let candidates: Vec<ModelFit> = [
+        ComplexityModel::Linear,
+        ComplexityModel::NLogN,
+        ComplexityModel::Quadratic,
+    ]
+    .into_iter()
+    .map(|m| fit_model(m, samples))
+    .filter(|f| f.slope > 0.0)
+    .collect();
+
+    let best_r2 = candidates
+        .iter()
+        .map(|f| f.r_squared)
+        .fold(f64::NEG_INFINITY, f64::max);
+
+    candidates
+        .into_iter()
+        .find(|f| f.r_squared >= best_r2 - R_SQUARED_TOLERANCE)
+        .or(Some(constant))
+}
+
+/// Smallest input size at which `metric` reaches `limit`.
+///
+/// Observed points take precedence; otherwise the fitted model is extrapolated
+/// beyond the last measured size.
+fn limit_crossing(
+    points: &[SweepPoint],
+    metric: Metric,
+    fit: &ModelFit,
+    limit: u64,
+) -> Option<(u64, bool)> {
+    if let Some(p) = points.iter().find(|p| metric.value(p) >= limit) {
+        return Some((p.n, true));
+    }
+    if fit.slope <= 0.0 {
+        return None;
+    }
+
+    let limit = limit as f64;
+    let mut lo = points.iter().map(|p| p.n).max().unwrap_or(0);
+    let mut hi = lo.max(1);
+    while fit.predict(hi as f64) < limit {
+        lo = hi;
+        hi = hi.checked_mul(2)?;
+        if hi > MAX_EXTRAPOLATED_N {
+            return None;
+        }
+    }
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        if fit.predict(mid as f64) >= limit {
+            hi = mid;
+        } else {
+            lo = mid;
+        }
+    }
+    Some((hi, false))
+}
+
+// ── Report ────────────────────────────────────────────────────────────────────
+
+/// Fitted growth of one metric and where it crosses the network limit.
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricAnalysis {
+    pub metric: Metric,
+    pub fit: ModelFit,
+    pub limit: u64,
+    /// Input size at which the per-transaction limit is reached, if ever.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exceeds_limit_at: Option<u64>,
+    /// `true` when `exceeds_limit_at` was measured rather than extrapolated.
+    pub observed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SweepReport {
+    pub function: String,
+    pub variable: SweepVariable,
+    pub range: SweepRange,
+    pub points: Vec<SweepPoint>,
+    pub analyses: Vec<MetricAnalysis>,
+}
+
+impl SweepReport {
+    /// Fit every non-zero metric of `points` and locate its limit crossing.
+    pub fn analyze(
+        spec: &SweepSpec,
+        points: Vec<SweepPoint>,
+        limits: &NetworkLimits,
+    ) -> SweepReport {
+        let ok: Vec<SweepPoint> = points
+            .iter()
+            .filter(|p| p.error.is_none())
+            .cloned()
+            .collect();
+
+        let analyses = Metric::ALL
+            .into_iter()
+            .filter(|m| ok.iter().any(|p| m.value(p) > 0))
+            .filter_map(|metric| {
+                let samples: Vec<(f64, f64)> = ok
+                    .iter()
+                    .map(|p| (p.n as f64, metric.value(p) as f64))
+                    .collect();
+                let fit = fit_series(&samples)?;
+                let limit = metric.limit(limits);
+                let crossing = limit_crossing(&ok, metric, &fit, limit);
+                Some(MetricAnalysis {
+                    metric,
+                    fit,
+                    limit,
+                    exceeds_limit_at: crossing.map(|(n, _)| n),
+                    observed: crossing.map(|(_, o)| o).unwrap_or(false),
+                })
+            })
+            .collect();
+
+        SweepReport {
+            function: spec.function.clone(),
+            variable: spec.variable,
+            range: spec.range,
+            points,
+            analyses,
+        }
+    }
+
+    /// Render the series and fits as a plain-text table for the terminal.
+    pub fn render_table(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "Sweep of `{}` over {:?} n = {}..{} step {}",
+            self.function, self.variable, self.range.start, self.range.end, self.range.step
+        );
+        let _ = writeln!(
+            out,
+            "{:>10} {:>14} {:>12} {:>6} {:>6} {:>10} {:>10} {:>8}",
+            "n", "cpu_insns", "mem_bytes", "r_ent", "w_ent", "r_bytes", "w_bytes", "events"
+        );
+        for p in &self.points {
+            if let Some(err) = &p.error {
+                let _ = writeln!(out, "{:>10} error: {}", p.n, err);
+                continue;
+            }
+            let _ = writeln!(
+                out,
+                "{:>10} {:>14} {:>12} {:>6} {:>6} {:>10} {:>10} {:>8}",
+                p.n,
+                p.cpu_instructions,
+                p.ram_bytes,
+                p.read_entries,
+                p.write_entries,
+                p.read_bytes,
+                p.write_bytes,
+                p.events_size_bytes
+            );
+        }
+        let _ = writeln!(out);
+        for a in &self.analyses {
+            let crossing = match (a.exceeds_limit_at, a.observed) {
+                (Some(n), true) => format!("limit {} reached at n = {} (measured)", a.limit, n),
+                (Some(n), false) => format!("limit {} reached at n ≈ {} (fitted)", a.limit, n),
+                (None, _) => format!("limit {} not reached", a.limit),
+            };
+            let _ = writeln!(
+                out,
+                "{:<18} {:<11} R²={:.4}  {}",
+                a.metric.label(),
+                a.fit.model.label(),
+                a.fit.r_squared,
+                crossing
+            );
+        }
+        out
+    }
+}
+
+/// Run `spec` against `wasm` locally, one fresh environment per input size.
+pub fn run_sweep(
+    wasm: &[u8],
+    spec: &SweepSpec,
+    limits: &NetworkLimits,
+) -> Result<SweepReport, SweepError> {
+    spec.validate()?;
+
+    let mut points = Vec::new();
+    for n in spec.range.values() {
+        let args = spec.build_args(n)?;
+        let point = match measure_point(wasm, &spec.function, args) {
+            Ok(p) => SweepPoint { n, ..p },
+            Err(e) => {
+                tracing::warn!(n, error = %e, "Sweep point failed");
+                SweepPoint {
+                    n,
+                    error: Some(e),
+                    ..SweepPoint::default()
+                }
+            }
+        };
+        tracing::debug!(n, cpu = point.cpu_instructions, "Sweep point measured");
+        points.push(point);
+    }
+
+    Ok(SweepReport::analyze(spec, points, limits))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(f: impl Fn(f64) -> f64) -> Vec<(f64, f64)> {
+        (1..=30)
+            .map(|i| (i as f64 * 10.0, f(i as f64 * 10.0)))
+            .collect()
+    }
+
+    fn point(n: u64, cpu: u64) -> SweepPoint {
+        SweepPoint {
+            n,
+            cpu_instructions: cpu,
+            ..SweepPoint::default()
+        }
+    }
+
+    fn spec(args: &[&str]) -> SweepSpec {
+        SweepSpec {
+            function: "bubble_sort".to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            variable: SweepVariable::VecU32,
+            range: "0..30:10".parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_parse_range_with_step() {
+        let range: SweepRange = "0..300:10".parse().unwrap();
+        assert_eq!(
+            range,
+            SweepRange {
+                start: 0,
+                end: 300,
+                step: 10
+            }
+        );
+        assert_eq!(range.values().count(), 31);
+        assert_eq!(range.point_count(), 31);
+    }
+
+    #[test]
+    fn test_parse_range_with_size_suffix() {
+        let range: SweepRange = "0..64KB:4kb".parse().unwrap();
+        assert_eq!(range.end, 65_536);
+        assert_eq!(range.step, 4_096);
+    }
+
+    #[test]
+    fn test_parse_range_rejects_invalid() {
+        assert!("10..0".parse::<SweepRange>().is_err());
+        assert!("0..10:0".parse::<SweepRange>().is_err());
+        assert!("0-10".parse::<SweepRange>().is_err());
+        assert!("0..99999999999999999MB".parse::<SweepRange>().is_err());
+    }
+
+    #[test]
+    fn test_variable_to_sc_val() {
+        assert_eq!(SweepVariable::U32.to_sc_val(7).unwrap(), ScVal::U32(7));
+        match SweepVariable::VecU32.to_sc_val(3).unwrap() {
+            ScVal::Vec(Some(v)) => {
+                assert_eq!(
+                    v.0.to_vec(),
+                    vec![ScVal::U32(2), ScVal::U32(1), ScVal::U32(0)]
+                )
+            }
+            other => panic!("Expected Vec, got {other:?}"),
+        }
+        match SweepVariable::Bytes.to_sc_val(16).unwrap() {
+            ScVal::Bytes(b) => assert_eq!(b.0.len(), 16),
+            other => panic!("Expected Bytes, got {other:?}"),
+        }
+        assert!(SweepVariable::U32.to_sc_val(u64::MAX).is_err());
+    }
+
+    #[test]
+    fn test_rejects_oversized_inputs() {
+        let too_big = MAX_SWEEP_INPUT_SIZE + 1;
+        assert!(SweepVariable::Bytes.to_sc_val(too_big).is_err());
+        assert!(SweepVariable::VecU32.to_sc_val(too_big).is_err());
+
+        let mut spec = spec(&["$n"]);
+        spec.range = "0..4MB:1MB".parse().unwrap();
+        assert!(matches!(spec.validate(), Err(SweepError::InvalidRange(_))));
+    }
+
+    #[test]
+    fn test_rejects_too_many_points() {
+        let mut spec = spec(&["$n"]);
+        spec.variable = SweepVariable::U32;
+        spec.range = "0..4294967295:1".parse().unwrap();
+        assert!(matches!(spec.validate(), Err(SweepError::InvalidRange(_))));
+
+        spec.range = "1..1000".parse().unwrap();
+        assert_eq!(spec.range.point_count(), MAX_SWEEP_POINTS);
+        assert!(spec.validate().is_ok());
+    }
+
+    #[test]
+    fn test_build_args_replaces_placeholder() {
+        let args = spec(&[":key", "$n"]).build_args(4).unwrap();
+        assert_eq!(args.len(), 2);
+        assert!(matches!(args[0], ScVal::Symbol(_)));
+        assert!(matches!(args[1], ScVal::Vec(Some(_))));
+    }
+
+    #[test]
+    fn test_validate_requires_single_placeholder() {
+        assert!(matches!(
+            spec(&[":key"]).validate(),
+            Err(SweepError::InvalidTemplate)
+        ));
+        assert!(matches!(
+            spec(&["$n", "$n"]).validate(),
+            Err(SweepError::InvalidTemplate)
+        ));
+        assert!(spec(&["$n"]).validate().is_ok());
+    }
+
+    #[test]
+    fn test_fit_constant() {
+        let fit = fit_series(&series(|_| 5_000.0)).unwrap();
+        assert_eq!(fit.model, ComplexityModel::Constant);
+        assert_eq!(fit.intercept, 5_000.0);
+    }
+
+    #[test]
+    fn test_fit_linear() {
+        let fit = fit_series(&series(|n| 1_000.0 + 40.0 * n)).unwrap();
+        assert_eq!(fit.model, ComplexityModel::Linear);
+        assert!((fit.slope - 40.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_fit_n_log_n() {
+        let fit = fit_series(&series(|n| 200.0 + 7.0 * n * n.log2())).unwrap();
+        assert_eq!(fit.model, ComplexityModel::NLogN);
+    }
+
+    #[test]
+    fn test_fit_quadratic() {
+        let fit = fit_series(&series(|n| 50.0 + 3.0 * n * n)).unwrap();
+        assert_eq!(fit.model, ComplexityModel::Quadratic);
+        assert!(fit.r_squared > 0.999);
+    }
+
+    #[test]
+    fn test_limit_crossing_extrapolated() {
+        let points: Vec<SweepPoint> = (1..=10).map(|i| point(i * 10, i * 10 * 100)).collect();
+        let samples: Vec<(f64, f64)> = points
+            .iter()
+            .map(|p| (p.n as f64, p.cpu_instructions as f64))
+            .collect();
+        let fit = fit_series(&samples).unwrap();
+        let crossing = limit_crossing(&points, Metric::CpuInstructions, &fit, 999_950);
+        assert_eq!(crossing, Some((10_000, false)));
+    }
+
+    #[test]
+    fn test_limit_crossing_observed() {
+        let points = vec![point(10, 10), point(20, 2_000), point(30, 3_000)];
+        let fit = ModelFit {
+            model: ComplexityModel::Linear,
+            intercept: 0.0,
+            slope: 100.0,
+            r_squared: 1.0,
+        };
+        let crossing = limit_crossing(&points, Metric::CpuInstructions, &fit, 1_500);
+        assert_eq!(crossing, Some((20, true)));
+    }
+
+    #[test]
+    fn test_analyze_skips_failed_and_zero_metrics() {
+        let mut points: Vec<SweepPoint> = (1..=5).map(|i| point(i * 10, i * 1_000 + 50)).collect();
+        points.push(SweepPoint {
+            n: 60,
+            error: Some("list too long".to_string()),
+            ..SweepPoint::default()
+        });
+        let report = SweepReport::analyze(&spec(&["$n"]), points, &NetworkLimits::bundled());
+        assert_eq!(report.points.len(), 6);
+        assert_eq!(report.analyses.len(), 1);
+        assert_eq!(report.analyses[0].metric, Metric::CpuInstructions);
+        assert_eq!(report.analyses[0].fit.model, ComplexityModel::Linear);
+        assert_eq!(report.analyses[0].exceeds_limit_at, Some(1_000_000));
+    }
+}